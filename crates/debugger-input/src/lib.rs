@@ -1,10 +1,23 @@
-use solana_sdk::account::Account as SolAccount;
-use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use solana_sdk::account::{create_account_with_fields, Account as SolAccount, AccountSharedData};
+use solana_sdk::{
+    bpf_loader,
+    clock::Clock,
+    epoch_schedule::EpochSchedule,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::{self, Sysvar},
+};
 use std::{
-    fs::{create_dir_all, File},
+    fs::{self, create_dir_all, File},
     io::Write,
     mem::size_of,
-    path::Path,
+    path::{Path, PathBuf},
+    str::FromStr,
 };
 use thiserror::Error;
 
@@ -16,22 +29,82 @@ pub enum DebuggerInputError {
     SerializationError(String),
     #[error("Missing account data for pubkey {0}")]
     MissingAccount(Pubkey),
+    #[error("Failed to read {0}: {1}")]
+    FileReadError(String, std::io::Error),
+    #[error("Invalid JSON input: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("Invalid pubkey '{0}'")]
+    InvalidPubkey(String),
+    #[error("Invalid {0} data: {1}")]
+    InvalidData(&'static str, String),
+    #[error("Data '{0}' has no 'base64:' or 'hex:' prefix")]
+    MissingDataEncoding(String),
+    #[error("Deserialization error: {0}")]
+    DeserializationError(String),
+    #[error("Account {0} is both writable and executable")]
+    WritableExecutable(Pubkey),
+    #[error("Account {0} is passed both as a signer and as a non-signer")]
+    InconsistentSigner(Pubkey),
+    #[error(
+        "Account {0} is a duplicate of account {1}, but a duplicate can only refer to accounts 0 to 255"
+    )]
+    DuplicateIndexOutOfRange(Pubkey, usize),
+    #[error(
+        "Instruction {0} refers to account index {1}, past the {2} account keys of the message"
+    )]
+    InvalidAccountIndex(usize, u8, usize),
 }
 
 /// Constants for alignment and memory management
 const BPF_ALIGN_OF_U128: usize = 16;
-const MAX_PERMITTED_DATA_INCREASE: usize = 10240; // 10k bytes
+/// Default space reserved after each account's data for realloc growth
+pub const MAX_PERMITTED_DATA_INCREASE: usize = 10240; // 10k bytes
 /// Marker for non-duplicate accounts
 const NON_DUP_MARKER: u8 = 0xff;
+/// Directory the input files are written to by default
+pub const DEFAULT_OUT_DIR: &str = ".dbg";
+
+/// Layout of the serialized parameters, which depends on the loader that owns the program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SerializeLayout {
+    /// Layout of the BPF loader (v2) and the upgradeable loader (v3): fields aligned to 8 bytes,
+    /// account data followed by the realloc padding and aligned to 16 bytes
+    #[default]
+    Aligned,
+    /// Layout of the deprecated BPF loader (v1), whose programs are all sBPF v0: fields packed
+    /// without padding, no realloc padding, and the owner after the account data
+    Unaligned,
+}
+
+/// Options of the serialized parameter format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerializeOptions {
+    pub layout: SerializeLayout,
+    /// Space reserved after each account's data for realloc growth, in the aligned layout only
+    pub realloc_padding: usize,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self {
+            layout: SerializeLayout::Aligned,
+            realloc_padding: MAX_PERMITTED_DATA_INCREASE,
+        }
+    }
+}
 
 /// Simple serializer that just writes bytes to a buffer
 struct Serializer {
     buffer: Vec<u8>,
+    options: SerializeOptions,
 }
 
 impl Serializer {
-    fn new() -> Self {
-        Self { buffer: Vec::new() }
+    fn new(options: SerializeOptions) -> Self {
+        Self {
+            buffer: Vec::new(),
+            options,
+        }
     }
 
     fn write<T>(&mut self, value: T) {
@@ -50,7 +123,7 @@ impl Serializer {
 
         // Add padding for realloc.
         self.buffer
-            .extend(std::iter::repeat(0u8).take(MAX_PERMITTED_DATA_INCREASE));
+            .extend(std::iter::repeat(0u8).take(self.options.realloc_padding));
 
         // Align to BPF_ALIGN_OF_U128.
         let current_len = self.buffer.len();
@@ -66,6 +139,7 @@ impl Serializer {
 }
 
 /// Account
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Account {
     pub key: Pubkey,
     pub owner: Pubkey,
@@ -99,28 +173,138 @@ impl Account {
             rent_epoch,
         }
     }
+
+    /// Create a read-only, non-signer and non-executable account from base58 key and owner.
+    pub fn from_base58_key(
+        key: &str,
+        owner: &str,
+        lamports: u64,
+        data: Vec<u8>,
+    ) -> Result<Self, DebuggerInputError> {
+        Ok(Self::new(
+            parse_pubkey(key)?,
+            parse_pubkey(owner)?,
+            lamports,
+            data,
+            false,
+            false,
+            false,
+            0,
+        ))
+    }
+}
+
+/// SHA-256 hash of the state of an account: its key, owner, lamports, data, executable flag and
+/// rent epoch, in that order (the data prefixed with its length). The signer and writable flags
+/// are not part of the state and are left out, so hashes taken before and after a run tell which
+/// accounts the program mutated.
+pub fn account_data_hash(account: &Account) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(account.key.as_ref());
+    hasher.update(account.owner.as_ref());
+    hasher.update(account.lamports.to_le_bytes());
+    hasher.update((account.data.len() as u64).to_le_bytes());
+    hasher.update(&account.data);
+    hasher.update([account.executable as u8]);
+    hasher.update(account.rent_epoch.to_le_bytes());
+    hasher.finalize().into()
 }
 
 /// Account for serialization
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SerializeAccount {
     Account(usize, Account),
     Duplicate(u8),
 }
 
+/// Reader over a serialized parameter buffer, the counterpart of `Serializer`
+struct Deserializer<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+    options: SerializeOptions,
+}
+
+impl<'a> Deserializer<'a> {
+    fn new(buffer: &'a [u8], options: SerializeOptions) -> Self {
+        Self {
+            buffer,
+            offset: 0,
+            options,
+        }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DebuggerInputError> {
+        let bytes = self
+            .offset
+            .checked_add(len)
+            .and_then(|end| self.buffer.get(self.offset..end))
+            .ok_or_else(|| {
+                DebuggerInputError::DeserializationError(format!(
+                    "Input truncated at offset {} (reading {} bytes)",
+                    self.offset, len
+                ))
+            })?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DebuggerInputError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u64(&mut self) -> Result<u64, DebuggerInputError> {
+        Ok(u64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+
+    fn read_pubkey(&mut self) -> Result<Pubkey, DebuggerInputError> {
+        Ok(Pubkey::new_from_array(
+            self.read_bytes(32)?.try_into().unwrap(),
+        ))
+    }
+
+    fn read_account_data(&mut self, len: usize) -> Result<Vec<u8>, DebuggerInputError> {
+        let data = self.read_bytes(len)?.to_vec();
+
+        // Skip the realloc padding and the alignment to BPF_ALIGN_OF_U128.
+        let realloc_padding = self.options.realloc_padding;
+        let padded_end = self.offset + realloc_padding;
+        let alignment_needed =
+            (BPF_ALIGN_OF_U128 - (padded_end % BPF_ALIGN_OF_U128)) % BPF_ALIGN_OF_U128;
+        self.read_bytes(realloc_padding + alignment_needed)?;
+
+        Ok(data)
+    }
+}
+
 /// Serialize parameters into the expected format.
 pub fn serialize_parameters(
     accounts: Vec<SerializeAccount>,
     instruction_data: &[u8],
     program_id: &Pubkey,
 ) -> Result<Vec<u8>, DebuggerInputError> {
-    let mut s = Serializer::new();
+    serialize_parameters_with_options(
+        accounts,
+        instruction_data,
+        program_id,
+        SerializeOptions::default(),
+    )
+}
+
+/// Like `serialize_parameters`, with the format configured by `options`.
+pub fn serialize_parameters_with_options(
+    accounts: Vec<SerializeAccount>,
+    instruction_data: &[u8],
+    program_id: &Pubkey,
+    options: SerializeOptions,
+) -> Result<Vec<u8>, DebuggerInputError> {
+    let mut s = Serializer::new(options);
 
     // Serialize into the buffer
     s.write::<u64>((accounts.len() as u64).to_le());
 
     for account in accounts {
-        match account {
-            SerializeAccount::Account(_, account) => {
+        match (account, options.layout) {
+            (SerializeAccount::Account(_, account), SerializeLayout::Aligned) => {
                 s.write::<u8>(NON_DUP_MARKER);
                 s.write::<u8>(account.is_signer as u8);
                 s.write::<u8>(account.is_writable as u8);
@@ -133,10 +317,25 @@ pub fn serialize_parameters(
                 s.write_account_data(&account.data);
                 s.write::<u64>(account.rent_epoch.to_le());
             }
-            SerializeAccount::Duplicate(position) => {
+            (SerializeAccount::Account(_, account), SerializeLayout::Unaligned) => {
+                s.write::<u8>(NON_DUP_MARKER);
+                s.write::<u8>(account.is_signer as u8);
+                s.write::<u8>(account.is_writable as u8);
+                s.write_all(account.key.as_ref());
+                s.write::<u64>(account.lamports.to_le());
+                s.write::<u64>((account.data.len() as u64).to_le());
+                s.write_all(&account.data);
+                s.write_all(account.owner.as_ref());
+                s.write::<u8>(account.executable as u8);
+                s.write::<u64>(account.rent_epoch.to_le());
+            }
+            (SerializeAccount::Duplicate(position), SerializeLayout::Aligned) => {
                 s.write::<u8>(position as u8);
                 s.write_all(&[0u8, 0, 0, 0, 0, 0, 0]); // 7 bytes padding
             }
+            (SerializeAccount::Duplicate(position), SerializeLayout::Unaligned) => {
+                s.write::<u8>(position as u8);
+            }
         };
     }
 
@@ -147,18 +346,144 @@ pub fn serialize_parameters(
     Ok(s.finish())
 }
 
-/// Generate debugger input from a Solana instruction and write to file.
-pub fn generate(
+/// Deserialize parameters serialized by `serialize_parameters` into the accounts, the
+/// instruction data and the program id.
+///
+/// Duplicate accounts are returned as `SerializeAccount::Duplicate` with the index of the
+/// original account.
+pub fn deserialize_parameters(
+    bytes: &[u8],
+) -> Result<(Vec<SerializeAccount>, Vec<u8>, Pubkey), DebuggerInputError> {
+    deserialize_parameters_with_options(bytes, SerializeOptions::default())
+}
+
+/// Like `deserialize_parameters`, for parameters serialized with `options`.
+pub fn deserialize_parameters_with_options(
+    bytes: &[u8],
+    options: SerializeOptions,
+) -> Result<(Vec<SerializeAccount>, Vec<u8>, Pubkey), DebuggerInputError> {
+    let mut d = Deserializer::new(bytes, options);
+
+    let num_accounts = d.read_u64()?;
+    let mut accounts = Vec::new();
+    for index in 0..num_accounts as usize {
+        let marker = d.read_u8()?;
+        if marker != NON_DUP_MARKER {
+            if marker as usize >= index {
+                return Err(DebuggerInputError::DeserializationError(format!(
+                    "Account {} is a duplicate of account {}, which does not precede it",
+                    index, marker
+                )));
+            }
+            if options.layout == SerializeLayout::Aligned {
+                d.read_bytes(7)?; // 7 bytes padding
+            }
+            accounts.push(SerializeAccount::Duplicate(marker));
+            continue;
+        }
+
+        let is_signer = d.read_u8()? != 0;
+        let is_writable = d.read_u8()? != 0;
+        let (key, owner, lamports, data, executable) = match options.layout {
+            SerializeLayout::Aligned => {
+                let executable = d.read_u8()? != 0;
+                d.read_bytes(4)?; // 4 bytes padding
+                let key = d.read_pubkey()?;
+                let owner = d.read_pubkey()?;
+                let lamports = d.read_u64()?;
+                let data_len = d.read_u64()? as usize;
+                let data = d.read_account_data(data_len)?;
+                (key, owner, lamports, data, executable)
+            }
+            SerializeLayout::Unaligned => {
+                let key = d.read_pubkey()?;
+                let lamports = d.read_u64()?;
+                let data_len = d.read_u64()? as usize;
+                let data = d.read_bytes(data_len)?.to_vec();
+                let owner = d.read_pubkey()?;
+                let executable = d.read_u8()? != 0;
+                (key, owner, lamports, data, executable)
+            }
+        };
+        let rent_epoch = d.read_u64()?;
+        accounts.push(SerializeAccount::Account(
+            index,
+            Account::new(
+                key,
+                owner,
+                lamports,
+                data,
+                is_signer,
+                is_writable,
+                executable,
+                rent_epoch,
+            ),
+        ));
+    }
+
+    let data_len = d.read_u64()? as usize;
+    let instruction_data = d.read_bytes(data_len)?.to_vec();
+    let program_id = d.read_pubkey()?;
+    if d.offset != bytes.len() {
+        return Err(DebuggerInputError::DeserializationError(format!(
+            "{} trailing bytes after the program id",
+            bytes.len() - d.offset
+        )));
+    }
+
+    Ok((accounts, instruction_data, program_id))
+}
+
+/// Default account of a well-known sysvar (Clock, Rent or EpochSchedule), holding the default
+/// value of the sysvar.
+pub fn sysvar_account(pubkey: &Pubkey) -> Option<SolAccount> {
+    fn account<S: Sysvar>(sysvar: &S) -> SolAccount {
+        let lamports = Rent::default().minimum_balance(S::size_of());
+        create_account_with_fields(sysvar, (lamports, 0))
+    }
+
+    if *pubkey == sysvar::clock::id() {
+        Some(account(&Clock::default()))
+    } else if *pubkey == sysvar::rent::id() {
+        Some(account(&Rent::default()))
+    } else if *pubkey == sysvar::epoch_schedule::id() {
+        Some(account(&EpochSchedule::default()))
+    } else {
+        None
+    }
+}
+
+/// Executable account of the program being invoked, owned by the BPF loader.
+pub fn program_account() -> SolAccount {
+    SolAccount {
+        lamports: 1,
+        data: vec![],
+        owner: bpf_loader::id(),
+        executable: true,
+        rent_epoch: 0,
+    }
+}
+
+/// Convert the account metas of an instruction to accounts for serialization, detecting
+/// duplicates and looking up the provided account data by pubkey.
+fn serialize_accounts(
     instruction: &Instruction,
     accounts: &[(Pubkey, SolAccount)],
-    output_name: &str,
-) -> Result<(), DebuggerInputError> {
-    // Convert AccountMeta to SerializeAccount with duplicate detection.
+) -> Result<Vec<SerializeAccount>, DebuggerInputError> {
     let mut serialized_accounts = Vec::new();
     let mut seen_pubkeys = std::collections::HashMap::new();
     let by_pubkey: std::collections::HashMap<Pubkey, &SolAccount> =
         accounts.iter().map(|(k, v)| (*k, v)).collect();
 
+    // Like the runtime, combine the signer/writable flags of all metas of an account.
+    let mut merged_flags: std::collections::HashMap<Pubkey, (bool, bool)> =
+        std::collections::HashMap::new();
+    for account_meta in &instruction.accounts {
+        let flags = merged_flags.entry(account_meta.pubkey).or_default();
+        flags.0 |= account_meta.is_signer;
+        flags.1 |= account_meta.is_writable;
+    }
+
     for (i, account_meta) in instruction.accounts.iter().enumerate() {
         if let Some(&first_index) = seen_pubkeys.get(&account_meta.pubkey) {
             // This is a duplicate account.
@@ -167,18 +492,29 @@ pub fn generate(
             // This is the first occurrence of this account.
             seen_pubkeys.insert(account_meta.pubkey.clone(), i);
 
-            // Find provided account data by pubkey.
-            let provided = by_pubkey
-                .get(&account_meta.pubkey)
-                .ok_or(DebuggerInputError::MissingAccount(account_meta.pubkey))?;
+            // Find provided account data by pubkey, falling back to well-known sysvars and the
+            // program account.
+            let default_account;
+            let provided = match by_pubkey.get(&account_meta.pubkey) {
+                Some(provided) => *provided,
+                None => {
+                    default_account = sysvar_account(&account_meta.pubkey)
+                        .or_else(|| {
+                            (account_meta.pubkey == instruction.program_id).then(program_account)
+                        })
+                        .ok_or(DebuggerInputError::MissingAccount(account_meta.pubkey))?;
+                    &default_account
+                }
+            };
 
+            let (is_signer, is_writable) = merged_flags[&account_meta.pubkey];
             let account = Account::new(
                 account_meta.pubkey,
                 provided.owner,
                 provided.lamports,
                 provided.data.clone(),
-                account_meta.is_signer,
-                account_meta.is_writable,
+                is_signer,
+                is_writable,
                 provided.executable,
                 provided.rent_epoch,
             );
@@ -186,40 +522,490 @@ pub fn generate(
         }
     }
 
+    Ok(serialized_accounts)
+}
+
+/// Check invariants the runtime relies on, which `serialize_parameters` does not enforce: no
+/// account is both writable and executable, the metas of an account agree on whether it signs
+/// and duplicates refer to an account within the range of the duplicate marker.
+pub fn validate_accounts(
+    instruction: &Instruction,
+    accounts: &[SerializeAccount],
+) -> Result<(), DebuggerInputError> {
+    let mut signers = std::collections::HashMap::new();
+    for account_meta in &instruction.accounts {
+        let is_signer = *signers
+            .entry(account_meta.pubkey)
+            .or_insert(account_meta.is_signer);
+        if is_signer != account_meta.is_signer {
+            return Err(DebuggerInputError::InconsistentSigner(account_meta.pubkey));
+        }
+    }
+
+    let mut first_indices = std::collections::HashMap::new();
+    for (account_meta, account) in instruction.accounts.iter().zip(accounts) {
+        let first_index = *first_indices
+            .entry(account_meta.pubkey)
+            .or_insert(match account {
+                SerializeAccount::Account(index, _) => *index,
+                SerializeAccount::Duplicate(index) => *index as usize,
+            });
+        match account {
+            SerializeAccount::Account(_, account) if account.is_writable && account.executable => {
+                return Err(DebuggerInputError::WritableExecutable(account.key));
+            }
+            SerializeAccount::Duplicate(_) if first_index > u8::MAX as usize => {
+                return Err(DebuggerInputError::DuplicateIndexOutOfRange(
+                    account_meta.pubkey,
+                    first_index,
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Options of `generate_with_options`
+#[derive(Debug, Clone)]
+pub struct GenerateOptions<'a> {
+    pub format: OutputFormat,
+    /// Directory the file is written to, `.dbg` if `None`
+    pub out_dir: Option<&'a Path>,
+    /// Check the accounts with `validate_accounts` before serializing them
+    pub strict: bool,
+    pub serialize: SerializeOptions,
+}
+
+impl Default for GenerateOptions<'_> {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Hex,
+            out_dir: None,
+            strict: false,
+            serialize: SerializeOptions::default(),
+        }
+    }
+}
+
+/// Format of a generated input file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Hex-encoded bytes in a `.hex` file
+    Hex,
+    /// Raw serialized bytes in a `.bin` file
+    Binary,
+}
+
+impl OutputFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Hex => ".hex",
+            OutputFormat::Binary => ".bin",
+        }
+    }
+}
+
+/// Generate debugger input from a Solana instruction and write to file in the `.dbg` directory.
+/// Returns the path of the written file.
+pub fn generate(
+    instruction: &Instruction,
+    accounts: &[(Pubkey, SolAccount)],
+    output_name: &str,
+) -> Result<PathBuf, DebuggerInputError> {
+    generate_with_format(instruction, accounts, output_name, OutputFormat::Hex, None)
+}
+
+/// Like `generate`, with the accounts given as `AccountSharedData`.
+pub fn generate_from_shared(
+    instruction: &Instruction,
+    accounts: &[(Pubkey, AccountSharedData)],
+    output_name: &str,
+) -> Result<PathBuf, DebuggerInputError> {
+    let accounts: Vec<(Pubkey, SolAccount)> = accounts
+        .iter()
+        .map(|(pubkey, account)| (*pubkey, account.clone().into()))
+        .collect();
+    generate(instruction, &accounts, output_name)
+}
+
+/// Like `generate`, but writes the raw serialized bytes to a `.bin` file.
+pub fn generate_bin(
+    instruction: &Instruction,
+    accounts: &[(Pubkey, SolAccount)],
+    output_name: &str,
+) -> Result<PathBuf, DebuggerInputError> {
+    generate_with_format(
+        instruction,
+        accounts,
+        output_name,
+        OutputFormat::Binary,
+        None,
+    )
+}
+
+/// Generate debugger input from a Solana instruction and write it to file in `format`.
+///
+/// The file is written to `out_dir`, which is created if needed, or to the `.dbg` directory if
+/// `None`. Returns the path of the written file.
+pub fn generate_with_format(
+    instruction: &Instruction,
+    accounts: &[(Pubkey, SolAccount)],
+    output_name: &str,
+    format: OutputFormat,
+    out_dir: Option<&Path>,
+) -> Result<PathBuf, DebuggerInputError> {
+    generate_with_options(
+        instruction,
+        accounts,
+        output_name,
+        &GenerateOptions {
+            format,
+            out_dir,
+            ..GenerateOptions::default()
+        },
+    )
+}
+
+/// Generate debugger input from a Solana instruction and write it to file as configured by
+/// `options`. In strict mode, malformed accounts are reported (see `validate_accounts`) instead
+/// of being serialized. Returns the path of the written file.
+pub fn generate_with_options(
+    instruction: &Instruction,
+    accounts: &[(Pubkey, SolAccount)],
+    output_name: &str,
+    options: &GenerateOptions,
+) -> Result<PathBuf, DebuggerInputError> {
+    let GenerateOptions {
+        format,
+        out_dir,
+        strict,
+        serialize,
+    } = *options;
+
+    // Convert AccountMeta to SerializeAccount with duplicate detection.
+    let serialized_accounts = serialize_accounts(instruction, accounts)?;
+    if strict {
+        validate_accounts(instruction, &serialized_accounts)?;
+    }
+
     // Serialize the parameters.
-    let serialized_data = serialize_parameters(
+    let serialized_data = serialize_parameters_with_options(
         serialized_accounts,
         &instruction.data,
         &instruction.program_id,
+        serialize,
     )?;
 
-    // Ensure .dbg directory exists and create output file inside it.
-    let out_dir = Path::new(".dbg");
+    // Ensure the output directory exists and create output file inside it.
+    let out_dir = out_dir.unwrap_or(Path::new(DEFAULT_OUT_DIR));
     create_dir_all(out_dir)?;
-    // Append .hex if not provided by the user.
+    // Append the format's extension if not provided by the user.
     let output_name = if Path::new(output_name).extension().is_none() {
-        format!("{}{}", output_name, ".hex")
+        format!("{}{}", output_name, format.extension())
     } else {
         output_name.to_string()
     };
     let output_path = out_dir.join(output_name);
-    // Write hex to file.
-    let mut file = File::create(output_path)?;
-    for byte in &serialized_data {
-        write!(file, "{:02x}", byte)?;
+    let mut file = File::create(&output_path)?;
+    match format {
+        OutputFormat::Hex => {
+            for byte in &serialized_data {
+                write!(file, "{:02x}", byte)?;
+            }
+            writeln!(file)?;
+        }
+        OutputFormat::Binary => file.write_all(&serialized_data)?,
     }
-    writeln!(file)?;
 
-    Ok(())
+    Ok(output_path)
+}
+
+/// Hashes (see `account_data_hash`) of the accounts of an instruction, by pubkey, in
+/// instruction order and without duplicates.
+pub fn account_hashes(
+    instruction: &Instruction,
+    accounts: &[(Pubkey, SolAccount)],
+) -> Result<Vec<(Pubkey, [u8; 32])>, DebuggerInputError> {
+    Ok(serialize_accounts(instruction, accounts)?
+        .iter()
+        .filter_map(|account| match account {
+            SerializeAccount::Account(_, account) => {
+                Some((account.key, account_data_hash(account)))
+            }
+            SerializeAccount::Duplicate(_) => None,
+        })
+        .collect())
+}
+
+/// Like `generate_with_format`, and also write the account hashes (see `account_hashes`) to a
+/// sidecar JSON file next to the input file, named after it with a `.hashes.json` extension.
+/// Returns the paths of the input file and of the sidecar file.
+pub fn generate_with_hashes(
+    instruction: &Instruction,
+    accounts: &[(Pubkey, SolAccount)],
+    output_name: &str,
+    format: OutputFormat,
+    out_dir: Option<&Path>,
+) -> Result<(PathBuf, PathBuf), DebuggerInputError> {
+    let output_path = generate_with_format(instruction, accounts, output_name, format, out_dir)?;
+
+    let hashes: Vec<serde_json::Value> = account_hashes(instruction, accounts)?
+        .iter()
+        .map(|(pubkey, hash)| {
+            let hash: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+            serde_json::json!({ "pubkey": pubkey.to_string(), "hash": hash })
+        })
+        .collect();
+    let hashes_path = output_path.with_extension("hashes.json");
+    fs::write(
+        &hashes_path,
+        serde_json::to_string_pretty(&serde_json::json!({ "accounts": hashes }))?,
+    )?;
+
+    Ok((output_path, hashes_path))
+}
+
+/// Bytes in a JSON input file, encoded as `"hex:..."` or `"base64:..."` like
+/// [`parse_account_data`] takes them, or as `{"hex": "..."}` or `{"base64": "..."}`
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum JsonData {
+    Prefixed(String),
+    Encoded(JsonEncodedData),
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+enum JsonEncodedData {
+    Hex(String),
+    Base64(String),
+}
+
+/// Decode hex digits, with or without a `0x` prefix.
+fn decode_hex(hex: &str) -> Result<Vec<u8>, DebuggerInputError> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if !hex.is_ascii() || hex.len() % 2 != 0 {
+        return Err(DebuggerInputError::InvalidData(
+            "hex",
+            "expected an even number of hex digits".to_string(),
+        ));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|e| DebuggerInputError::InvalidData("hex", e.to_string()))
+        })
+        .collect()
+}
+
+fn decode_base64(base64: &str) -> Result<Vec<u8>, DebuggerInputError> {
+    BASE64
+        .decode(base64)
+        .map_err(|e| DebuggerInputError::InvalidData("base64", e.to_string()))
+}
+
+/// Parse account data given as `base64:<data>` or `hex:<data>`.
+pub fn parse_account_data(s: &str) -> Result<Vec<u8>, DebuggerInputError> {
+    if let Some(base64) = s.strip_prefix("base64:") {
+        decode_base64(base64)
+    } else if let Some(hex) = s.strip_prefix("hex:") {
+        decode_hex(hex)
+    } else {
+        Err(DebuggerInputError::MissingDataEncoding(s.to_string()))
+    }
+}
+
+impl JsonData {
+    fn decode(&self) -> Result<Vec<u8>, DebuggerInputError> {
+        match self {
+            JsonData::Prefixed(data) => parse_account_data(data),
+            JsonData::Encoded(JsonEncodedData::Hex(hex)) => decode_hex(hex),
+            JsonData::Encoded(JsonEncodedData::Base64(base64)) => decode_base64(base64),
+        }
+    }
+}
+
+impl Default for JsonData {
+    fn default() -> Self {
+        JsonData::Encoded(JsonEncodedData::Hex(String::new()))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct JsonAccountMeta {
+    pubkey: String,
+    #[serde(default)]
+    is_signer: bool,
+    #[serde(default)]
+    is_writable: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct JsonInstruction {
+    program_id: String,
+    #[serde(default)]
+    data: JsonData,
+    accounts: Vec<JsonAccountMeta>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct JsonAccount {
+    pubkey: String,
+    owner: String,
+    lamports: u64,
+    #[serde(default)]
+    data: JsonData,
+    #[serde(default)]
+    executable: bool,
+    #[serde(default)]
+    rent_epoch: u64,
+}
+
+/// Instruction and accounts of a JSON input file
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct JsonInput {
+    instruction: JsonInstruction,
+    accounts: Vec<JsonAccount>,
+}
+
+fn parse_pubkey(pubkey: &str) -> Result<Pubkey, DebuggerInputError> {
+    Pubkey::from_str(pubkey).map_err(|_| DebuggerInputError::InvalidPubkey(pubkey.to_string()))
+}
+
+/// Parse the instruction and accounts described by a JSON input file.
+fn parse_json_input(
+    json: &str,
+) -> Result<(Instruction, Vec<(Pubkey, SolAccount)>), DebuggerInputError> {
+    let input: JsonInput = serde_json::from_str(json)?;
+
+    let metas = input
+        .instruction
+        .accounts
+        .iter()
+        .map(|meta| {
+            Ok(AccountMeta {
+                pubkey: parse_pubkey(&meta.pubkey)?,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+        })
+        .collect::<Result<Vec<_>, DebuggerInputError>>()?;
+    let instruction = Instruction::new_with_bytes(
+        parse_pubkey(&input.instruction.program_id)?,
+        &input.instruction.data.decode()?,
+        metas,
+    );
+
+    let accounts = input
+        .accounts
+        .iter()
+        .map(|account| {
+            Ok((
+                parse_pubkey(&account.pubkey)?,
+                SolAccount {
+                    lamports: account.lamports,
+                    data: account.data.decode()?,
+                    owner: parse_pubkey(&account.owner)?,
+                    executable: account.executable,
+                    rent_epoch: account.rent_epoch,
+                },
+            ))
+        })
+        .collect::<Result<Vec<_>, DebuggerInputError>>()?;
+
+    Ok((instruction, accounts))
+}
+
+/// Generate debugger input from a JSON file describing the instruction and its accounts and
+/// write it to file (see the crate README for the format).
+pub fn generate_from_json(path: &Path, output_name: &str) -> Result<PathBuf, DebuggerInputError> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| DebuggerInputError::FileReadError(path.display().to_string(), e))?;
+    let (instruction, accounts) = parse_json_input(&json)?;
+    generate(&instruction, &accounts, output_name)
+}
+
+/// Whether the account at `index` of a message is writable, from the message header: signed
+/// accounts come first and unsigned accounts last, each with their read-only accounts at the end.
+fn is_writable_in_message(message: &Message, index: usize) -> bool {
+    let header = &message.header;
+    let num_signers = header.num_required_signatures as usize;
+    if index < num_signers {
+        index < num_signers - header.num_readonly_signed_accounts as usize
+    } else {
+        index < message.account_keys.len() - header.num_readonly_unsigned_accounts as usize
+    }
+}
+
+/// Resolve the instructions of a message against its account keys. The signer and writable
+/// flags of each account come from the message header, like in the runtime.
+pub fn message_instructions(message: &Message) -> Result<Vec<Instruction>, DebuggerInputError> {
+    let keys = &message.account_keys;
+    let key = |instruction_index: usize, index: u8| {
+        keys.get(index as usize)
+            .copied()
+            .ok_or(DebuggerInputError::InvalidAccountIndex(
+                instruction_index,
+                index,
+                keys.len(),
+            ))
+    };
+
+    message
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(instruction_index, compiled)| {
+            let accounts = compiled
+                .accounts
+                .iter()
+                .map(|&index| {
+                    Ok(AccountMeta {
+                        pubkey: key(instruction_index, index)?,
+                        is_signer: message.is_signer(index as usize),
+                        is_writable: is_writable_in_message(message, index as usize),
+                    })
+                })
+                .collect::<Result<Vec<_>, DebuggerInputError>>()?;
+            Ok(Instruction {
+                program_id: key(instruction_index, compiled.program_id_index)?,
+                accounts,
+                data: compiled.data.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Generate one input file per instruction of a transaction message, named
+/// `<out_prefix>_<instruction index>`, in the `.dbg` directory. Returns the paths of the
+/// written files, in instruction order.
+pub fn generate_from_message(
+    message: &Message,
+    accounts: &[(Pubkey, SolAccount)],
+    out_prefix: &str,
+) -> Result<Vec<PathBuf>, DebuggerInputError> {
+    message_instructions(message)?
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| {
+            generate(instruction, accounts, &format!("{}_{}", out_prefix, index))
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+    use solana_sdk::{account::ReadableAccount, pubkey::Pubkey};
 
-    #[test]
-    fn test_serialize_parameters() {
+    /// An instruction with three distinct accounts and the data of those accounts.
+    fn fixture() -> (Instruction, Vec<(Pubkey, SolAccount)>) {
         let program_id = Pubkey::new_unique();
         let owner_pubkey = Pubkey::new_unique();
         let vault_pda = Pubkey::new_unique();
@@ -267,13 +1053,11 @@ mod tests {
                 },
             ),
         ];
-
-        let result = generate(&instruction, &accounts, "test_output.hex");
-        assert!(result.is_ok());
+        (instruction, accounts)
     }
 
-    #[test]
-    fn test_serialize_parameters_with_duplicates() {
+    /// An instruction that passes each of its two accounts twice.
+    fn fixture_with_duplicates() -> (Instruction, Vec<(Pubkey, SolAccount)>) {
         let program_id = Pubkey::new_unique();
         let owner_pubkey = Pubkey::new_unique();
         let vault_pda = Pubkey::new_unique();
@@ -311,8 +1095,572 @@ mod tests {
                 },
             ),
         ];
+        (instruction, accounts)
+    }
 
+    #[test]
+    fn test_serialize_parameters() {
+        let (instruction, accounts) = fixture();
+        let result = generate(&instruction, &accounts, "test_output.hex");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_serialize_parameters_with_duplicates() {
+        let (instruction, accounts) = fixture_with_duplicates();
         let result = generate(&instruction, &accounts, "test_duplicates.hex");
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_generate_bin() {
+        let (instruction, accounts) = fixture_with_duplicates();
+        let path = generate_bin(&instruction, &accounts, "test_output_bin").unwrap();
+
+        let expected = serialize_parameters(
+            serialize_accounts(&instruction, &accounts).unwrap(),
+            &instruction.data,
+            &instruction.program_id,
+        )
+        .unwrap();
+        assert_eq!(path, Path::new(".dbg/test_output_bin.bin"));
+        assert_eq!(fs::read(path).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_generate_from_shared() {
+        let (instruction, accounts) = fixture();
+        let shared: Vec<(Pubkey, AccountSharedData)> = accounts
+            .iter()
+            .map(|(pubkey, account)| (*pubkey, account.clone().into()))
+            .collect();
+        assert_eq!(shared[0].1.data(), &[1, 2, 3]);
+
+        let path = generate(&instruction, &accounts, "test_sol_account").unwrap();
+        let shared_path = generate_from_shared(&instruction, &shared, "test_shared").unwrap();
+        assert_eq!(fs::read(path).unwrap(), fs::read(shared_path).unwrap());
+    }
+
+    #[test]
+    fn test_generate_out_dir() {
+        let (instruction, accounts) = fixture();
+        let out_dir = std::env::temp_dir()
+            .join(format!("sbpf-dbg-input-{}", instruction.program_id))
+            .join("nested");
+
+        let path = generate_with_format(
+            &instruction,
+            &accounts,
+            "input",
+            OutputFormat::Hex,
+            Some(&out_dir),
+        )
+        .unwrap();
+        assert_eq!(path, out_dir.join("input.hex"));
+        assert!(path.exists());
+        fs::remove_dir_all(out_dir.parent().unwrap()).unwrap();
+
+        // Without an explicit directory the file goes to .dbg.
+        let path = generate(&instruction, &accounts, "test_default_dir").unwrap();
+        assert_eq!(
+            path,
+            Path::new(DEFAULT_OUT_DIR).join("test_default_dir.hex")
+        );
+    }
+
+    #[test]
+    fn test_duplicate_account_flags_are_merged() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![
+                AccountMeta::new_readonly(key, false),
+                AccountMeta::new(key, false),
+                AccountMeta::new_readonly(key, true),
+            ],
+        );
+        let accounts = vec![(key, SolAccount::new(1, 0, &program_id))];
+
+        let bytes = serialize_parameters(
+            serialize_accounts(&instruction, &accounts).unwrap(),
+            &instruction.data,
+            &instruction.program_id,
+        )
+        .unwrap();
+        let (deserialized, _, _) = deserialize_parameters(&bytes).unwrap();
+        match &deserialized[0] {
+            SerializeAccount::Account(0, account) => {
+                assert!(account.is_writable);
+                assert!(account.is_signer);
+            }
+            _ => panic!("Expected the first occurrence to be serialized"),
+        }
+        assert_eq!(deserialized[1], SerializeAccount::Duplicate(0));
+        assert_eq!(deserialized[2], SerializeAccount::Duplicate(0));
+    }
+
+    #[test]
+    fn test_default_sysvar_and_program_accounts() {
+        let program_id = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(program_id, false),
+            ],
+        );
+
+        let bytes = serialize_parameters(
+            serialize_accounts(&instruction, &[]).unwrap(),
+            &instruction.data,
+            &instruction.program_id,
+        )
+        .unwrap();
+        let (deserialized, _, _) = deserialize_parameters(&bytes).unwrap();
+        match &deserialized[0] {
+            SerializeAccount::Account(_, clock) => {
+                assert_eq!(clock.key, sysvar::clock::id());
+                assert_eq!(clock.owner, sysvar::id());
+                assert_eq!(clock.data.len(), Clock::size_of());
+                assert!(clock.lamports > 0);
+            }
+            _ => panic!("Expected the Clock sysvar account"),
+        }
+        match &deserialized[1] {
+            SerializeAccount::Account(_, program) => {
+                assert!(program.executable);
+                assert_eq!(program.owner, bpf_loader::id());
+            }
+            _ => panic!("Expected the program account"),
+        }
+
+        // Other accounts must still be provided.
+        let missing = Pubkey::new_unique();
+        let instruction =
+            Instruction::new_with_bytes(program_id, &[], vec![AccountMeta::new(missing, false)]);
+        assert!(matches!(
+            serialize_accounts(&instruction, &[]),
+            Err(DebuggerInputError::MissingAccount(key)) if key == missing
+        ));
+    }
+
+    #[test]
+    fn test_deserialize_parameters_round_trip() {
+        for (instruction, accounts) in [fixture(), fixture_with_duplicates()] {
+            let serialized_accounts = serialize_accounts(&instruction, &accounts).unwrap();
+            let bytes = serialize_parameters(
+                serialized_accounts.clone(),
+                &instruction.data,
+                &instruction.program_id,
+            )
+            .unwrap();
+
+            let deserialized = deserialize_parameters(&bytes).unwrap();
+            assert_eq!(
+                deserialized,
+                (
+                    serialized_accounts,
+                    instruction.data.clone(),
+                    instruction.program_id
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_zero_realloc_padding() {
+        let (instruction, accounts) = fixture();
+        let serialized_accounts = serialize_accounts(&instruction, &accounts).unwrap();
+        let options = SerializeOptions {
+            realloc_padding: 0,
+            ..SerializeOptions::default()
+        };
+        let bytes = serialize_parameters_with_options(
+            serialized_accounts.clone(),
+            &instruction.data,
+            &instruction.program_id,
+            options,
+        )
+        .unwrap();
+        let padded = serialize_parameters(
+            serialized_accounts.clone(),
+            &instruction.data,
+            &instruction.program_id,
+        )
+        .unwrap();
+        assert_eq!(
+            padded.len() - bytes.len(),
+            accounts.len() * MAX_PERMITTED_DATA_INCREASE
+        );
+
+        // The first account's data (3 bytes) is only followed by the alignment to 16 bytes.
+        let data_start = 8 + 8 + 32 + 32 + 8 + 8;
+        let rent_epoch_start = (data_start + 3).next_multiple_of(BPF_ALIGN_OF_U128);
+        assert_eq!(&bytes[data_start..data_start + 3], &[1, 2, 3]);
+        assert!(bytes[data_start + 3..rent_epoch_start + 8]
+            .iter()
+            .all(|&b| b == 0));
+
+        assert_eq!(
+            deserialize_parameters_with_options(&bytes, options).unwrap(),
+            (
+                serialized_accounts,
+                instruction.data.clone(),
+                instruction.program_id
+            )
+        );
+        assert!(deserialize_parameters(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_serialize_layouts() {
+        let (instruction, accounts) = fixture_with_duplicates();
+        let serialized_accounts = serialize_accounts(&instruction, &accounts).unwrap();
+        let serialize = |layout| {
+            let options = SerializeOptions {
+                layout,
+                ..SerializeOptions::default()
+            };
+            let bytes = serialize_parameters_with_options(
+                serialized_accounts.clone(),
+                &instruction.data,
+                &instruction.program_id,
+                options,
+            )
+            .unwrap();
+            assert_eq!(
+                deserialize_parameters_with_options(&bytes, options).unwrap(),
+                (
+                    serialized_accounts.clone(),
+                    instruction.data.clone(),
+                    instruction.program_id
+                )
+            );
+            bytes
+        };
+
+        let aligned = serialize(SerializeLayout::Aligned);
+        assert_eq!(aligned.len() % 8, 0);
+
+        let unaligned = serialize(SerializeLayout::Unaligned);
+        // Two accounts with two bytes of data each and two 1-byte duplicates.
+        let account_len = 1 + 1 + 1 + 32 + 8 + 8 + 2 + 32 + 1 + 8;
+        assert_eq!(unaligned.len(), 8 + 2 * account_len + 2 + 8 + 4 + 32);
+        // The key follows the flags directly, and the owner follows the data.
+        let (key, owner) = (&accounts[0].0, &accounts[0].1.owner);
+        assert_eq!(&unaligned[11..43], key.as_ref());
+        assert_eq!(&unaligned[59..61], &[9, 9]);
+        assert_eq!(&unaligned[61..93], owner.as_ref());
+    }
+
+    #[test]
+    fn test_deserialize_parameters_truncated() {
+        let (instruction, accounts) = fixture();
+        let bytes = serialize_parameters(
+            serialize_accounts(&instruction, &accounts).unwrap(),
+            &instruction.data,
+            &instruction.program_id,
+        )
+        .unwrap();
+
+        for len in [0, 7, 100, bytes.len() - 1] {
+            assert!(matches!(
+                deserialize_parameters(&bytes[..len]),
+                Err(DebuggerInputError::DeserializationError(_))
+            ));
+        }
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(deserialize_parameters(&extended).is_err());
+    }
+
+    #[test]
+    fn test_validate_accounts() {
+        let validate = |instruction: &Instruction, accounts: &[(Pubkey, SolAccount)]| {
+            validate_accounts(
+                instruction,
+                &serialize_accounts(instruction, accounts).unwrap(),
+            )
+        };
+        let (instruction, accounts) = fixture_with_duplicates();
+        assert!(validate(&instruction, &accounts).is_ok());
+
+        // The program account is executable and cannot be writable.
+        let program_id = Pubkey::new_unique();
+        let instruction =
+            Instruction::new_with_bytes(program_id, &[], vec![AccountMeta::new(program_id, false)]);
+        assert!(matches!(
+            validate(&instruction, &[]),
+            Err(DebuggerInputError::WritableExecutable(key)) if key == program_id
+        ));
+        // Lenient generation still serializes it.
+        assert!(generate(&instruction, &[], "test_lenient").is_ok());
+        let strict = GenerateOptions {
+            strict: true,
+            ..GenerateOptions::default()
+        };
+        assert!(generate_with_options(&instruction, &[], "test_strict", &strict).is_err());
+
+        let key = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![AccountMeta::new(key, true), AccountMeta::new(key, false)],
+        );
+        let accounts = [(key, SolAccount::new(1, 0, &program_id))];
+        assert!(matches!(
+            validate(&instruction, &accounts),
+            Err(DebuggerInputError::InconsistentSigner(k)) if k == key
+        ));
+
+        // A duplicate can only refer to one of the first 256 accounts.
+        let mut metas: Vec<_> = (0..257)
+            .map(|_| AccountMeta::new_readonly(Pubkey::new_unique(), false))
+            .collect();
+        metas.push(metas[256].clone());
+        let accounts: Vec<_> = metas
+            .iter()
+            .map(|meta| (meta.pubkey, SolAccount::new(1, 0, &program_id)))
+            .collect();
+        let instruction = Instruction::new_with_bytes(program_id, &[], metas);
+        assert!(matches!(
+            validate(&instruction, &accounts),
+            Err(DebuggerInputError::DuplicateIndexOutOfRange(_, 256))
+        ));
+    }
+
+    #[test]
+    fn test_account_data_hash() {
+        let (instruction, accounts) = fixture();
+        let hashes = account_hashes(&instruction, &accounts).unwrap();
+        assert_eq!(hashes, account_hashes(&instruction, &accounts).unwrap());
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[0].0, accounts[0].0);
+
+        let account = Account::new(
+            accounts[0].0,
+            accounts[0].1.owner,
+            10,
+            vec![1, 2, 3],
+            true,
+            true,
+            false,
+            0,
+        );
+        // Instruction flags are not part of the hash, the account state is.
+        let readonly = Account {
+            is_signer: false,
+            is_writable: false,
+            ..account.clone()
+        };
+        assert_eq!(account_data_hash(&account), account_data_hash(&readonly));
+        assert_eq!(account_data_hash(&account), hashes[0].1);
+        let mutated = Account {
+            data: vec![1, 2, 4],
+            ..account.clone()
+        };
+        assert_ne!(account_data_hash(&account), account_data_hash(&mutated));
+
+        let (input_path, hashes_path) = generate_with_hashes(
+            &instruction,
+            &accounts,
+            "test_hashes",
+            OutputFormat::Hex,
+            None,
+        )
+        .unwrap();
+        assert_eq!(input_path, Path::new(".dbg/test_hashes.hex"));
+        assert_eq!(hashes_path, Path::new(".dbg/test_hashes.hashes.json"));
+        let sidecar: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(hashes_path).unwrap()).unwrap();
+        assert_eq!(sidecar["accounts"][1]["pubkey"], accounts[1].0.to_string());
+        assert_eq!(sidecar["accounts"][1]["hash"].as_str().unwrap().len(), 64);
+    }
+
+    #[test]
+    fn test_generate_from_message() {
+        let (instruction, accounts) = fixture();
+        let payer = instruction.accounts[0].pubkey;
+        let vault = instruction.accounts[1].pubkey;
+        // The second instruction shares the payer and passes the vault as read-only.
+        let second = Instruction::new_with_bytes(
+            instruction.program_id,
+            &[5],
+            vec![
+                AccountMeta::new_readonly(vault, false),
+                AccountMeta::new(payer, true),
+            ],
+        );
+        let message = Message::new(&[instruction.clone(), second], Some(&payer));
+
+        let instructions = message_instructions(&message).unwrap();
+        assert_eq!(instructions[0], instruction);
+        assert_eq!(instructions[1].data, vec![5]);
+        // The vault is writable in the message, so in both instructions.
+        assert_eq!(
+            instructions[1].accounts,
+            vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(payer, true)
+            ]
+        );
+
+        let paths = generate_from_message(&message, &accounts, "test_message").unwrap();
+        assert_eq!(
+            paths,
+            [
+                Path::new(".dbg/test_message_0.hex"),
+                Path::new(".dbg/test_message_1.hex")
+            ]
+        );
+        let hex = fs::read_to_string(&paths[1]).unwrap();
+        let bytes = decode_hex(hex.trim()).unwrap();
+        let (deserialized, data, program_id) = deserialize_parameters(&bytes).unwrap();
+        assert_eq!(deserialized.len(), 2);
+        assert_eq!(data, vec![5]);
+        assert_eq!(program_id, instruction.program_id);
+
+        let mut invalid = message.clone();
+        invalid.instructions[1].accounts[0] = 42;
+        assert!(matches!(
+            message_instructions(&invalid),
+            Err(DebuggerInputError::InvalidAccountIndex(1, 42, _))
+        ));
+    }
+
+    #[test]
+    fn test_parse_account_data() {
+        assert_eq!(parse_account_data("base64:AQID").unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_account_data("hex:010203").unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_account_data("hex:0x0a").unwrap(), vec![10]);
+        assert_eq!(parse_account_data("hex:").unwrap(), Vec::<u8>::new());
+
+        assert!(matches!(
+            parse_account_data("base64:!"),
+            Err(DebuggerInputError::InvalidData("base64", _))
+        ));
+        assert!(matches!(
+            parse_account_data("hex:123"),
+            Err(DebuggerInputError::InvalidData("hex", _))
+        ));
+        assert!(matches!(
+            parse_account_data("hex:zz"),
+            Err(DebuggerInputError::InvalidData("hex", _))
+        ));
+        assert!(matches!(
+            parse_account_data("010203"),
+            Err(DebuggerInputError::MissingDataEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_account_from_base58_key() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let account = Account::from_base58_key(
+            &key.to_string(),
+            &owner.to_string(),
+            5,
+            parse_account_data("hex:ff").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            account,
+            Account::new(key, owner, 5, vec![0xff], false, false, false, 0)
+        );
+
+        assert!(matches!(
+            Account::from_base58_key("not-a-pubkey", &owner.to_string(), 0, vec![]),
+            Err(DebuggerInputError::InvalidPubkey(key)) if key == "not-a-pubkey"
+        ));
+        assert!(matches!(
+            Account::from_base58_key(&key.to_string(), "0OIl", 0, vec![]),
+            Err(DebuggerInputError::InvalidPubkey(_))
+        ));
+    }
+
+    #[test]
+    fn test_generate_from_json() {
+        let program_id = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let json = format!(
+            r#"{{
+                "instruction": {{
+                    "programId": "{program_id}",
+                    "data": {{ "hex": "01020304" }},
+                    "accounts": [
+                        {{ "pubkey": "{signer}", "isSigner": true, "isWritable": true }},
+                        {{ "pubkey": "{signer}", "isSigner": true, "isWritable": true }}
+                    ]
+                }},
+                "accounts": [
+                    {{ "pubkey": "{signer}", "owner": "{owner}", "lamports": 10, "data": {{ "base64": "AQID" }} }},
+                    {{ "pubkey": "{owner}", "owner": "{owner}", "lamports": 0, "data": "hex:0a0b" }}
+                ]
+            }}"#
+        );
+
+        let (instruction, accounts) = parse_json_input(&json).unwrap();
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.data, vec![1, 2, 3, 4]);
+        assert_eq!(instruction.accounts.len(), 2);
+        assert!(instruction.accounts[0].is_signer);
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].0, signer);
+        assert_eq!(accounts[0].1.owner, owner);
+        assert_eq!(accounts[0].1.data, vec![1, 2, 3]);
+        assert_eq!(accounts[0].1.rent_epoch, 0);
+        assert_eq!(accounts[1].1.data, vec![10, 11]);
+
+        let path = std::env::temp_dir().join(format!("sbpf-dbg-input-{}.json", program_id));
+        fs::write(&path, &json).unwrap();
+        let result = generate_from_json(&path, "test_json.hex");
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_generate_from_json_malformed() {
+        let program_id = Pubkey::new_unique();
+        let input = |data: &str, pubkey: &str| {
+            format!(
+                r#"{{ "instruction": {{ "programId": "{program_id}", "data": {data}, "accounts": [] }},
+                     "accounts": [{{ "pubkey": "{pubkey}", "owner": "{program_id}", "lamports": 0 }}] }}"#
+            )
+        };
+        let key = Pubkey::new_unique().to_string();
+
+        assert!(matches!(
+            parse_json_input(&input(r#"{ "hex": "123" }"#, &key)),
+            Err(DebuggerInputError::InvalidData("hex", _))
+        ));
+        assert!(matches!(
+            parse_json_input(&input(r#"{ "base64": "!" }"#, &key)),
+            Err(DebuggerInputError::InvalidData("base64", _))
+        ));
+        assert!(matches!(
+            parse_json_input(&input(r#""base64:!""#, &key)),
+            Err(DebuggerInputError::InvalidData("base64", _))
+        ));
+        assert!(matches!(
+            parse_json_input(&input(r#""01020304""#, &key)),
+            Err(DebuggerInputError::MissingDataEncoding(_))
+        ));
+        assert!(matches!(
+            parse_json_input(&input(r#"{ "hex": "" }"#, "not-a-pubkey")),
+            Err(DebuggerInputError::InvalidPubkey(_))
+        ));
+        assert!(matches!(
+            parse_json_input("{}"),
+            Err(DebuggerInputError::InvalidJson(_))
+        ));
+        assert!(matches!(
+            generate_from_json(Path::new("/nonexistent/input.json"), "out"),
+            Err(DebuggerInputError::FileReadError(_, _))
+        ));
+    }
 }
@@ -1,6 +1,17 @@
+use crate::{
+    debugger::{RegisterName, DEFAULT_PROFILE_TOP},
+    parser::Symbol,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::io::{self, BufRead, Write};
+use std::{
+    io::{self, BufRead, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
 
 pub trait DebuggerInterface {
     fn step(&mut self) -> Value;
@@ -10,11 +21,34 @@ pub trait DebuggerInterface {
     fn get_stack_frames(&self) -> Value;
     fn get_registers(&self) -> Value;
     fn get_memory(&self, address: u64, size: usize) -> Value;
-    fn set_register(&mut self, index: usize, value: u64) -> Value;
+    fn get_memory_map(&self) -> Value;
+    fn evaluate(&self, expression: String) -> Value;
+    fn set_register(&mut self, register: RegisterName, value: u64) -> Value;
     fn get_rodata(&self) -> Value;
+    fn get_data(&self) -> Value;
     fn clear_breakpoints(&mut self, file: String) -> Value;
+    fn set_function_breakpoint(&mut self, name: String) -> Value;
+    fn clear_function_breakpoints(&mut self) -> Value;
+    /// Move the PC without executing anything
+    fn set_pc(&mut self, pc: u64) -> Value;
+    /// Get the PCs of a source line as DAP `gotoTargets`
+    fn get_goto_targets(&self, file: String, line: usize) -> Value;
+    /// Run the program again from its entrypoint, keeping the breakpoints
+    fn restart(&mut self) -> Value;
+    /// Acknowledge a `pause`. The pause itself is requested through [`Self::pause_handle`].
+    fn pause(&mut self) -> Value;
+    /// Get the flag that interrupts a running `continue` when set from another thread
+    fn pause_handle(&self) -> Arc<AtomicBool>;
     fn quit(&mut self) -> Value;
     fn get_compute_units(&self) -> Value;
+    fn get_capabilities(&self) -> Value;
+    fn get_syscall_trace(&self) -> Value;
+    fn get_variables(&self) -> Value;
+    fn get_profile(&self, top: usize) -> Value;
+    /// Take the program logs emitted since the last call
+    fn take_logs(&mut self) -> Vec<String>;
+    /// Get all program logs of the run
+    fn get_logs(&self) -> Value;
 }
 
 #[derive(Deserialize)]
@@ -25,6 +59,88 @@ struct AdapterCommand {
     request_id: Option<Value>,
 }
 
+/// Largest integer a JSON number (an f64 in the extension) represents exactly
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Id of the single thread reported to clients. Programs run on one thread, so `threadId`
+/// arguments are accepted and ignored.
+const THREAD_ID: u64 = 1;
+
+/// Encode a u64 as a JSON number if it fits in the f64 mantissa and as a hex string otherwise.
+pub fn json_u64(value: u64) -> Value {
+    if value <= MAX_SAFE_INTEGER {
+        json!(value)
+    } else {
+        json!(format!("0x{:016x}", value))
+    }
+}
+
+/// Encode bytes as a hex string without separators.
+pub fn json_bytes(bytes: &[u8]) -> Value {
+    json!(bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>())
+}
+
+/// JSON representation of a register, shared by the adapter and the REPL's JSON output.
+pub fn register_json(register: RegisterName, value: u64) -> Value {
+    json!({
+        "name": register.to_string(),
+        "value": format!("0x{:016x}", value),
+        "type": "u64",
+        "signed": value as i64,
+        "binary": format!("0b{:b}", value),
+        "ascii": register_ascii(value)
+    })
+}
+
+/// The bytes of a register in memory order as ASCII, with `.` for non-printable bytes.
+pub fn register_ascii(value: u64) -> String {
+    value
+        .to_le_bytes()
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+/// JSON representation of a `.rodata` or `.data` symbol, shared by the adapter and the REPL's
+/// JSON output.
+pub fn symbol_json(symbol: &Symbol) -> Value {
+    json!({
+        "name": symbol.display_name(),
+        "rawName": symbol.name,
+        "address": format!("0x{:016x}", symbol.address),
+        "size": symbol.size,
+        "value": symbol.content,
+    })
+}
+
+/// Decode a u64 encoded as a JSON number or a (hex or decimal) string.
+fn parse_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        },
+        _ => value.as_u64(),
+    }
+}
+
+/// Resolve a register given by index or by name: `r0` to `r11`, `pc` (in bytes), or `sp`/`fp` (r10).
+fn parse_register(value: &Value) -> Option<RegisterName> {
+    if let Some(index) = value.as_u64() {
+        return Some(RegisterName::Index(index as usize));
+    }
+    RegisterName::parse(value.as_str()?)
+}
+
 #[derive(Serialize)]
 struct AdapterResponse {
     success: bool,
@@ -34,10 +150,320 @@ struct AdapterResponse {
     request_id: Option<Value>,
 }
 
-pub fn run_adapter_loop<T: DebuggerInterface>(debugger: &mut T) {
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    for line in stdin.lock().lines() {
+/// Execute an adapter command and return its result.
+fn execute_command<T: DebuggerInterface>(
+    debugger: &mut T,
+    command: &str,
+    args: Option<Value>,
+) -> Value {
+    match command {
+        "step" => debugger.step(),
+        "continue" => debugger.r#continue(),
+        "setBreakpoint" => {
+            if let Some(args) = args {
+                let file = args
+                    .get(0)
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string();
+                let line = args.get(1).and_then(Value::as_u64).unwrap_or(0) as usize;
+                debugger.set_breakpoint(file, line)
+            } else {
+                json!({"type": "error", "message": "Missing args"})
+            }
+        }
+        "removeBreakpoint" => {
+            if let Some(args) = args {
+                let file = args
+                    .get(0)
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string();
+                let line = args.get(1).and_then(Value::as_u64).unwrap_or(0) as usize;
+                debugger.remove_breakpoint(file, line)
+            } else {
+                json!({"type": "error", "message": "Missing args"})
+            }
+        }
+        "setFunctionBreakpoint" => match args.as_ref().and_then(|args| args.get(0)) {
+            Some(Value::String(name)) => debugger.set_function_breakpoint(name.clone()),
+            _ => json!({"type": "error", "message": "Missing function name"}),
+        },
+        "clearFunctionBreakpoints" => debugger.clear_function_breakpoints(),
+        "getStackFrames" => debugger.get_stack_frames(),
+        "getRegisters" => debugger.get_registers(),
+        "getRodata" => debugger.get_rodata(),
+        "getData" => debugger.get_data(),
+        "clearBreakpoints" => {
+            if let Some(args) = args {
+                let file = args
+                    .get(0)
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string();
+                debugger.clear_breakpoints(file)
+            } else {
+                json!({"type": "error", "message": "Missing args"})
+            }
+        }
+        "getMemory" => {
+            if let Some(args) = args {
+                let address = args.get(0).and_then(parse_u64).unwrap_or(0);
+                let size = args.get(1).and_then(Value::as_u64).unwrap_or(0) as usize;
+                debugger.get_memory(address, size)
+            } else {
+                json!({"type": "error", "message": "Missing args"})
+            }
+        }
+        "getMemoryMap" => debugger.get_memory_map(),
+        "evaluate" => {
+            // Accept positional args as well as DAP's `{"expression": ...}` arguments.
+            let expression = args
+                .as_ref()
+                .and_then(|args| args.get(0).or_else(|| args.get("expression")))
+                .and_then(Value::as_str);
+            match expression {
+                Some(expression) => debugger.evaluate(expression.to_string()),
+                None => json!({"type": "error", "message": "Missing expression"}),
+            }
+        }
+        "getComputeUnits" => debugger.get_compute_units(),
+        "getCapabilities" => debugger.get_capabilities(),
+        "getSyscallTrace" => debugger.get_syscall_trace(),
+        "getLogs" => debugger.get_logs(),
+        "variables" => debugger.get_variables(),
+        "getProfile" => {
+            let top = args
+                .as_ref()
+                .and_then(|args| args.get(0).or_else(|| args.get("top")))
+                .and_then(Value::as_u64)
+                .map_or(DEFAULT_PROFILE_TOP, |top| top as usize);
+            debugger.get_profile(top)
+        }
+        "setRegister" => {
+            if let Some(args) = args {
+                let register = args.get(0).cloned().unwrap_or(json!(0));
+                let value = args.get(1).and_then(parse_u64).unwrap_or(0);
+                match parse_register(&register) {
+                    Some(register) => debugger.set_register(register, value),
+                    None => {
+                        json!({"type": "error", "message": format!("Unknown register {}", register)})
+                    }
+                }
+            } else {
+                json!({"type": "error", "message": "Missing args"})
+            }
+        }
+        "setVariable" => {
+            // Accept positional args as well as DAP's `{"name": ..., "value": ...}` arguments.
+            let args = args.unwrap_or(Value::Null);
+            let name = args.get(0).or_else(|| args.get("name")).cloned();
+            let value = args
+                .get(1)
+                .or_else(|| args.get("value"))
+                .and_then(parse_u64);
+            match (name, value) {
+                (Some(name), Some(value)) => match parse_register(&name) {
+                    Some(register) => {
+                        let mut result = debugger.set_register(register, value);
+                        if result.get("error").is_none() {
+                            // Report the value the way the variables pane displays registers.
+                            result["type"] = json!("setVariable");
+                            result["name"] = name;
+                            result["value"] = json!(format!("0x{:016x}", value));
+                        }
+                        result
+                    }
+                    None => {
+                        json!({"type": "error", "message": format!("Unknown register {}", name)})
+                    }
+                },
+                _ => json!({"type": "error", "message": "Missing name or value"}),
+            }
+        }
+        "setPc" | "goto" => {
+            // Accept positional args as well as DAP's `{"targetId": ...}` arguments, whose
+            // target ids are PCs.
+            let pc = args
+                .as_ref()
+                .and_then(|args| args.get(0).or_else(|| args.get("targetId")))
+                .and_then(parse_u64);
+            match pc {
+                Some(pc) => debugger.set_pc(pc),
+                None => json!({"type": "error", "message": "Missing pc"}),
+            }
+        }
+        "gotoTargets" => {
+            // Accept positional `[file, line]` args as well as DAP's
+            // `{"source": {"path": ...}, "line": ...}` arguments.
+            let args = args.unwrap_or(Value::Null);
+            let file = args
+                .get(0)
+                .or_else(|| args.get("source").and_then(|source| source.get("path")))
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string();
+            match args
+                .get(1)
+                .or_else(|| args.get("line"))
+                .and_then(Value::as_u64)
+            {
+                Some(line) => debugger.get_goto_targets(file, line as usize),
+                None => json!({"type": "error", "message": "Missing line"}),
+            }
+        }
+        "restart" => debugger.restart(),
+        "pause" => debugger.pause(),
+        "threads" => json!({"threads": [{"id": THREAD_ID, "name": "sbpf"}]}),
+        "quit" => debugger.quit(),
+        _ => json!({"type": "error", "message": "Unknown command"}),
+    }
+}
+
+/// Get the error message of a command result, if the command failed.
+fn result_error(result: &Value) -> Option<String> {
+    let result_obj = result.as_object()?;
+    let message = if result_obj.contains_key("error") {
+        result_obj.get("error")
+    } else if result_obj.get("type").and_then(|v| v.as_str()) == Some("error") {
+        result_obj.get("message")
+    } else {
+        return None;
+    };
+    Some(
+        message
+            .and_then(|v| v.as_str())
+            .unwrap_or("Command failed")
+            .to_string(),
+    )
+}
+
+/// An event pushed to the client without a request, so that it does not have to poll
+#[derive(Serialize, Debug, Default, PartialEq)]
+struct AdapterEvent {
+    event: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pc: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+}
+
+/// Get the event announcing the outcome of a `step` or `continue` result, if any.
+fn adapter_event(result: &Value) -> Option<AdapterEvent> {
+    match result.get("type").and_then(Value::as_str)? {
+        "breakpoint" => Some(AdapterEvent {
+            event: "stopped",
+            reason: Some("breakpoint"),
+            pc: result.get("pc").cloned(),
+            line: result.get("line").cloned(),
+            ..Default::default()
+        }),
+        "paused" => Some(AdapterEvent {
+            event: "stopped",
+            reason: Some("pause"),
+            pc: result.get("pc").cloned(),
+            line: result.get("line").cloned(),
+            ..Default::default()
+        }),
+        "watchpoint" => Some(AdapterEvent {
+            event: "stopped",
+            reason: Some("data breakpoint"),
+            pc: result.get("pc").cloned(),
+            line: result.get("line").cloned(),
+            ..Default::default()
+        }),
+        "limitReached" => Some(AdapterEvent {
+            event: "stopped",
+            reason: Some("limit"),
+            pc: result.get("pc").cloned(),
+            line: result.get("line").cloned(),
+            ..Default::default()
+        }),
+        "exit" => Some(AdapterEvent {
+            event: "terminated",
+            code: result.get("code").cloned(),
+            ..Default::default()
+        }),
+        _ => None,
+    }
+}
+
+/// Get the event announcing the outcome of a DAP stepping or `continue` request, if any. Unlike
+/// adapter clients, DAP clients also expect a `stopped` event after a plain step.
+fn dap_event(result: &Value) -> Option<AdapterEvent> {
+    match result.get("type").and_then(Value::as_str)? {
+        "step" => Some(AdapterEvent {
+            event: "stopped",
+            reason: Some("step"),
+            pc: result.get("pc").cloned(),
+            line: result.get("line").cloned(),
+            ..Default::default()
+        }),
+        _ => adapter_event(result),
+    }
+}
+
+/// Get the `output` event carrying a program log line.
+fn output_event(text: String) -> AdapterEvent {
+    AdapterEvent {
+        event: "output",
+        category: Some("stdout"),
+        text: Some(text),
+        ..Default::default()
+    }
+}
+
+/// Read messages with `read` on a separate thread until the input ends or fails.
+///
+/// Commands still run one at a time, in order, on the thread that drains the returned channel.
+/// The reader thread only looks ahead for `pause` messages: it sets `pause_requested` as soon
+/// as it reads one, so that a `continue` running on the command thread stops at the next
+/// instruction. The `pause` message is then queued like any other and acknowledged once the
+/// `continue` has returned. The reader thread is detached, as it may stay blocked on the input
+/// after the session ends.
+fn spawn_reader<M: Send + 'static>(
+    pause_requested: Arc<AtomicBool>,
+    mut read: impl FnMut() -> io::Result<Option<M>> + Send + 'static,
+    is_pause: impl Fn(&M) -> bool + Send + 'static,
+) -> mpsc::Receiver<io::Result<M>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        while let Some(message) = read().transpose() {
+            if message.as_ref().is_ok_and(&is_pause) {
+                pause_requested.store(true, Ordering::Relaxed);
+            }
+            let failed = message.is_err();
+            if sender.send(message).is_err() || failed {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Serve newline-delimited adapter commands from `reader` until the input ends.
+fn run_adapter<T: DebuggerInterface>(
+    debugger: &mut T,
+    reader: impl BufRead + Send + 'static,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let mut lines = reader.lines();
+    let messages = spawn_reader(
+        debugger.pause_handle(),
+        move || lines.next().transpose(),
+        |line: &String| {
+            serde_json::from_str::<AdapterCommand>(line).is_ok_and(|cmd| cmd.command == "pause")
+        },
+    );
+    for line in messages {
         let line = match line {
             Ok(l) => l,
             Err(_) => break,
@@ -52,90 +478,18 @@ pub fn run_adapter_loop<T: DebuggerInterface>(debugger: &mut T) {
             error: None,
             request_id: None,
         };
+        let mut event = None;
         match cmd {
             Ok(cmd) => {
                 response.request_id = cmd.request_id.clone();
-                let result = match cmd.command.as_str() {
-                    "step" => debugger.step(),
-                    "continue" => debugger.r#continue(),
-                    "setBreakpoint" => {
-                        if let Some(args) = cmd.args {
-                            let file = args
-                                .get(0)
-                                .and_then(Value::as_str)
-                                .unwrap_or("")
-                                .to_string();
-                            let line = args.get(1).and_then(Value::as_u64).unwrap_or(0) as usize;
-                            debugger.set_breakpoint(file, line)
-                        } else {
-                            json!({"type": "error", "message": "Missing args"})
-                        }
-                    }
-                    "removeBreakpoint" => {
-                        if let Some(args) = cmd.args {
-                            let file = args
-                                .get(0)
-                                .and_then(Value::as_str)
-                                .unwrap_or("")
-                                .to_string();
-                            let line = args.get(1).and_then(Value::as_u64).unwrap_or(0) as usize;
-                            debugger.remove_breakpoint(file, line)
-                        } else {
-                            json!({"type": "error", "message": "Missing args"})
-                        }
-                    }
-                    "getStackFrames" => debugger.get_stack_frames(),
-                    "getRegisters" => debugger.get_registers(),
-                    "getRodata" => debugger.get_rodata(),
-                    "clearBreakpoints" => {
-                        if let Some(args) = cmd.args {
-                            let file = args
-                                .get(0)
-                                .and_then(Value::as_str)
-                                .unwrap_or("")
-                                .to_string();
-                            debugger.clear_breakpoints(file)
-                        } else {
-                            json!({"type": "error", "message": "Missing args"})
-                        }
-                    }
-                    "getMemory" => {
-                        if let Some(args) = cmd.args {
-                            let address = args.get(0).and_then(Value::as_u64).unwrap_or(0);
-                            let size = args.get(1).and_then(Value::as_u64).unwrap_or(0) as usize;
-                            debugger.get_memory(address, size)
-                        } else {
-                            json!({"type": "error", "message": "Missing args"})
-                        }
-                    }
-                    "getComputeUnits" => debugger.get_compute_units(),
-                    "setRegister" => {
-                        if let Some(args) = cmd.args {
-                            let index = args.get(0).and_then(Value::as_u64).unwrap_or(0) as usize;
-                            let value = args.get(1).and_then(Value::as_u64).unwrap_or(0);
-                            debugger.set_register(index, value)
-                        } else {
-                            json!({"type": "error", "message": "Missing args"})
-                        }
-                    }
-                    "quit" => debugger.quit(),
-                    _ => json!({"type": "error", "message": "Unknown command"}),
-                };
+                let result = execute_command(debugger, &cmd.command, cmd.args);
                 // Check if the result contains an error
-                if let Some(result_obj) = result.as_object() {
-                    if result_obj.contains_key("error") {
-                        response.success = false;
-                        response.error = result_obj
-                            .get("error")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                    } else if result_obj.get("type").and_then(|v| v.as_str()) == Some("error") {
-                        response.success = false;
-                        response.error = result_obj
-                            .get("message")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                    }
+                if let Some(error) = result_error(&result) {
+                    response.success = false;
+                    response.error = Some(error);
+                }
+                if matches!(cmd.command.as_str(), "step" | "continue") {
+                    event = adapter_event(&result);
                 }
                 response.data = Some(result);
             }
@@ -145,7 +499,761 @@ pub fn run_adapter_loop<T: DebuggerInterface>(debugger: &mut T) {
             }
         }
         let resp_str = serde_json::to_string(&response).unwrap();
-        writeln!(stdout, "{}", resp_str).unwrap();
-        stdout.flush().unwrap();
+        writeln!(writer, "{}", resp_str)?;
+        for text in debugger.take_logs() {
+            writeln!(
+                writer,
+                "{}",
+                serde_json::to_string(&output_event(text)).unwrap()
+            )?;
+        }
+        if let Some(event) = event {
+            writeln!(writer, "{}", serde_json::to_string(&event).unwrap())?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+pub fn run_adapter_loop<T: DebuggerInterface>(debugger: &mut T) {
+    run_adapter(debugger, io::BufReader::new(io::stdin()), io::stdout()).unwrap();
+}
+
+/// A Debug Adapter Protocol request
+#[derive(Deserialize)]
+struct DapRequest {
+    seq: u64,
+    #[serde(rename = "type")]
+    message_type: String,
+    command: String,
+    arguments: Option<Value>,
+}
+
+/// A Debug Adapter Protocol response
+#[derive(Serialize)]
+struct DapResponse {
+    seq: u64,
+    #[serde(rename = "type")]
+    message_type: &'static str,
+    request_seq: u64,
+    success: bool,
+    command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<Value>,
+}
+
+/// A Debug Adapter Protocol event
+#[derive(Serialize)]
+struct DapEvent {
+    seq: u64,
+    #[serde(rename = "type")]
+    message_type: &'static str,
+    event: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<Value>,
+}
+
+/// Read a message framed by a `Content-Length` header. Returns `None` at the end of the input.
+fn read_dap_message(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            // Tolerate blank lines between messages.
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(value.trim().parse::<usize>().map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid Content-Length '{}': {}", value.trim(), e),
+                    )
+                })?);
+            }
+        }
+    }
+    let mut content = vec![0; content_length.unwrap()];
+    reader.read_exact(&mut content)?;
+    Ok(Some(content))
+}
+
+/// Write a message with a `Content-Length` header.
+fn write_dap_message(writer: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+    let content = serde_json::to_string(message).unwrap();
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()
+}
+
+/// Writes DAP responses and events, numbering them with increasing sequence numbers.
+struct DapWriter<W: Write> {
+    writer: W,
+    seq: u64,
+}
+
+impl<W: Write> DapWriter<W> {
+    fn next_seq(&mut self) -> u64 {
+        self.seq += 1;
+        self.seq
+    }
+
+    fn respond(&mut self, request: &DapRequest, result: Result<Value, String>) -> io::Result<()> {
+        let (success, message, body) = match result {
+            Ok(body) => (true, None, Some(body)),
+            Err(message) => (false, Some(message), None),
+        };
+        let response = DapResponse {
+            seq: self.next_seq(),
+            message_type: "response",
+            request_seq: request.seq,
+            success,
+            command: request.command.clone(),
+            message,
+            body,
+        };
+        write_dap_message(&mut self.writer, &response)
+    }
+
+    fn event(&mut self, event: &str, body: Option<Value>) -> io::Result<()> {
+        let event = DapEvent {
+            seq: self.next_seq(),
+            message_type: "event",
+            event: event.to_string(),
+            body,
+        };
+        write_dap_message(&mut self.writer, &event)
+    }
+}
+
+/// `variablesReference` of the locals scope
+const LOCALS_REFERENCE: u64 = 1;
+/// `variablesReference` of the registers scope
+const REGISTERS_REFERENCE: u64 = 2;
+
+/// Get the DAP `Breakpoint` of a `setBreakpoint` or `setFunctionBreakpoint` result.
+fn dap_breakpoint(result: &Value) -> Value {
+    let mut breakpoint = json!({ "verified": result["verified"].as_bool().unwrap_or(false) });
+    if let Some(line) = result.get("line").filter(|line| !line.is_null()) {
+        breakpoint["line"] = line.clone();
+    }
+    if let Some(error) = result.get("error") {
+        breakpoint["message"] = error.clone();
+    }
+    breakpoint
+}
+
+/// Get the `initialize` response body: the DAP requests supported on top of the base protocol,
+/// followed by the `getCapabilities` result (sBPF version and VM configuration).
+fn dap_capabilities<T: DebuggerInterface>(debugger: &T) -> Value {
+    let mut body = json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsFunctionBreakpoints": true,
+        "supportsGotoTargetsRequest": true,
+        "supportsSetVariable": true,
+        "supportsRestartRequest": true,
+    });
+    if let Value::Object(capabilities) = debugger.get_capabilities() {
+        for (name, value) in capabilities {
+            if name != "type" {
+                body[name] = value;
+            }
+        }
+    }
+    body
+}
+
+/// Execute a DAP request, translating the standard requests into adapter commands and their
+/// results into the response bodies of the protocol. Other requests are executed as the adapter
+/// command of the same name, with the request arguments as the command arguments.
+fn execute_dap_request<T: DebuggerInterface>(
+    debugger: &mut T,
+    command: &str,
+    args: Option<Value>,
+) -> Value {
+    let arguments = args.clone().unwrap_or(Value::Null);
+    match command {
+        "setBreakpoints" => {
+            // The breakpoints of a source replace all of its previous ones.
+            let file = arguments["source"]["path"]
+                .as_str()
+                .unwrap_or("")
+                .to_string();
+            debugger.clear_breakpoints(file.clone());
+            let breakpoints: Vec<Value> = arguments["breakpoints"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .map(|breakpoint| {
+                    let line = breakpoint["line"].as_u64().unwrap_or(0) as usize;
+                    dap_breakpoint(&debugger.set_breakpoint(file.clone(), line))
+                })
+                .collect();
+            json!({ "breakpoints": breakpoints })
+        }
+        "setFunctionBreakpoints" => {
+            debugger.clear_function_breakpoints();
+            let breakpoints: Vec<Value> = arguments["breakpoints"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .map(|breakpoint| {
+                    let name = breakpoint["name"].as_str().unwrap_or("").to_string();
+                    dap_breakpoint(&debugger.set_function_breakpoint(name))
+                })
+                .collect();
+            json!({ "breakpoints": breakpoints })
+        }
+        "next" | "stepIn" => debugger.step(),
+        "evaluate" => {
+            let mut result = execute_command(debugger, command, args);
+            if result_error(&result).is_none() {
+                result = json!({ "result": result["result"], "variablesReference": 0 });
+            }
+            result
+        }
+        "setVariable" => {
+            let mut result = execute_command(debugger, command, args);
+            if result_error(&result).is_none() {
+                result = json!({ "value": result["value"] });
+            }
+            result
+        }
+        "stackTrace" => {
+            let result = debugger.get_stack_frames();
+            let frames = result["frames"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let start = arguments["startFrame"].as_u64().unwrap_or(0) as usize;
+            let levels = match arguments["levels"].as_u64().unwrap_or(0) {
+                0 => frames.len(),
+                levels => levels as usize,
+            };
+            let stack_frames: Vec<Value> = frames
+                .iter()
+                .skip(start)
+                .take(levels)
+                .map(|frame| {
+                    let mut stack_frame = json!({
+                        "id": frame["index"],
+                        "name": frame["name"],
+                        "line": frame["line"],
+                        "column": frame["column"],
+                    });
+                    if let Some(pc) = parse_u64(&frame["instruction"]) {
+                        stack_frame["instructionPointerReference"] = json!(format!("0x{:x}", pc));
+                    }
+                    if frame["file"] != "?" {
+                        stack_frame["source"] = json!({ "path": frame["file"] });
+                    }
+                    stack_frame
+                })
+                .collect();
+            json!({ "stackFrames": stack_frames, "totalFrames": frames.len() })
+        }
+        "scopes" => json!({
+            "scopes": [
+                {"name": "Locals", "variablesReference": LOCALS_REFERENCE, "expensive": false},
+                {"name": "Registers", "variablesReference": REGISTERS_REFERENCE, "expensive": false},
+            ]
+        }),
+        "variables" => match arguments["variablesReference"].as_u64() {
+            Some(LOCALS_REFERENCE) => {
+                let result = debugger.get_variables();
+                let variables: Vec<Value> = result["variables"]
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|variable| {
+                        // Locals that cannot be read show why in place of their value.
+                        let value = match &variable["value"] {
+                            Value::Null => variable["message"].clone(),
+                            value => value.clone(),
+                        };
+                        json!({"name": variable["name"], "value": value, "variablesReference": 0})
+                    })
+                    .collect();
+                json!({ "variables": variables })
+            }
+            Some(REGISTERS_REFERENCE) => {
+                let result = debugger.get_registers();
+                let variables: Vec<Value> = result["registers"]
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|register| {
+                        json!({
+                            "name": register["name"],
+                            "value": register["value"],
+                            "type": register["type"],
+                            "variablesReference": 0
+                        })
+                    })
+                    .collect();
+                json!({ "variables": variables })
+            }
+            _ => json!({"type": "error", "message": "Unknown variablesReference"}),
+        },
+        _ => execute_command(debugger, command, args),
+    }
+}
+
+/// Serve DAP requests from `reader` until the input ends or the client disconnects.
+///
+/// The `initialize`, `launch`, `configurationDone` and `disconnect` requests are handled here and
+/// the other standard requests are translated by [`execute_dap_request`]. Stepping and
+/// continuing are followed by a `stopped` or `terminated` event.
+fn run_dap<T: DebuggerInterface>(
+    debugger: &mut T,
+    mut reader: impl BufRead + Send + 'static,
+    writer: impl Write,
+) -> io::Result<()> {
+    let mut writer = DapWriter { writer, seq: 0 };
+    let messages = spawn_reader(
+        debugger.pause_handle(),
+        move || read_dap_message(&mut reader),
+        |content: &Vec<u8>| {
+            serde_json::from_slice::<DapRequest>(content).is_ok_and(|r| r.command == "pause")
+        },
+    );
+    for content in messages {
+        let content = content?;
+        let request: DapRequest = match serde_json::from_slice(&content) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("Invalid DAP message: {}", e);
+                continue;
+            }
+        };
+        if request.message_type != "request" {
+            continue;
+        }
+        match request.command.as_str() {
+            "initialize" => {
+                writer.respond(&request, Ok(dap_capabilities(debugger)))?;
+                writer.event("initialized", None)?;
+            }
+            "launch" | "attach" | "configurationDone" => writer.respond(&request, Ok(json!({})))?,
+            "disconnect" => {
+                debugger.quit();
+                writer.respond(&request, Ok(json!({})))?;
+                break;
+            }
+            command => {
+                let result = execute_dap_request(debugger, command, request.arguments.clone());
+                let event = match command {
+                    "step" | "next" | "stepIn" | "continue" => dap_event(&result),
+                    _ => None,
+                };
+                let result = match result_error(&result) {
+                    Some(error) => Err(error),
+                    None => Ok(result),
+                };
+                writer.respond(&request, result)?;
+                for text in debugger.take_logs() {
+                    let body = json!({ "category": "stdout", "output": format!("{}\n", text) });
+                    writer.event("output", Some(body))?;
+                }
+                if let Some(event) = event {
+                    let mut body = serde_json::to_value(&event).unwrap();
+                    let body_obj = body.as_object_mut().unwrap();
+                    body_obj.remove("event");
+                    if event.event == "stopped" {
+                        body_obj.insert("threadId".to_string(), json!(THREAD_ID));
+                    }
+                    writer.event(event.event, Some(body))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run the adapter speaking the Debug Adapter Protocol, with `Content-Length` framed messages.
+pub fn run_dap_loop<T: DebuggerInterface>(debugger: &mut T) {
+    if let Err(e) = run_dap(debugger, io::BufReader::new(io::stdin()), io::stdout()) {
+        eprintln!("error:DAP connection failed: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::with_debugger, LogSink};
+
+    #[test]
+    fn test_json_u64() {
+        assert_eq!(json_u64(42), json!(42));
+        assert_eq!(json_u64(MAX_SAFE_INTEGER), json!(MAX_SAFE_INTEGER));
+        assert_eq!(json_u64(1 << 53), json!("0x0020000000000000"));
+        assert_eq!(parse_u64(&json_u64(u64::MAX)), Some(u64::MAX));
+        assert_eq!(parse_u64(&json!(7)), Some(7));
+    }
+
+    #[test]
+    fn test_dap_framing() {
+        let input = b"Content-Length: 2\r\n\r\n{}\r\nContent-Length: 13\r\n\r\n{\"seq\": 1234}";
+        let mut reader = &input[..];
+        assert_eq!(read_dap_message(&mut reader).unwrap().unwrap(), b"{}");
+        assert_eq!(
+            read_dap_message(&mut reader).unwrap().unwrap(),
+            b"{\"seq\": 1234}"
+        );
+        assert!(read_dap_message(&mut reader).unwrap().is_none());
+
+        let mut output = Vec::new();
+        write_dap_message(&mut output, &json!({"a": 1})).unwrap();
+        assert_eq!(output, b"Content-Length: 7\r\n\r\n{\"a\":1}");
+        assert!(read_dap_message(&mut &b"Content-Length: x\r\n\r\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_dap_session() {
+        let mut input = Vec::new();
+        for (seq, command) in [(1, "initialize"), (2, "getRegisters"), (3, "disconnect")] {
+            let request = json!({"seq": seq, "type": "request", "command": command});
+            write_dap_message(&mut input, &request).unwrap();
+        }
+
+        let mut output = Vec::new();
+        with_debugger("exit", |mut dbg| {
+            run_dap(&mut dbg, io::Cursor::new(input), &mut output).unwrap();
+        });
+
+        let mut reader = &output[..];
+        let mut messages = Vec::new();
+        while let Some(content) = read_dap_message(&mut reader).unwrap() {
+            messages.push(serde_json::from_slice::<Value>(&content).unwrap());
+        }
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0]["type"], "response");
+        assert_eq!(messages[0]["request_seq"], 1);
+        assert_eq!(messages[1]["type"], "event");
+        assert_eq!(messages[1]["event"], "initialized");
+        assert_eq!(messages[2]["command"], "getRegisters");
+        assert_eq!(messages[2]["success"], true);
+        assert!(messages[2]["body"]["registers"].is_array());
+        assert_eq!(messages[3]["command"], "disconnect");
+        // Sequence numbers increase across responses and events.
+        let seqs: Vec<u64> = messages
+            .iter()
+            .map(|m| m["seq"].as_u64().unwrap())
+            .collect();
+        assert_eq!(seqs, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_dap_requests() {
+        let requests = [
+            ("initialize", json!({})),
+            ("next", json!({"threadId": THREAD_ID})),
+            ("stackTrace", json!({"threadId": THREAD_ID})),
+            ("scopes", json!({"frameId": 0})),
+            (
+                "variables",
+                json!({"variablesReference": REGISTERS_REFERENCE}),
+            ),
+            (
+                "setBreakpoints",
+                json!({"source": {"path": "program.s"}, "breakpoints": [{"line": 1}]}),
+            ),
+            ("evaluate", json!({"expression": "r0 + 1"})),
+            (
+                "setVariable",
+                json!({"variablesReference": REGISTERS_REFERENCE, "name": "r3", "value": "0x10"}),
+            ),
+            ("disconnect", json!({})),
+        ];
+        let mut input = Vec::new();
+        for (seq, (command, arguments)) in requests.into_iter().enumerate() {
+            let request = json!({
+                "seq": seq + 1,
+                "type": "request",
+                "command": command,
+                "arguments": arguments
+            });
+            write_dap_message(&mut input, &request).unwrap();
+        }
+
+        let mut output = Vec::new();
+        with_debugger("mov64 r0, 1\nexit", |mut dbg| {
+            run_dap(&mut dbg, io::Cursor::new(input), &mut output).unwrap();
+        });
+
+        let mut reader = &output[..];
+        let mut messages = Vec::new();
+        while let Some(content) = read_dap_message(&mut reader).unwrap() {
+            messages.push(serde_json::from_slice::<Value>(&content).unwrap());
+        }
+        let response = |seq: u64| {
+            messages
+                .iter()
+                .find(|m| m["type"] == "response" && m["request_seq"] == seq)
+                .unwrap()
+        };
+
+        let capabilities = &response(1)["body"];
+        assert_eq!(capabilities["supportsConfigurationDoneRequest"], true);
+        assert_eq!(capabilities["sbpfVersion"], "V0");
+        assert!(capabilities.get("type").is_none());
+
+        // Stepping is announced with a `stopped` event right after its response.
+        let next = messages.iter().position(|m| m["request_seq"] == 2).unwrap();
+        assert_eq!(messages[next + 1]["event"], "stopped");
+        assert_eq!(messages[next + 1]["body"]["reason"], "step");
+        assert_eq!(messages[next + 1]["body"]["threadId"], THREAD_ID);
+
+        let stack_frames = &response(3)["body"]["stackFrames"];
+        assert_eq!(stack_frames[0]["id"], 0);
+        assert_eq!(stack_frames[0]["instructionPointerReference"], "0x8");
+        assert_eq!(response(3)["body"]["totalFrames"], 1);
+
+        let scopes = &response(4)["body"]["scopes"];
+        assert_eq!(scopes[1]["variablesReference"], REGISTERS_REFERENCE);
+
+        let variables = &response(5)["body"]["variables"];
+        assert_eq!(variables[0]["name"], "r0");
+        assert_eq!(variables[0]["value"], "0x0000000000000001");
+        assert_eq!(variables[0]["variablesReference"], 0);
+
+        // Without line information, line breakpoints cannot be verified.
+        let breakpoints = &response(6)["body"]["breakpoints"];
+        assert_eq!(breakpoints[0]["verified"], false);
+        assert!(breakpoints[0]["message"].is_string());
+
+        assert_eq!(
+            response(7)["body"],
+            json!({"result": "0x2", "variablesReference": 0})
+        );
+        assert_eq!(response(8)["body"], json!({"value": "0x0000000000000010"}));
+    }
+
+    #[test]
+    fn test_adapter_events() {
+        let source = "
+            mov64 r0, 3
+            mov64 r1, 1
+            exit";
+        let mut input = Vec::new();
+        for command in ["continue", "continue"] {
+            writeln!(input, "{}", json!({"command": command, "requestId": 1})).unwrap();
+        }
+        let mut output = Vec::new();
+        with_debugger(source, |mut dbg| {
+            dbg.set_breakpoint(8);
+            run_adapter(&mut dbg, io::Cursor::new(input), &mut output).unwrap();
+        });
+
+        let lines: Vec<Value> = output
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        // The first continue stops at the breakpoint, the second runs to the exit.
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["requestId"], 1);
+        assert_eq!(
+            lines[1],
+            json!({"event": "stopped", "reason": "breakpoint", "pc": 8, "line": null})
+        );
+        assert_eq!(lines[2]["requestId"], 1);
+        assert_eq!(lines[3], json!({"event": "terminated", "code": 3}));
+    }
+
+    #[test]
+    fn test_adapter_step_has_no_event() {
+        let input = format!("{}\n", json!({"command": "step", "requestId": 1}));
+        let mut output = Vec::new();
+        with_debugger("mov64 r0, 1\nexit", |mut dbg| {
+            run_adapter(&mut dbg, io::Cursor::new(input), &mut output).unwrap();
+        });
+
+        // Only DAP clients get a `stopped` event after a step.
+        let lines: Vec<Value> = output
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["data"]["type"], "step");
+    }
+
+    #[test]
+    fn test_adapter_watchpoint_event() {
+        let source = "
+            lddw r1, 0x300000000
+            mov64 r2, 7
+            stxdw [r1+8], r2
+            exit";
+        let input = format!("{}\n", json!({"command": "continue", "requestId": 1}));
+        let mut output = Vec::new();
+        with_debugger(source, |mut dbg| {
+            dbg.add_watchpoint(solana_sbpf::ebpf::MM_HEAP_START + 8, 8)
+                .unwrap();
+            run_adapter(&mut dbg, io::Cursor::new(input), &mut output).unwrap();
+        });
+
+        let lines: Vec<Value> = output
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["data"]["type"], "watchpoint");
+        assert_eq!(
+            lines[1],
+            json!({"event": "stopped", "reason": "data breakpoint", "pc": 32, "line": null})
+        );
+    }
+
+    #[test]
+    fn test_adapter_output_events() {
+        let source = "
+            mov64 r1, 42
+            call sol_log_64_
+            exit";
+        let input = format!("{}\n", json!({"command": "continue", "requestId": 1}));
+        let mut output = Vec::new();
+        with_debugger(source, |mut dbg| {
+            dbg.get_context_object_mut().set_log_sink(LogSink::Buffer);
+            run_adapter(&mut dbg, io::Cursor::new(input), &mut output).unwrap();
+        });
+
+        let lines: Vec<Value> = output
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            json!({
+                "event": "output",
+                "category": "stdout",
+                "text": "Program log: 0x2a, 0x0, 0x0, 0x0, 0x0"
+            })
+        );
+        assert_eq!(lines[2]["event"], "terminated");
+    }
+
+    #[test]
+    fn test_pause_running_continue() {
+        let source = "
+            mov64 r1, 0
+            add64 r1, 1
+            ja -2";
+        let mut input = Vec::new();
+        for (id, command) in [(1, "continue"), (2, "pause")] {
+            writeln!(input, "{}", json!({"command": command, "requestId": id})).unwrap();
+        }
+        let mut output = Vec::new();
+        with_debugger(source, |mut dbg| {
+            run_adapter(&mut dbg, io::Cursor::new(input), &mut output).unwrap();
+        });
+
+        let lines: Vec<Value> = output
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        // The pause is read while the loop runs, and acknowledged after the continue returns.
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["requestId"], 1);
+        assert_eq!(lines[0]["data"]["type"], "paused");
+        assert_eq!(lines[1]["event"], "stopped");
+        assert_eq!(lines[1]["reason"], "pause");
+        assert_eq!(lines[2]["requestId"], 2);
+        assert_eq!(lines[2]["data"]["type"], "pause");
+    }
+
+    #[test]
+    fn test_goto() {
+        with_debugger("mov64 r0, 1\nmov64 r0, 2\nexit", |mut dbg| {
+            let result = execute_command(&mut dbg, "setPc", Some(json!(["0x10"])));
+            assert_eq!(result["pc"], 16);
+            assert_eq!(dbg.get_pc(), 16);
+            let result = execute_command(&mut dbg, "goto", Some(json!({"targetId": 8})));
+            assert_eq!(result["type"], "setPc");
+            assert_eq!(dbg.get_pc(), 8);
+            let result = execute_command(&mut dbg, "goto", Some(json!({"targetId": 24})));
+            assert!(result_error(&result).unwrap().contains("outside"));
+            let result = execute_command(&mut dbg, "goto", None);
+            assert_eq!(result_error(&result).unwrap(), "Missing pc");
+            // Without line information there is nowhere to go by line.
+            let result = execute_command(&mut dbg, "gotoTargets", Some(json!({"line": 1})));
+            assert!(result_error(&result).is_some());
+        });
+    }
+
+    #[test]
+    fn test_threads() {
+        with_debugger("mov64 r0, 1\nexit", |mut dbg| {
+            let result = execute_command(&mut dbg, "threads", None);
+            assert_eq!(result, json!({"threads": [{"id": 1, "name": "sbpf"}]}));
+
+            // DAP clients pass the thread id to thread-scoped requests.
+            let args = Some(json!({"threadId": THREAD_ID}));
+            let result = execute_command(&mut dbg, "getStackFrames", args.clone());
+            assert!(result_error(&result).is_none());
+            let result = execute_command(&mut dbg, "step", args.clone());
+            assert!(result_error(&result).is_none());
+            let result = execute_command(&mut dbg, "continue", args);
+            assert_eq!(result["type"], "exit");
+        });
+    }
+
+    #[test]
+    fn test_set_register_by_name() {
+        assert_eq!(parse_register(&json!(3)), Some(RegisterName::Index(3)));
+        assert_eq!(parse_register(&json!("r3")), Some(RegisterName::Index(3)));
+        assert_eq!(parse_register(&json!("pc")), Some(RegisterName::Pc));
+        assert_eq!(parse_register(&json!("sp")), Some(RegisterName::Index(10)));
+        assert_eq!(parse_register(&json!("fp")), Some(RegisterName::Index(10)));
+        assert_eq!(parse_register(&json!("x1")), None);
+
+        with_debugger("exit", |mut dbg| {
+            let result = execute_command(&mut dbg, "setRegister", Some(json!(["r3", "0x10"])));
+            assert_eq!(result["index"], 3);
+            assert_eq!(result["value"], 16);
+
+            let result = execute_command(
+                &mut dbg,
+                "setVariable",
+                Some(json!({"name": "fp", "value": "0x20"})),
+            );
+            assert_eq!(result["name"], "fp");
+            assert_eq!(result["value"], "0x0000000000000020");
+            assert_eq!(dbg.get_register(10), Some(0x20));
+
+            let result = execute_command(&mut dbg, "setRegister", Some(json!(["pc", "0x10"])));
+            assert_eq!(result["index"], 11);
+            assert_eq!(dbg.get_register(11), Some(2));
+            assert_eq!(dbg.get_pc(), 0x10);
+            let result = execute_command(&mut dbg, "setRegister", Some(json!(["pc", 3])));
+            assert!(result_error(&result).is_some());
+
+            let result = execute_command(&mut dbg, "setRegister", Some(json!(["r12", 1])));
+            assert!(result_error(&result).is_some());
+            let result = execute_command(&mut dbg, "setVariable", Some(json!(["bogus", 1])));
+            assert_eq!(result["type"], "error");
+        });
     }
 }
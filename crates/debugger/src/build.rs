@@ -1,7 +1,8 @@
-use dirs::home_dir;
+use dirs::{cache_dir, home_dir};
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
 use tempfile::TempDir;
 use thiserror::Error;
 
@@ -11,14 +12,16 @@ pub enum BuildError {
     SolanaConfigNotFound,
     #[error("Could not find active_release_dir in Solana config")]
     ActiveReleaseDirNotFound,
-    #[error("Solana platform-tools not found. Please download the latest release from here: https://docs.solanalabs.com/cli/install")]
-    PlatformToolsNotFound,
+    #[error("Solana platform-tools not found (tried {}). Please download the latest release from here: https://docs.solanalabs.com/cli/install, or set SBPF_DBG_TOOLCHAIN to the platform-tools directory", .0.join(", "))]
+    PlatformToolsNotFound(Vec<String>),
     #[error("Invalid assembly file path")]
     InvalidAssemblyPath,
-    #[error("Compilation failed")]
-    CompilationFailed,
-    #[error("Linking failed")]
-    LinkingFailed,
+    #[error("Compilation of {file} failed:\n{diagnostics}")]
+    CompilationFailed { file: String, diagnostics: String },
+    #[error("Linking of {file} failed:\n{diagnostics}")]
+    LinkingFailed { file: String, diagnostics: String },
+    #[error("Flag '{0}' is set by the debugger and cannot be overridden")]
+    ReservedFlag(String),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     #[error("YAML parsing error: {0}")]
@@ -29,6 +32,13 @@ pub enum BuildError {
 
 pub type Result<T> = std::result::Result<T, BuildError>;
 
+/// Base address of the shared object, passed to ld with `--image-base`
+pub const DEFAULT_IMAGE_BASE: u64 = 0x100000000;
+
+/// Environment variable pointing at the platform-tools directory, used when
+/// `BuildConfig::toolchain` is not set
+pub const TOOLCHAIN_ENV: &str = "SBPF_DBG_TOOLCHAIN";
+
 pub const DEFAULT_LINKER: &str = r#"PHDRS
 {
   text    PT_LOAD    ;
@@ -57,19 +67,181 @@ ENTRY (entrypoint)"#;
 
 #[derive(Debug)]
 pub struct BuildConfig {
-    pub assembly_file: String,
+    /// Assembly (.s) or C (.c) source files, each compiled to an object file and linked together
+    /// into one shared object. The first file names the outputs.
+    pub source_files: Vec<String>,
     pub linker_file: Option<String>,
+    /// Compile with debug information (`-g`). Without it, the shared object is also stripped of
+    /// its symbols (`--strip-all`), like a release build.
     pub debug: bool,
+    /// Extra arguments for clang
+    pub clang_flags: Vec<String>,
+    /// Extra arguments for ld
+    pub ld_flags: Vec<String>,
+    /// Path to the platform-tools directory, instead of the one of the active Solana release
+    pub toolchain: Option<String>,
+    /// Base address of the shared object (`DEFAULT_IMAGE_BASE` by default). The VM addresses of
+    /// the sections only depend on it if the linker script does not place the sections itself.
+    pub image_base: u64,
+    /// Reuse the artifacts of a previous build of the same sources and settings
+    pub use_cache: bool,
 }
 
 #[derive(Debug)]
 pub struct BuildResult {
+    /// Object file of the first source file
     pub object_file: String,
+    /// Object files of all source files, in order
+    pub object_files: Vec<String>,
     pub shared_object_file: String,
-    pub _temp_dir: TempDir, // Keep the temp directory alive
+    pub _temp_dir: Option<TempDir>, // Keep the temp directory of uncached builds alive
+}
+
+/// Flags that the build sets itself, so that user flags cannot change the target or the outputs
+const RESERVED_CLANG_FLAGS: &[&str] = &["-target", "--target", "-c", "-o"];
+const RESERVED_LD_FLAGS: &[&str] = &["-shared", "-T", "--script", "-o", "--output"];
+
+fn check_flags(flags: &[String], reserved: &[&str]) -> Result<()> {
+    match flags.iter().find(|flag| {
+        reserved.iter().any(|reserved| {
+            flag.as_str() == *reserved
+                || flag
+                    .strip_prefix(reserved)
+                    .is_some_and(|value| value.starts_with('='))
+        })
+    }) {
+        Some(flag) => Err(BuildError::ReservedFlag(flag.clone())),
+        None => Ok(()),
+    }
+}
+
+impl BuildResult {
+    /// Copy the object file and the shared object to `dir`, which is created if needed, and
+    /// return the paths of the copies.
+    pub fn copy_artifacts(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        self.object_files
+            .iter()
+            .chain([&self.shared_object_file])
+            .map(|artifact| {
+                let path = dir.join(Path::new(artifact).file_name().unwrap_or_default());
+                fs::copy(artifact, &path)?;
+                Ok(path)
+            })
+            .collect()
+    }
 }
 
 pub fn build_assembly(config: &BuildConfig) -> Result<BuildResult> {
+    check_flags(&config.clang_flags, RESERVED_CLANG_FLAGS)?;
+    check_flags(&config.ld_flags, RESERVED_LD_FLAGS)?;
+
+    let (clang, ld) = find_toolchain(config)?;
+
+    // Extract the filenames without extension from the source file paths.
+    let filenames = object_names(&config.source_files)?;
+    let filename = filenames
+        .first()
+        .ok_or(BuildError::InvalidAssemblyPath)?
+        .as_str();
+
+    if let Some(cache_root) = cache_root().filter(|_| config.use_cache) {
+        let cache_dir = cache_root.join(cache_key(config, &clang)?);
+        let object_files: Vec<String> = filenames
+            .iter()
+            .map(|name| format!("{}/{}.o", cache_dir.display(), name))
+            .collect();
+        let shared_object_file = format!("{}/{}.so", cache_dir.display(), filename);
+
+        if !(object_files.iter().all(|file| Path::new(file).exists())
+            && Path::new(&shared_object_file).exists())
+        {
+            // Build next to the cache entry and move it in place once complete, so that an
+            // interrupted build never leaves a partial entry behind.
+            let temp_dir = TempDir::new_in(&cache_root)?;
+            build_in(temp_dir.path(), &filenames, config, &clang, &ld)?;
+            if let Err(err) = fs::rename(temp_dir.path(), &cache_dir) {
+                // Another build of the same sources may have won the race, keep its entry.
+                if !Path::new(&shared_object_file).exists() {
+                    return Err(err.into());
+                }
+            }
+        }
+
+        return Ok(BuildResult {
+            object_file: object_files[0].clone(),
+            object_files,
+            shared_object_file,
+            _temp_dir: None,
+        });
+    }
+
+    // Create temporary directory for build artifacts.
+    let temp_dir = TempDir::new()?;
+    let (object_files, shared_object_file) =
+        build_in(temp_dir.path(), &filenames, config, &clang, &ld)?;
+
+    Ok(BuildResult {
+        object_file: object_files[0].clone(),
+        object_files,
+        shared_object_file,
+        _temp_dir: Some(temp_dir),
+    })
+}
+
+/// Names of the object files of the source files: the file stems, with the index of the file
+/// appended to the stems that are already taken, e.g. `lib` and `lib_1` for `a/lib.s` and
+/// `b/lib.s`.
+fn object_names(source_files: &[String]) -> Result<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+    for (index, source_file) in source_files.iter().enumerate() {
+        let stem = Path::new(source_file)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or(BuildError::InvalidAssemblyPath)?;
+        let name = if names.iter().any(|name| name == stem) {
+            format!("{}_{}", stem, index)
+        } else {
+            stem.to_string()
+        };
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// Find clang and ld in the platform-tools directory given in the config or the environment, or
+/// else in the one of the active Solana release.
+fn find_toolchain(config: &BuildConfig) -> Result<(String, String)> {
+    let platform_tools = match config
+        .toolchain
+        .clone()
+        .or_else(|| std::env::var(TOOLCHAIN_ENV).ok())
+    {
+        Some(platform_tools) => platform_tools,
+        None => solana_platform_tools()?,
+    };
+
+    // Accept the LLVM directory itself too.
+    let candidates = [
+        format!("{}/llvm/bin", platform_tools),
+        format!("{}/bin", platform_tools),
+    ];
+    candidates
+        .iter()
+        .find(|bin_dir| Path::new(&format!("{}/clang", bin_dir)).exists())
+        .map(|bin_dir| (format!("{}/clang", bin_dir), format!("{}/ld.lld", bin_dir)))
+        .ok_or_else(|| {
+            BuildError::PlatformToolsNotFound(
+                candidates
+                    .iter()
+                    .map(|bin_dir| format!("{}/clang", bin_dir))
+                    .collect(),
+            )
+        })
+}
+
+/// Platform-tools directory of the active Solana release.
+fn solana_platform_tools() -> Result<String> {
     // Construct the path to the config file.
     let home_dir = home_dir().expect("Could not find $HOME directory");
     // Solana Config path.
@@ -90,35 +262,84 @@ pub fn build_assembly(config: &BuildConfig) -> Result<BuildResult> {
         .as_str()
         .ok_or_else(|| BuildError::ActiveReleaseDirNotFound)?;
 
-    let platform_tools = format!(
+    Ok(format!(
         "{}/bin/platform-tools-sdk/sbf/dependencies/platform-tools",
         active_release_dir
-    );
-    let llvm_dir = format!("{}/llvm", platform_tools);
-    let clang = format!("{}/bin/clang", llvm_dir);
-    let ld = format!("{}/bin/ld.lld", llvm_dir);
+    ))
+}
+
+/// Directory holding cached build artifacts, one subdirectory per cache key.
+fn cache_root() -> Option<PathBuf> {
+    let cache_root = cache_dir()?.join("sbpf-dbg");
+    fs::create_dir_all(&cache_root).ok()?;
+    Some(cache_root)
+}
 
-    // Check for platform tools
-    if !Path::new(&llvm_dir).exists() {
-        return Err(BuildError::PlatformToolsNotFound);
+/// Hash of everything that affects the build output: the source files (paths and contents), the
+/// linker script, the toolchain and the flags.
+fn cache_key(config: &BuildConfig, clang: &str) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for source_file in &config.source_files {
+        let source_path = fs::canonicalize(source_file)?.to_string_lossy().to_string();
+        let source = fs::read(&source_path)?;
+        // The path is part of the DWARF info, which maps instructions back to the source.
+        for field in [source_path.as_bytes(), &source] {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field);
+        }
     }
 
-    // Create temporary directory for build artifacts.
-    let temp_dir = TempDir::new()?;
-    let dbg_dir = temp_dir.path().to_string_lossy().to_string();
+    let linker = match &config.linker_file {
+        Some(linker_file) => fs::read_to_string(linker_file)?,
+        None => DEFAULT_LINKER.to_string(),
+    };
+
+    let clang_flags = config.clang_flags.join("\0");
+    let ld_flags = config.ld_flags.join("\0");
 
-    // Extract filename without extension from assembly file path.
-    let assembly_path = Path::new(&config.assembly_file);
-    let filename = assembly_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .ok_or_else(|| BuildError::InvalidAssemblyPath)?;
+    let fields: [&[u8]; 6] = [
+        linker.as_bytes(),
+        clang.as_bytes(),
+        &[config.debug as u8],
+        clang_flags.as_bytes(),
+        ld_flags.as_bytes(),
+        &config.image_base.to_le_bytes(),
+    ];
+    for field in fields {
+        hasher.update((field.len() as u64).to_le_bytes());
+        hasher.update(field);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
 
-    // Generate object file path.
-    let object_file = format!("{}/{}.o", dbg_dir, filename);
+/// Compile the source files to the object files named `filenames` and link them into `dir`,
+/// returning the paths of the object files and the shared object.
+fn build_in(
+    dir: &Path,
+    filenames: &[String],
+    config: &BuildConfig,
+    clang: &str,
+    ld: &str,
+) -> Result<(Vec<String>, String)> {
+    let dbg_dir = dir.to_string_lossy().to_string();
 
-    // Compile assembly to object file.
-    compile_assembly(&clang, &config.assembly_file, &object_file, config.debug)?;
+    // Compile each source file to an object file.
+    let mut object_files = Vec::new();
+    for (source_file, filename) in config.source_files.iter().zip(filenames) {
+        let object_file = format!("{}/{}.o", dbg_dir, filename);
+        compile_assembly(
+            clang,
+            source_file,
+            &object_file,
+            config.debug,
+            &config.clang_flags,
+        )?;
+        object_files.push(object_file);
+    }
 
     // Handle linker file.
     let linker_file = if let Some(ref custom_linker) = config.linker_file {
@@ -131,58 +352,334 @@ pub fn build_assembly(config: &BuildConfig) -> Result<BuildResult> {
     };
 
     // Generate shared object file path.
-    let shared_object_file = format!("{}/{}.so", dbg_dir, filename);
+    let shared_object_file = format!("{}/{}.so", dbg_dir, filenames[0]);
 
     // Build shared object.
-    build_shared_object(&ld, &object_file, &linker_file, &shared_object_file)?;
+    build_shared_object(
+        ld,
+        &object_files,
+        &linker_file,
+        &shared_object_file,
+        config.image_base,
+        config.debug,
+        &config.ld_flags,
+    )?;
 
-    Ok(BuildResult {
-        object_file,
-        shared_object_file,
-        _temp_dir: temp_dir,
-    })
+    Ok((object_files, shared_object_file))
+}
+
+/// Diagnostics printed by a failed clang or ld run, which point at the offending source line.
+fn diagnostics(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let diagnostics = if stderr.trim().is_empty() {
+        String::from_utf8_lossy(&output.stdout)
+    } else {
+        stderr
+    };
+    match diagnostics.trim_end() {
+        "" => format!("{} (no diagnostics)", output.status),
+        diagnostics => diagnostics.to_string(),
+    }
+}
+
+/// Whether the source file is a C file rather than assembly.
+fn is_c_source(input_file: &str) -> bool {
+    Path::new(input_file)
+        .extension()
+        .is_some_and(|extension| extension == "c")
 }
 
-fn compile_assembly(clang: &str, input_file: &str, output_file: &str, debug: bool) -> Result<()> {
-    let mut clang_args = vec!["-target", "sbf", "-c", "-o", output_file, input_file];
+/// Compile an assembly (or C) source file to an object file.
+fn compile_assembly(
+    clang: &str,
+    input_file: &str,
+    output_file: &str,
+    debug: bool,
+    flags: &[String],
+) -> Result<()> {
+    let mut clang_args = vec![];
+
+    if is_c_source(input_file) {
+        clang_args.push("-O2");
+    }
 
     if debug {
         clang_args.push("-g");
     }
 
-    let status = Command::new(clang).args(clang_args).status()?;
+    // User flags come after the defaults to override them, but before the target and outputs.
+    clang_args.extend(flags.iter().map(String::as_str));
+    clang_args.extend(["-target", "sbf", "-c", "-o", output_file, input_file]);
 
-    if !status.success() {
-        eprintln!("Failed to compile assembly file: {}", input_file);
-        return Err(BuildError::CompilationFailed);
+    let output = Command::new(clang).args(clang_args).output()?;
+
+    if !output.status.success() {
+        return Err(BuildError::CompilationFailed {
+            file: input_file.to_string(),
+            diagnostics: diagnostics(&output),
+        });
     }
 
     Ok(())
 }
 
+/// Link object files into a shared object.
 fn build_shared_object(
     ld: &str,
-    input_file: &str,
+    input_files: &[String],
     linker_file: &str,
     output_file: &str,
+    image_base: u64,
+    debug: bool,
+    flags: &[String],
 ) -> Result<()> {
-    let status = Command::new(ld)
+    let mut command = Command::new(ld);
+    if !debug {
+        command.arg("--strip-all");
+    }
+    let output = command
+        .args(flags)
         .arg("-shared")
         .arg("-z")
         .arg("notext")
         .arg("--image-base")
-        .arg("0x100000000")
+        .arg(format!("0x{:x}", image_base))
         .arg("-T")
         .arg(linker_file)
         .arg("-o")
         .arg(output_file)
-        .arg(input_file)
-        .status()?;
+        .args(input_files)
+        .output()?;
 
-    if !status.success() {
-        eprintln!("Failed to build shared object: {}", output_file);
-        return Err(BuildError::LinkingFailed);
+    if !output.status.success() {
+        return Err(BuildError::LinkingFailed {
+            file: output_file.to_string(),
+            diagnostics: diagnostics(&output),
+        });
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{build_or_skip, is_toolchain_missing, test_config};
+
+    #[test]
+    fn test_cache_key() {
+        let dir = TempDir::new().unwrap();
+        let assembly_file = dir.path().join("program.s");
+        fs::write(&assembly_file, "exit\n").unwrap();
+        let mut config = test_config(&[&assembly_file], true);
+        config.use_cache = true;
+
+        let key = cache_key(&config, "clang").unwrap();
+        assert_eq!(key.len(), 64);
+        assert_eq!(cache_key(&config, "clang").unwrap(), key);
+
+        // Any change to the inputs of the build invalidates the entry.
+        assert_ne!(cache_key(&config, "other/clang").unwrap(), key);
+        config.debug = false;
+        assert_ne!(cache_key(&config, "clang").unwrap(), key);
+        config.debug = true;
+        config.clang_flags = vec!["-DVALUE=1".to_string()];
+        assert_ne!(cache_key(&config, "clang").unwrap(), key);
+        config.clang_flags = vec![];
+        fs::write(&assembly_file, "mov64 r0, 0\nexit\n").unwrap();
+        assert_ne!(cache_key(&config, "clang").unwrap(), key);
+
+        let key = cache_key(&config, "clang").unwrap();
+        let helper_file = dir.path().join("helper.s");
+        fs::write(&helper_file, "exit\n").unwrap();
+        config
+            .source_files
+            .push(helper_file.to_string_lossy().to_string());
+        assert_ne!(cache_key(&config, "clang").unwrap(), key);
+    }
+
+    #[test]
+    fn test_object_names() {
+        let files = |files: &[&str]| {
+            files
+                .iter()
+                .map(|file| file.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            object_names(&files(&["src/main.s", "src/helper.c"])).unwrap(),
+            files(&["main", "helper"])
+        );
+        assert_eq!(
+            object_names(&files(&["a/lib.s", "b/lib.s", "main.s"])).unwrap(),
+            files(&["lib", "lib_1", "main"])
+        );
+        assert!(matches!(
+            object_names(&files(&[".."])),
+            Err(BuildError::InvalidAssemblyPath)
+        ));
+    }
+
+    #[test]
+    fn test_copy_artifacts() {
+        let build_dir = TempDir::new().unwrap();
+        let object_file = build_dir.path().join("program.o");
+        let shared_object_file = build_dir.path().join("program.so");
+        fs::write(&object_file, "object").unwrap();
+        fs::write(&shared_object_file, "shared object").unwrap();
+        let build_result = BuildResult {
+            object_file: object_file.to_string_lossy().to_string(),
+            object_files: vec![object_file.to_string_lossy().to_string()],
+            shared_object_file: shared_object_file.to_string_lossy().to_string(),
+            _temp_dir: Some(build_dir),
+        };
+
+        let keep_dir = TempDir::new().unwrap();
+        let dir = keep_dir.path().join("artifacts");
+        let copies = build_result.copy_artifacts(&dir).unwrap();
+        assert_eq!(copies, vec![dir.join("program.o"), dir.join("program.so")]);
+        assert_eq!(fs::read_to_string(&copies[1]).unwrap(), "shared object");
+    }
+
+    #[test]
+    fn test_compilation_diagnostics() {
+        let dir = TempDir::new().unwrap();
+        let source_file = dir.path().join("broken.s");
+        fs::write(
+            &source_file,
+            ".globl entrypoint\nentrypoint:\n  bogus r0, 1\n  exit\n",
+        )
+        .unwrap();
+
+        match build_assembly(&test_config(&[&source_file], true)) {
+            Err(BuildError::CompilationFailed { file, diagnostics }) => {
+                assert!(file.ends_with("broken.s"));
+                // clang reports the offending line.
+                assert!(diagnostics.contains("bogus"), "{}", diagnostics);
+            }
+            Err(err) if is_toolchain_missing(&err) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_find_toolchain() {
+        let dir = TempDir::new().unwrap();
+        let platform_tools = dir.path().to_string_lossy().to_string();
+        let mut config = test_config(&["program.s"], true);
+        config.toolchain = Some(platform_tools.clone());
+
+        match find_toolchain(&config) {
+            Err(BuildError::PlatformToolsNotFound(tried)) => assert_eq!(
+                tried,
+                vec![
+                    format!("{}/llvm/bin/clang", platform_tools),
+                    format!("{}/bin/clang", platform_tools),
+                ]
+            ),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let bin_dir = dir.path().join("llvm/bin");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("clang"), "").unwrap();
+        let (clang, ld) = find_toolchain(&config).unwrap();
+        assert_eq!(clang, format!("{}/llvm/bin/clang", platform_tools));
+        assert_eq!(ld, format!("{}/llvm/bin/ld.lld", platform_tools));
+
+        // The LLVM directory itself is accepted too.
+        config.toolchain = Some(format!("{}/llvm", platform_tools));
+        assert_eq!(find_toolchain(&config).unwrap().0, clang);
+    }
+
+    #[test]
+    fn test_reserved_flags() {
+        let flags = |flags: &[&str]| {
+            flags
+                .iter()
+                .map(|flag| flag.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(check_flags(&flags(&["-O1", "-Iinclude", "-DX=1"]), RESERVED_CLANG_FLAGS).is_ok());
+        assert!(check_flags(&flags(&["--gc-sections"]), RESERVED_LD_FLAGS).is_ok());
+        for flag in ["-o", "-c", "-target", "--target=bpfel"] {
+            assert!(matches!(
+                check_flags(&flags(&["-O1", flag]), RESERVED_CLANG_FLAGS),
+                Err(BuildError::ReservedFlag(reserved)) if reserved == flag
+            ));
+        }
+        assert!(check_flags(&flags(&["--output=out.so"]), RESERVED_LD_FLAGS).is_err());
+    }
+
+    #[test]
+    fn test_build_c_source() {
+        let dir = TempDir::new().unwrap();
+        let source_file = dir.path().join("entrypoint.c");
+        fs::write(
+            &source_file,
+            "unsigned long entrypoint(const unsigned char *input) {\n    return input[0];\n}\n",
+        )
+        .unwrap();
+
+        let Some(build_result) = build_or_skip(&test_config(&[&source_file], true)) else {
+            return;
+        };
+
+        assert!(build_result.object_file.ends_with("entrypoint.o"));
+        assert!(Path::new(&build_result.shared_object_file).exists());
+    }
+
+    #[test]
+    fn test_build_without_debug_info() {
+        use object::{Object, ObjectSection};
+
+        let dir = TempDir::new().unwrap();
+        let source_file = dir.path().join("stripped.s");
+        fs::write(
+            &source_file,
+            ".globl entrypoint\nentrypoint:\n  mov64 r0, 0\n  exit\n",
+        )
+        .unwrap();
+
+        let Some(build_result) = build_or_skip(&test_config(&[&source_file], false)) else {
+            return;
+        };
+
+        let data = fs::read(&build_result.shared_object_file).unwrap();
+        let shared_object = object::File::parse(&*data).unwrap();
+        assert!(shared_object.section_by_name(".debug_line").is_none());
+        assert!(shared_object.section_by_name(".symtab").is_none());
+        let data = fs::read(&build_result.object_file).unwrap();
+        let object = object::File::parse(&*data).unwrap();
+        assert!(object.section_by_name(".debug_line").is_none());
+    }
+
+    #[test]
+    fn test_link_multiple_files() {
+        let dir = TempDir::new().unwrap();
+        let main_file = dir.path().join("main.s");
+        let helper_file = dir.path().join("helper.s");
+        fs::write(
+            &main_file,
+            ".globl entrypoint\nentrypoint:\n  call helper\n  exit\n",
+        )
+        .unwrap();
+        fs::write(
+            &helper_file,
+            ".globl helper\nhelper:\n  mov64 r0, 42\n  exit\n",
+        )
+        .unwrap();
+
+        let Some(build_result) = build_or_skip(&test_config(&[&main_file, &helper_file], true))
+        else {
+            return;
+        };
+
+        assert_eq!(build_result.object_files.len(), 2);
+        assert!(build_result.object_file.ends_with("main.o"));
+        assert!(build_result.object_files[1].ends_with("helper.o"));
+        assert!(build_result.shared_object_file.ends_with("main.so"));
+        assert!(Path::new(&build_result.shared_object_file).exists());
+    }
+}
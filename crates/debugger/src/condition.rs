@@ -0,0 +1,151 @@
+//! Conditions of conditional breakpoints, such as `r2 == 0x10`.
+//!
+//! A condition compares a register (`r0` to `r10`, or `sp`/`fp`) with a decimal or `0x` hex
+//! constant using `==`, `!=`, `<`, `>`, `<=` or `>=`. Values are compared as unsigned.
+
+use std::{fmt, str::FromStr};
+
+use crate::debugger::RegisterName;
+
+/// A comparison operator of a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+impl Comparison {
+    /// Operators in matching order: two-character operators come before their prefixes.
+    const ALL: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Eq),
+        ("!=", Comparison::Ne),
+        ("<=", Comparison::Le),
+        (">=", Comparison::Ge),
+        ("<", Comparison::Lt),
+        (">", Comparison::Gt),
+    ];
+
+    fn symbol(&self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(_, comparison)| comparison == self)
+            .map(|(symbol, _)| *symbol)
+            .unwrap_or_default()
+    }
+
+    fn compare(&self, left: u64, right: u64) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Gt => left > right,
+            Comparison::Le => left <= right,
+            Comparison::Ge => left >= right,
+        }
+    }
+}
+
+/// A condition on a register value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    /// Index of the register
+    pub register: usize,
+    pub comparison: Comparison,
+    pub value: u64,
+}
+
+impl Condition {
+    /// Evaluate the condition against the register values.
+    pub fn evaluate(&self, registers: &[u64]) -> bool {
+        registers
+            .get(self.register)
+            .is_some_and(|&register| self.comparison.compare(register, self.value))
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (register, comparison, value) = Comparison::ALL
+            .iter()
+            .find_map(|(symbol, comparison)| {
+                s.split_once(symbol)
+                    .map(|(register, value)| (register.trim(), *comparison, value.trim()))
+            })
+            .ok_or_else(|| format!("Invalid condition '{}', expected e.g. 'r2 == 0x10'", s))?;
+        let register = match RegisterName::parse(register) {
+            Some(RegisterName::Index(index)) if index <= 10 => index,
+            _ => return Err(format!("Unknown register '{}'", register)),
+        };
+        let value = match value.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => value.parse::<u64>(),
+        }
+        .map_err(|_| format!("Invalid number '{}'", value))?;
+        Ok(Condition {
+            register,
+            comparison,
+            value,
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "r{} {} 0x{:x}",
+            self.register,
+            self.comparison.symbol(),
+            self.value
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(s: &str) -> Result<Condition, String> {
+        s.parse()
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        assert_eq!(
+            condition("r2 == 0x10"),
+            Ok(Condition {
+                register: 2,
+                comparison: Comparison::Eq,
+                value: 16
+            })
+        );
+        assert_eq!(condition("r1<=3").unwrap().comparison, Comparison::Le);
+        assert_eq!(condition("fp > 0").unwrap().register, 10);
+        assert_eq!(condition("r3 != 7").unwrap().to_string(), "r3 != 0x7");
+        assert!(condition("r2 = 1")
+            .unwrap_err()
+            .contains("Invalid condition"));
+        assert!(condition("pc == 8")
+            .unwrap_err()
+            .contains("Unknown register"));
+        assert!(condition("r1 == x").unwrap_err().contains("Invalid number"));
+    }
+
+    #[test]
+    fn test_evaluate_condition() {
+        let registers = [0, 5, 0x10];
+        assert!(condition("r2 == 0x10").unwrap().evaluate(&registers));
+        assert!(!condition("r2 != 16").unwrap().evaluate(&registers));
+        assert!(condition("r1 < 6").unwrap().evaluate(&registers));
+        assert!(!condition("r1 > 5").unwrap().evaluate(&registers));
+        assert!(condition("r1 >= 5").unwrap().evaluate(&registers));
+        // Registers that are not available never match.
+        assert!(!condition("r9 == 0").unwrap().evaluate(&registers));
+    }
+}
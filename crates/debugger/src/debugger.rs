@@ -1,17 +1,26 @@
 #![allow(dead_code)]
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use solana_sbpf::ebpf;
 use solana_sbpf::elf::Executable;
-use solana_sbpf::error::ProgramResult;
+use solana_sbpf::error::{EbpfError, ProgramResult};
 use solana_sbpf::interpreter::Interpreter;
-use solana_sbpf::vm::{ContextObject, EbpfVm};
+use solana_sbpf::memory_region::{AccessType, MemoryMapping, MemoryState};
+use solana_sbpf::static_analysis::Analysis;
+use solana_sbpf::vm::{CallFrame, ContextObject, EbpfVm};
 
-use crate::adapter::DebuggerInterface;
-use crate::error::DebuggerResult;
-use crate::parser::{LineMap, ROData};
+use crate::adapter::{json_bytes, json_u64, register_json, symbol_json, DebuggerInterface};
+use crate::condition::Condition;
+use crate::error::{DebuggerError, DebuggerResult};
+use crate::expression;
+use crate::parser::{demangle, format_symbol_content, LineMap, Symbol, VariableLocation};
+use crate::syscalls::SyscallError;
+use crate::DebugContextObject;
 
 #[derive(Debug)]
 pub enum DebugMode {
@@ -19,20 +28,206 @@ pub enum DebugMode {
     Continue,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum DebugEvent {
     Breakpoint(u64, Option<usize>), // PC and optional line number
     Step(u64, Option<usize>),       // PC and optional line number
     Exit(u64),
     Error(String),
+    LimitReached(u64, u64), // PC and number of executed instructions
+    Watchpoint(u64, Vec<u8>, Vec<u8>), // Address of the watchpoint, old and new bytes
+    Pause(u64, Option<usize>), // PC and optional line number
+}
+
+/// A memory range re-read and shown whenever execution stops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watch {
+    pub address: u64,
+    pub len: u64,
+}
+
+/// A register given by index (`r0` to `r11`, or a bare index) or by alias.
+///
+/// `sp` and `fp` are r10. `pc` is not a raw register: it is derived from r11 as
+/// `r11 * INSN_SIZE`, the byte offset shown everywhere else in the debugger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterName {
+    Index(usize),
+    Pc,
+}
+
+impl RegisterName {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "pc" => Some(Self::Pc),
+            "sp" | "fp" => Some(Self::Index(10)),
+            name => name
+                .strip_prefix('r')
+                .unwrap_or(name)
+                .parse()
+                .ok()
+                .map(Self::Index),
+        }
+    }
+
+    /// The index of the underlying register.
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Index(index) => *index,
+            Self::Pc => 11,
+        }
+    }
+}
+
+impl std::fmt::Display for RegisterName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "r{}", index),
+            Self::Pc => write!(f, "pc"),
+        }
+    }
+}
+
+/// Describe a program error raised at `pc`.
+fn program_error_message(pc: u64, err: &EbpfError) -> String {
+    match err {
+        EbpfError::ExceededMaxInstructions => format!(
+            "Program error at PC 0x{:016x}: {}",
+            pc,
+            DebuggerError::ComputationalBudgetExceeded
+        ),
+        // Aborts and panics have a readable message, including the location of the panic.
+        EbpfError::SyscallError(err) if err.is::<SyscallError>() => {
+            format!("Program error at PC 0x{:016x}: {}", pc, err)
+        }
+        err => format!("Program error at PC 0x{:016x}: {:?}", pc, err),
+    }
+}
+
+/// Number of call sites listed when the call stack depth is exceeded
+const CALL_CHAIN_LIMIT: usize = 8;
+
+/// Put `vm` at the entrypoint of `executable`, with the input address in r1 and the instruction
+/// meter starting from the remaining compute units, like `EbpfVm::execute_program` does before
+/// running a program.
+pub fn init_vm<C: ContextObject>(vm: &mut EbpfVm<C>, executable: &Executable<C>) {
+    vm.registers[1] = ebpf::MM_INPUT_START;
+    vm.registers[11] = executable.get_entrypoint_instruction_offset() as u64;
+    vm.previous_instruction_meter = vm.context_object_pointer.get_remaining();
+    vm.due_insn_count = 0;
+    vm.program_result = ProgramResult::Ok(0);
+}
+
+/// Copy the contents of the writable memory regions, by start address. Regions are read from
+/// the host memory as a whole, so that a stack with gaps between its frames is copied too.
+fn writable_memory(memory_mapping: &MemoryMapping) -> Vec<(u64, Vec<u8>)> {
+    memory_mapping
+        .get_regions()
+        .iter()
+        .filter(|region| matches!(region.state.get(), MemoryState::Writable))
+        .map(|region| {
+            let data = unsafe {
+                std::slice::from_raw_parts(region.host_addr.get() as *const u8, region.len as usize)
+            };
+            (region.vm_addr, data.to_vec())
+        })
+        .collect()
+}
+
+/// Execution state saved by [`Debugger::snapshot`] and put back by [`Debugger::restore`].
+///
+/// Besides the registers and call frames, a snapshot holds a copy of every writable memory
+/// region (stack, heap, input and `.bss`) and of the context object, except for its trace log and
+/// instruction counts: these keep covering the whole run, including the instructions executed
+/// after the snapshot, once it is restored. Taking one therefore costs about as much memory as
+/// the writable regions, which is dominated by the stack and the heap for most programs.
+#[derive(Clone)]
+pub struct Snapshot {
+    registers: [u64; 12],
+    call_depth: u64,
+    call_frames: Vec<CallFrame>,
+    due_insn_count: u64,
+    previous_instruction_meter: u64,
+    context_object: DebugContextObject,
+    /// Contents of the writable regions by start address
+    memory: Vec<(u64, Vec<u8>)>,
+    instruction_count: u64,
+    at_breakpoint: bool,
+    last_breakpoint_pc: Option<u64>,
+}
+
+impl Snapshot {
+    /// PC of the snapshot in bytes
+    pub fn pc(&self) -> u64 {
+        self.registers[11] * ebpf::INSN_SIZE as u64
+    }
+}
+
+/// A region of the VM memory map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap {
+    pub name: &'static str,
+    pub start: u64,
+    pub end: u64,
+    pub writable: bool,
+}
+
+/// Number of instructions reported by `profile` unless specified otherwise
+pub const DEFAULT_PROFILE_TOP: usize = 10;
+
+/// Execution count of an instruction, as reported by [`Debugger::get_profile`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileEntry {
+    /// PC of the instruction in bytes
+    pub pc: u64,
+    pub count: u64,
+    /// Share of all executed instructions, in percent
+    pub percent: f64,
+    pub line: Option<usize>,
+}
+
+/// Breakpoints as written by `save breakpoints`. Line and function breakpoints are kept by
+/// location so that they are resolved again when loaded into a rebuilt program.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedBreakpoints {
+    /// Line breakpoints as (file, line)
+    pub lines: Vec<(String, usize)>,
+    /// Function breakpoints by symbol
+    pub functions: Vec<String>,
+    /// PC breakpoints that are not set by a line or function breakpoint
+    pub pcs: Vec<u64>,
+}
+
+/// Detailed information about a single stepped instruction.
+#[derive(Debug, Clone)]
+pub struct StepInfo {
+    /// PC of the stepped instruction
+    pub pc: u64,
+    pub mnemonic: String,
+    pub operands: String,
+    /// Source line of the stepped instruction
+    pub line: Option<usize>,
+    /// Registers changed by the instruction as (index, old value, new value)
+    pub changed_registers: Vec<(usize, u64, u64)>,
+    /// Compute units consumed by the instruction
+    pub compute_units: u64,
+    /// Event produced by the step
+    pub event: DebugEvent,
 }
 
 pub struct Debugger<'a, 'b, C: ContextObject> {
     pub(crate) interpreter: Interpreter<'a, 'b, C>,
-    pub breakpoints: HashSet<u64>,        // PC-based breakpoints
-    pub line_breakpoints: HashSet<usize>, // Line-based breakpoints
-    pub dwarf_line_map: Option<LineMap>,  // DWARF line mapping
-    pub rodata: Option<Vec<ROData>>,
+    pub(crate) executable: &'a Executable<C>,
+    pub(crate) analysis: Option<Analysis<'a>>,
+    pub breakpoints: HashSet<u64>, // PC-based breakpoints
+    pub line_breakpoints: HashSet<(String, usize)>, // Line-based breakpoints as (file, line)
+    pub function_breakpoints: BTreeMap<String, u64>, // Function breakpoints as symbol -> entry PC
+    pub temporary_breakpoints: HashSet<u64>, // PC breakpoints removed once they are hit
+    pub disabled: HashSet<u64>,    // Breakpoints kept in the list but skipped when running
+    pub conditions: HashMap<u64, Condition>, // Breakpoints that only stop when a condition holds
+    pub dwarf_line_map: Option<LineMap>, // DWARF line mapping
+    pub rodata: Option<Vec<Symbol>>,
+    pub data: Option<Vec<Symbol>>,
     pub last_breakpoint: Option<u64>,
     pub debug_mode: DebugMode,
     pub stopped: bool,
@@ -40,19 +235,37 @@ pub struct Debugger<'a, 'b, C: ContextObject> {
     pub at_breakpoint: bool, // Whether we're currently stopped at a breakpoint
     pub last_breakpoint_pc: Option<u64>, // Last PC where we hit a breakpoint to avoid duplicates
     pub initial_compute_budget: u64, // Store the initial compute budget for tracking
+    pub max_instructions: Option<u64>, // Stop once this many instructions have been executed
+    pub instruction_count: u64, // Number of instructions executed so far
+    pub selected_frame: usize, // Stack frame selected with up/down/frame, 0 being the innermost
+    pub watches: BTreeMap<usize, Watch>, // Memory ranges shown whenever execution stops, by id
+    pub watchpoints: BTreeMap<usize, (Watch, Vec<u8>)>, // Stop when these change, with their last bytes
+    next_watch_id: usize,
+    pause_requested: Arc<AtomicBool>, // Set from another thread to interrupt a `continue`
+    initial_registers: [u64; 12],     // Registers at the entrypoint, for `restart`
+    initial_memory: Vec<(u64, Vec<u8>)>, // Writable regions before the program ran, for `restart`
 }
 
 impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
     pub fn new(vm: &'a mut EbpfVm<'b, C>, executable: &'a Executable<C>) -> Self {
         let initial_compute_budget = vm.context_object_pointer.get_remaining();
+        let initial_registers = vm.registers;
+        let initial_memory = writable_memory(&vm.memory_mapping);
         let interpreter = Interpreter::new(vm, executable, vm.registers);
 
         Self {
             interpreter: interpreter,
+            executable,
+            analysis: Analysis::from_executable(executable).ok(),
             breakpoints: HashSet::new(),
             line_breakpoints: HashSet::new(),
+            function_breakpoints: BTreeMap::new(),
+            temporary_breakpoints: HashSet::new(),
+            disabled: HashSet::new(),
+            conditions: HashMap::new(),
             dwarf_line_map: None,
             rodata: None,
+            data: None,
             last_breakpoint: None,
             debug_mode: DebugMode::Continue,
             stopped: false,
@@ -60,46 +273,260 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
             at_breakpoint: false,
             last_breakpoint_pc: None,
             initial_compute_budget,
+            max_instructions: None,
+            instruction_count: 0,
+            selected_frame: 0,
+            watches: BTreeMap::new(),
+            watchpoints: BTreeMap::new(),
+            next_watch_id: 1,
+            pause_requested: Arc::new(AtomicBool::new(false)),
+            initial_registers,
+            initial_memory,
         }
     }
 
+    /// Stop execution once `max_instructions` instructions have been executed, or never if
+    /// `None`.
+    pub fn set_max_instructions(&mut self, max_instructions: Option<u64>) {
+        self.max_instructions = max_instructions;
+    }
+
+    /// Get the flag that interrupts a running `continue` once set. It can be set from any thread;
+    /// the debugger checks it before every instruction and clears it when it pauses.
+    pub fn pause_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.pause_requested)
+    }
+
     /// Set the DWARF line mapping after construction
     pub fn set_dwarf_line_map(&mut self, dwarf_map: LineMap) {
         self.dwarf_line_map = Some(dwarf_map);
     }
 
-    pub fn set_rodata(&mut self, rodata: Vec<ROData>) {
+    pub fn set_rodata(&mut self, rodata: Vec<Symbol>) {
         self.rodata = Some(rodata);
     }
 
+    pub fn set_data(&mut self, data: Vec<Symbol>) {
+        self.data = Some(data);
+    }
+
     pub fn set_breakpoint(&mut self, pc: u64) {
         self.breakpoints.insert(pc);
     }
 
-    pub fn set_breakpoint_at_line(&mut self, line: usize) -> Result<(), String> {
-        if let Some(dwarf_map) = &self.dwarf_line_map {
-            let pcs = dwarf_map.get_pcs_for_line(line);
-            if !pcs.is_empty() {
-                self.line_breakpoints.insert(line);
-                for &pc in &pcs {
-                    self.breakpoints.insert(pc);
-                }
-            }
+    /// Set a breakpoint that is removed the first time it is hit. Does nothing if there is already
+    /// a breakpoint at `pc`.
+    pub fn set_temporary_breakpoint(&mut self, pc: u64) {
+        if self.breakpoints.insert(pc) {
+            self.temporary_breakpoints.insert(pc);
+        }
+    }
+
+    /// Set a temporary breakpoint at the first instruction of a line and return its PC.
+    pub fn set_temporary_breakpoint_at_line(
+        &mut self,
+        file: &str,
+        line: usize,
+    ) -> Result<u64, String> {
+        let file = self.resolve_breakpoint_file(file, line)?;
+        let pc = self
+            .get_pcs_for_file_line(&file, line)
+            .into_iter()
+            .min()
+            .ok_or_else(|| format!("No instructions at {}:{}", file, line))?;
+        self.set_temporary_breakpoint(pc);
+        Ok(pc)
+    }
+
+    /// Enable or disable the breakpoint at `pc`.
+    pub fn set_breakpoint_enabled(&mut self, pc: u64, enabled: bool) -> Result<(), String> {
+        if !self.breakpoints.contains(&pc) {
+            return Err(format!("No breakpoint at PC 0x{:x}", pc));
+        }
+        if enabled {
+            self.disabled.remove(&pc);
+        } else {
+            self.disabled.insert(pc);
         }
         Ok(())
     }
 
-    pub fn remove_breakpoint_at_line(&mut self, line: usize) -> Result<(), String> {
-        if let Some(dwarf_map) = &self.dwarf_line_map {
-            let pcs = dwarf_map.get_pcs_for_line(line);
-            if !pcs.is_empty() {
-                self.line_breakpoints.remove(&line);
-                for &pc in &pcs {
-                    self.breakpoints.remove(&pc);
+    /// Enable or disable the breakpoints at the instructions of a line and return their PCs.
+    pub fn set_line_breakpoint_enabled(
+        &mut self,
+        file: &str,
+        line: usize,
+        enabled: bool,
+    ) -> Result<Vec<u64>, String> {
+        let file = self.resolve_breakpoint_file(file, line)?;
+        let mut pcs: Vec<u64> = self
+            .get_pcs_for_file_line(&file, line)
+            .into_iter()
+            .filter(|pc| self.breakpoints.contains(pc))
+            .collect();
+        if pcs.is_empty() {
+            return Err(format!("No breakpoint at {}:{}", file, line));
+        }
+        pcs.sort();
+        for &pc in &pcs {
+            self.set_breakpoint_enabled(pc, enabled)?;
+        }
+        Ok(pcs)
+    }
+
+    /// Set or remove the condition of the breakpoint at `pc`.
+    pub fn set_breakpoint_condition(
+        &mut self,
+        pc: u64,
+        condition: Option<Condition>,
+    ) -> Result<(), String> {
+        if !self.breakpoints.contains(&pc) {
+            return Err(format!("No breakpoint at PC 0x{:x}", pc));
+        }
+        match condition {
+            Some(condition) => self.conditions.insert(pc, condition),
+            None => self.conditions.remove(&pc),
+        };
+        Ok(())
+    }
+
+    /// Set or remove the condition of the breakpoints at the instructions of a line.
+    pub fn set_line_breakpoint_condition(
+        &mut self,
+        file: &str,
+        line: usize,
+        condition: Option<Condition>,
+    ) -> Result<(), String> {
+        let file = self.resolve_breakpoint_file(file, line)?;
+        for pc in self.get_pcs_for_file_line(&file, line) {
+            self.set_breakpoint_condition(pc, condition)?;
+        }
+        Ok(())
+    }
+
+    /// Whether execution stops at `pc`: there is a breakpoint, it is enabled and its condition,
+    /// if any, holds.
+    fn is_breakpoint_active(&self, pc: u64) -> bool {
+        self.breakpoints.contains(&pc)
+            && !self.disabled.contains(&pc)
+            && self
+                .conditions
+                .get(&pc)
+                .map_or(true, |condition| condition.evaluate(&self.interpreter.reg))
+    }
+
+    /// Stop at the breakpoint at `pc`, removing it if it is temporary.
+    fn hit_breakpoint(&mut self, pc: u64) -> DebugEvent {
+        self.at_breakpoint = true;
+        self.last_breakpoint_pc = Some(pc);
+        if self.temporary_breakpoints.remove(&pc) {
+            self.breakpoints.remove(&pc);
+            self.conditions.remove(&pc);
+        }
+        DebugEvent::Breakpoint(pc, self.get_line_for_pc(pc))
+    }
+
+    /// Resolve the source file of a line breakpoint to its DWARF name. An empty `file` selects the
+    /// only source file with instructions at `line`.
+    fn resolve_breakpoint_file(&self, file: &str, line: usize) -> Result<String, String> {
+        let dwarf_map = self
+            .dwarf_line_map
+            .as_ref()
+            .ok_or("No DWARF line mapping available")?;
+        if !file.is_empty() {
+            return dwarf_map
+                .find_file(file)
+                .map(str::to_string)
+                .ok_or_else(|| format!("No line information for file '{}'", file));
+        }
+        match dwarf_map.get_files_for_line(line).as_slice() {
+            [] => Err(format!("No instructions at line {}", line)),
+            [file] => Ok(file.to_string()),
+            files => Err(format!(
+                "Line {} exists in several files, use <file>:<line> with one of: {}",
+                line,
+                files.join(", ")
+            )),
+        }
+    }
+
+    pub fn set_breakpoint_at_line(&mut self, file: &str, line: usize) -> Result<(), String> {
+        let file = self.resolve_breakpoint_file(file, line)?;
+        let pcs = self.get_pcs_for_file_line(&file, line);
+        if pcs.is_empty() {
+            return Err(format!("No instructions at {}:{}", file, line));
+        }
+        self.breakpoints.extend(pcs);
+        self.line_breakpoints.insert((file, line));
+        Ok(())
+    }
+
+    pub fn remove_breakpoint_at_line(&mut self, file: &str, line: usize) -> Result<(), String> {
+        let file = self.resolve_breakpoint_file(file, line)?;
+        for pc in self.get_pcs_for_file_line(&file, line) {
+            self.breakpoints.remove(&pc);
+            self.disabled.remove(&pc);
+            self.conditions.remove(&pc);
+        }
+        self.line_breakpoints.remove(&(file, line));
+        Ok(())
+    }
+
+    /// Returns the functions matching `name` as (raw symbol, entry PC), from the ELF symbol
+    /// table. Both raw and demangled names are matched, and symbols equal to `name` are preferred
+    /// over symbols containing it.
+    pub fn find_functions(&self, name: &str) -> Vec<(String, u64)> {
+        let Some(analysis) = &self.analysis else {
+            return Vec::new();
+        };
+        let functions = analysis.functions.iter().map(|(&insn_ptr, (_, symbol))| {
+            (
+                symbol,
+                demangle(symbol),
+                (insn_ptr * ebpf::INSN_SIZE) as u64,
+            )
+        });
+        let exact: Vec<_> = functions
+            .clone()
+            .filter(|(symbol, demangled, _)| *symbol == name || demangled == name)
+            .map(|(symbol, _, pc)| (symbol.clone(), pc))
+            .collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+        functions
+            .filter(|(symbol, demangled, _)| symbol.contains(name) || demangled.contains(name))
+            .map(|(symbol, _, pc)| (symbol.clone(), pc))
+            .collect()
+    }
+
+    /// Set a breakpoint at the entry of the function `name`. Returns the matched symbol and its
+    /// entry PC, or an error listing the candidates if several functions match.
+    pub fn set_breakpoint_at_function(&mut self, name: &str) -> Result<(String, u64), String> {
+        match self.find_functions(name).as_slice() {
+            [] => Err(format!("No function named {}", name)),
+            [(symbol, pc)] => {
+                self.breakpoints.insert(*pc);
+                self.function_breakpoints.insert(symbol.clone(), *pc);
+                Ok((symbol.clone(), *pc))
+            }
+            candidates => {
+                let mut message = format!("Function name {} is ambiguous, candidates:", name);
+                for (symbol, pc) in candidates {
+                    message.push_str(&format!("\n  {} (PC 0x{:x})", demangle(symbol), pc));
                 }
+                Err(message)
             }
         }
-        Ok(())
+    }
+
+    /// Remove all function breakpoints.
+    pub fn clear_function_breakpoints(&mut self) {
+        for (_, pc) in std::mem::take(&mut self.function_breakpoints) {
+            self.breakpoints.remove(&pc);
+            self.disabled.remove(&pc);
+            self.conditions.remove(&pc);
+        }
     }
 
     pub fn get_current_line(&self) -> Option<usize> {
@@ -123,6 +550,57 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
         }
     }
 
+    pub fn get_pcs_for_file_line(&self, file: &str, line: usize) -> Vec<u64> {
+        if let Some(dwarf_map) = &self.dwarf_line_map {
+            dwarf_map.get_pcs_for_file_line(file, line)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Get the current breakpoints in their saved form. Temporary breakpoints are not saved.
+    pub fn saved_breakpoints(&self) -> SavedBreakpoints {
+        let mut lines: Vec<_> = self.line_breakpoints.iter().cloned().collect();
+        lines.sort();
+        let mut resolved: HashSet<u64> = lines
+            .iter()
+            .flat_map(|(file, line)| self.get_pcs_for_file_line(file, *line))
+            .collect();
+        resolved.extend(self.function_breakpoints.values());
+        let mut pcs: Vec<_> = self
+            .breakpoints
+            .iter()
+            .filter(|pc| !resolved.contains(pc) && !self.temporary_breakpoints.contains(pc))
+            .copied()
+            .collect();
+        pcs.sort();
+        SavedBreakpoints {
+            lines,
+            functions: self.function_breakpoints.keys().cloned().collect(),
+            pcs,
+        }
+    }
+
+    /// Set saved breakpoints, resolving line and function breakpoints against the current
+    /// program. Returns an error message for each breakpoint that could not be set.
+    pub fn restore_breakpoints(&mut self, saved: &SavedBreakpoints) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (file, line) in &saved.lines {
+            if let Err(e) = self.set_breakpoint_at_line(file, *line) {
+                errors.push(e);
+            }
+        }
+        for function in &saved.functions {
+            if let Err(e) = self.set_breakpoint_at_function(function) {
+                errors.push(e);
+            }
+        }
+        for &pc in &saved.pcs {
+            self.set_breakpoint(pc);
+        }
+        errors
+    }
+
     pub fn get_breakpoints_info(&self) -> String {
         let mut info = String::new();
 
@@ -130,28 +608,55 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
         if !self.breakpoints.is_empty() {
             info.push_str("PC breakpoints:\n");
             for &pc in &self.breakpoints {
+                let mut flags = String::new();
+                if self.temporary_breakpoints.contains(&pc) {
+                    flags.push_str(" [temporary]");
+                }
+                if let Some(condition) = self.conditions.get(&pc) {
+                    flags.push_str(&format!(" if {}", condition));
+                }
+                if self.disabled.contains(&pc) {
+                    flags.push_str(" [disabled]");
+                }
                 if let Some(line) = self.get_line_for_pc(pc) {
-                    info.push_str(&format!("  PC 0x{:x} (line {})\n", pc, line));
+                    info.push_str(&format!("  PC 0x{:x} (line {}){}\n", pc, line, flags));
                 } else {
-                    info.push_str(&format!("  PC 0x{:x}\n", pc));
+                    info.push_str(&format!("  PC 0x{:x}{}\n", pc, flags));
                 }
             }
         }
 
+        // Function breakpoints.
+        if !self.function_breakpoints.is_empty() {
+            info.push_str("Function breakpoints:\n");
+            for (symbol, pc) in &self.function_breakpoints {
+                info.push_str(&format!("  {} (PC 0x{:x})\n", demangle(symbol), pc));
+            }
+        }
+
         // Line-based breakpoints.
         if !self.line_breakpoints.is_empty() {
             info.push_str("Line breakpoints:\n");
-            for &line in &self.line_breakpoints {
-                let pcs = self.get_pcs_for_line(line);
+            let mut line_breakpoints: Vec<_> = self.line_breakpoints.iter().collect();
+            line_breakpoints.sort();
+            for (file, line) in line_breakpoints {
+                let pcs = self.get_pcs_for_file_line(file, *line);
                 if !pcs.is_empty() {
-                    info.push_str(&format!("  Line {} (PCs: ", line));
+                    info.push_str(&format!("  {}:{} (PCs: ", file, line));
                     for (i, &pc) in pcs.iter().enumerate() {
                         if i > 0 {
                             info.push_str(", ");
                         }
                         info.push_str(&format!("0x{:x}", pc));
                     }
-                    info.push_str(")\n");
+                    info.push(')');
+                    if let Some(condition) = pcs.first().and_then(|pc| self.conditions.get(pc)) {
+                        info.push_str(&format!(" if {}", condition));
+                    }
+                    if pcs.iter().all(|pc| self.disabled.contains(pc)) {
+                        info.push_str(" [disabled]");
+                    }
+                    info.push('\n');
                 }
             }
         }
@@ -169,38 +674,66 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
 
     /// Consume the accumulated due_insn_count from the VM
     fn consume_instruction_cost(&mut self) {
-        let due_insn_count = self.interpreter.vm.due_insn_count;
-        if due_insn_count > 0 {
-            self.interpreter
-                .vm
-                .context_object_pointer
-                .consume(due_insn_count);
-            self.interpreter.vm.due_insn_count = 0;
+        let vm = &mut self.interpreter.vm;
+        if vm.due_insn_count > 0 {
+            vm.context_object_pointer.consume(vm.due_insn_count);
+            vm.due_insn_count = 0;
+        }
+        // The interpreter checks the due instructions against this meter, so it must track the
+        // remaining budget for the program to stop once the budget is exhausted.
+        vm.previous_instruction_meter = vm.context_object_pointer.get_remaining();
+    }
+
+    /// Execute one instruction, exposing its PC to syscalls through `vm.registers[11]`.
+    fn step_interpreter(&mut self) -> bool {
+        self.interpreter.vm.registers[11] = self.interpreter.reg[11];
+        let stepped = self.interpreter.step();
+        if stepped {
+            self.instruction_count += 1;
+        }
+        stepped
+    }
+
+    /// Get the event to stop with, without executing anything, once the instruction limit is
+    /// reached.
+    fn limit_reached(&self) -> Option<DebugEvent> {
+        match self.max_instructions {
+            Some(max) if self.instruction_count >= max => Some(DebugEvent::LimitReached(
+                self.get_pc(),
+                self.instruction_count,
+            )),
+            _ => None,
         }
     }
 
     /// Run the debugger.
     pub fn run(&mut self) -> DebuggerResult<DebugEvent> {
+        // Resuming execution selects the innermost frame again.
+        self.selected_frame = 0;
         match self.debug_mode {
             DebugMode::Step => {
+                if let Some(event) = self.limit_reached() {
+                    return Ok(event);
+                }
                 let current_pc = self.get_pc();
 
                 // If we're at a breakpoint, execute the instruction and then check for next breakpoint
                 if self.at_breakpoint {
-                    if self.interpreter.step() {
+                    if self.step_interpreter() {
                         // Consume instruction cost after successful step
                         self.consume_instruction_cost();
 
                         self.at_breakpoint = false;
                         self.last_breakpoint_pc = None; // Clear the last breakpoint PC
 
+                        if let Some(event) = self.watchpoint_hit() {
+                            return Ok(event);
+                        }
+
                         // After executing, check if the new PC has a breakpoint
                         let new_pc = self.get_pc();
-                        if self.breakpoints.contains(&new_pc) {
-                            self.at_breakpoint = true;
-                            self.last_breakpoint_pc = Some(new_pc);
-                            let line_number = self.get_line_for_pc(new_pc);
-                            return Ok(DebugEvent::Breakpoint(new_pc, line_number));
+                        if self.is_breakpoint_active(new_pc) {
+                            return Ok(self.hit_breakpoint(new_pc));
                         } else {
                             // No breakpoint at new PC, return Step event
                             let line_number = self.get_line_for_pc(new_pc);
@@ -210,8 +743,7 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
                         self.consume_instruction_cost();
                         return Ok(DebugEvent::Exit(result));
                     } else if let ProgramResult::Err(err) = &self.interpreter.vm.program_result {
-                        let error_msg =
-                            format!("Program error at PC 0x{:016x}: {:?}", current_pc, err);
+                        let error_msg = self.program_error_message(current_pc, err);
                         return Ok(DebugEvent::Error(error_msg));
                     } else {
                         let error_msg =
@@ -221,18 +753,18 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
                 }
 
                 // Check for breakpoints BEFORE executing the instruction
-                if self.breakpoints.contains(&current_pc)
+                if self.is_breakpoint_active(current_pc)
                     && self.last_breakpoint_pc != Some(current_pc)
                 {
-                    self.at_breakpoint = true;
-                    self.last_breakpoint_pc = Some(current_pc);
-                    let line_number = self.get_line_for_pc(current_pc);
-                    return Ok(DebugEvent::Breakpoint(current_pc, line_number));
+                    return Ok(self.hit_breakpoint(current_pc));
                 }
 
-                let event = if self.interpreter.step() {
+                let event = if self.step_interpreter() {
                     // Consume instruction cost after successful step
                     self.consume_instruction_cost();
+                    if let Some(event) = self.watchpoint_hit() {
+                        return Ok(event);
+                    }
 
                     let line_number = self.get_line_for_pc(current_pc);
                     DebugEvent::Step(current_pc, line_number)
@@ -240,7 +772,7 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
                     self.consume_instruction_cost();
                     DebugEvent::Exit(result)
                 } else if let ProgramResult::Err(err) = &self.interpreter.vm.program_result {
-                    let error_msg = format!("Program error at PC 0x{:016x}: {:?}", current_pc, err);
+                    let error_msg = self.program_error_message(current_pc, err);
                     DebugEvent::Error(error_msg)
                 } else {
                     let error_msg = format!("Unknown program error at PC 0x{:016x}", current_pc);
@@ -249,22 +781,31 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
                 return Ok(event);
             }
             DebugMode::Continue => loop {
+                if let Some(event) = self.limit_reached() {
+                    return Ok(event);
+                }
                 let current_pc = self.get_pc();
+                if self.pause_requested.swap(false, Ordering::Relaxed) {
+                    let line_number = self.get_line_for_pc(current_pc);
+                    return Ok(DebugEvent::Pause(current_pc, line_number));
+                }
 
                 // If we're at a breakpoint, execute the instruction and continue.
                 if self.at_breakpoint {
-                    if self.interpreter.step() {
+                    if self.step_interpreter() {
                         // Consume instruction cost after successful step
                         self.consume_instruction_cost();
 
                         self.at_breakpoint = false;
                         self.last_breakpoint_pc = None; // Clear the last breakpoint PC.
+                        if let Some(event) = self.watchpoint_hit() {
+                            return Ok(event);
+                        }
                     } else if let ProgramResult::Ok(result) = self.interpreter.vm.program_result {
                         self.consume_instruction_cost();
                         return Ok(DebugEvent::Exit(result));
                     } else if let ProgramResult::Err(err) = &self.interpreter.vm.program_result {
-                        let error_msg =
-                            format!("Program error at PC 0x{:016x}: {:?}", current_pc, err);
+                        let error_msg = self.program_error_message(current_pc, err);
                         return Ok(DebugEvent::Error(error_msg));
                     } else {
                         let error_msg =
@@ -275,25 +816,25 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
                 }
 
                 // Check for breakpoints BEFORE executing the instruction.
-                if self.breakpoints.contains(&current_pc)
+                if self.is_breakpoint_active(current_pc)
                     && self.last_breakpoint_pc != Some(current_pc)
                 {
                     // Stop at breakpoint without executing the instruction.
-                    self.at_breakpoint = true;
-                    self.last_breakpoint_pc = Some(current_pc);
-                    let line_number = self.get_line_for_pc(current_pc);
-                    return Ok(DebugEvent::Breakpoint(current_pc, line_number));
+                    return Ok(self.hit_breakpoint(current_pc));
                 }
 
                 // Execute the instruction.
-                if self.interpreter.step() {
+                if self.step_interpreter() {
                     // Consume instruction cost after successful step
                     self.consume_instruction_cost();
+                    if let Some(event) = self.watchpoint_hit() {
+                        return Ok(event);
+                    }
                 } else if let ProgramResult::Ok(result) = self.interpreter.vm.program_result {
                     self.consume_instruction_cost();
                     return Ok(DebugEvent::Exit(result));
                 } else if let ProgramResult::Err(err) = &self.interpreter.vm.program_result {
-                    let error_msg = format!("Program error at PC 0x{:016x}: {:?}", current_pc, err);
+                    let error_msg = self.program_error_message(current_pc, err);
                     return Ok(DebugEvent::Error(error_msg));
                 } else {
                     let error_msg = format!("Unknown program error at PC 0x{:016x}", current_pc);
@@ -307,6 +848,222 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
         self.interpreter.reg[11] * ebpf::INSN_SIZE as u64
     }
 
+    /// Disassemble the instruction at the given PC.
+    pub fn disassemble_instruction(&self, pc: u64) -> Option<String> {
+        let analysis = self.analysis.as_ref()?;
+        let (_, program) = self.executable.get_text_bytes();
+        let insn_pc = pc as usize / ebpf::INSN_SIZE;
+        if (insn_pc + 1) * ebpf::INSN_SIZE > program.len() {
+            return None;
+        }
+        let mut insn = ebpf::get_insn(program, insn_pc);
+        if insn.opc == ebpf::LD_DW_IMM && (insn_pc + 2) * ebpf::INSN_SIZE <= program.len() {
+            ebpf::augment_lddw_unchecked(program, &mut insn);
+        }
+        Some(analysis.disassemble_instruction(&insn, insn_pc))
+    }
+
+    /// Disassemble the function containing `pc` as (PC, instruction), or the whole program if
+    /// there is no function information.
+    pub fn disassemble_function(&self, pc: u64) -> Vec<(u64, String)> {
+        let (_, program) = self.executable.get_text_bytes();
+        let insn_ptr = pc as usize / ebpf::INSN_SIZE;
+        let mut start = 0;
+        let mut end = program.len() / ebpf::INSN_SIZE;
+        if let Some(analysis) = &self.analysis {
+            if let Some((&first, _)) = analysis.functions.range(..=insn_ptr).next_back() {
+                start = first;
+            }
+            if let Some((&next, _)) = analysis.functions.range(insn_ptr + 1..).next() {
+                end = next;
+            }
+        }
+        let mut instructions = Vec::new();
+        let mut insn_ptr = start;
+        while insn_ptr < end {
+            let pc = (insn_ptr * ebpf::INSN_SIZE) as u64;
+            let Some(disassembly) = self.disassemble_instruction(pc) else {
+                break;
+            };
+            instructions.push((pc, disassembly));
+            // `lddw` takes two slots.
+            insn_ptr += if ebpf::get_insn(program, insn_ptr).opc == ebpf::LD_DW_IMM {
+                2
+            } else {
+                1
+            };
+        }
+        instructions
+    }
+
+    /// Move the PC to `pc` without executing anything, e.g. to skip over or re-run code. The
+    /// program stays stopped at `pc`, and resuming executes the instruction there even if it has a
+    /// breakpoint. Registers and the stack are left as they are, so jumping to another function
+    /// leaves the current frame on the stack: a warning is returned in that case.
+    pub fn jump(&mut self, pc: u64) -> Result<Option<String>, String> {
+        let (_, program) = self.executable.get_text_bytes();
+        let end = program.len() as u64;
+        if pc >= end {
+            return Err(format!(
+                "PC 0x{:x} is outside the program (0x0 to 0x{:x})",
+                pc,
+                end.saturating_sub(ebpf::INSN_SIZE as u64)
+            ));
+        }
+        let from = self.get_pc();
+        self.write_register(RegisterName::Pc, pc)?;
+        self.at_breakpoint = true;
+        self.last_breakpoint_pc = Some(pc);
+        self.selected_frame = 0;
+        if self.function_start(from) == self.function_start(pc) {
+            return Ok(None);
+        }
+        let name = |pc| {
+            self.function_name_for_pc(pc)
+                .unwrap_or_else(|| format!("0x{:x}", pc))
+        };
+        Ok(Some(format!(
+            "Jumped from {} to {}, leaving the stack frame of {} in place: the stack may be \
+             inconsistent",
+            name(from),
+            name(pc),
+            name(from)
+        )))
+    }
+
+    /// Returns the PCs of the instructions of a source line, where [`Self::jump`] can go. An
+    /// empty `file` selects the only source file with instructions at `line`.
+    pub fn jump_targets(&self, file: &str, line: usize) -> Result<Vec<u64>, String> {
+        let file = self.resolve_breakpoint_file(file, line)?;
+        let mut pcs = self.get_pcs_for_file_line(&file, line);
+        if pcs.is_empty() {
+            return Err(format!("No instructions at {}:{}", file, line));
+        }
+        pcs.sort_unstable();
+        pcs.dedup();
+        Ok(pcs)
+    }
+
+    /// Returns the instruction index of the entry of the function containing `pc`.
+    fn function_start(&self, pc: u64) -> Option<usize> {
+        let insn_ptr = pc as usize / ebpf::INSN_SIZE;
+        self.analysis
+            .as_ref()?
+            .functions
+            .range(..=insn_ptr)
+            .next_back()
+            .map(|(&start, _)| start)
+    }
+
+    /// Returns the name of the function containing `pc`, from the DWARF info or else from the
+    /// symbol table.
+    pub fn function_name_for_pc(&self, pc: u64) -> Option<String> {
+        if let Some(name) = self
+            .dwarf_line_map
+            .as_ref()
+            .and_then(|dwarf| dwarf.function_for_pc(pc))
+        {
+            return Some(demangle(name));
+        }
+        let insn_ptr = pc as usize / ebpf::INSN_SIZE;
+        self.analysis
+            .as_ref()?
+            .functions
+            .range(..=insn_ptr)
+            .next_back()
+            .map(|(_, (_, name))| demangle(name))
+    }
+
+    /// Describe a program error raised at `pc`. When the call stack is full, the message lists
+    /// the call chain, innermost call first.
+    fn program_error_message(&self, pc: u64, err: &EbpfError) -> String {
+        let vm = &self.interpreter.vm;
+        let max_call_depth = self.executable.get_config().max_call_depth;
+        let call_depth = vm.call_depth as usize;
+        if !matches!(err, EbpfError::CallDepthExceeded) && call_depth < max_call_depth {
+            return program_error_message(pc, err);
+        }
+
+        let mut message = format!(
+            "Program error at PC 0x{:016x}: {} (maximum {} frames). Call chain, innermost first:",
+            pc,
+            DebuggerError::CallDepthExceeded,
+            max_call_depth
+        );
+        // Each frame holds the return address, right after its call instruction.
+        let call_sites = vm.call_frames[..call_depth.min(vm.call_frames.len())]
+            .iter()
+            .rev()
+            .map(|frame| frame.target_pc.saturating_sub(1) * ebpf::INSN_SIZE as u64);
+        for call_pc in call_sites.clone().take(CALL_CHAIN_LIMIT) {
+            let function = self
+                .function_name_for_pc(call_pc)
+                .unwrap_or_else(|| "?".to_string());
+            message.push_str(&format!("\n  {} at PC 0x{:016x}", function, call_pc));
+        }
+        let hidden = call_sites.count().saturating_sub(CALL_CHAIN_LIMIT);
+        if hidden > 0 {
+            message.push_str(&format!("\n  ... {} more", hidden));
+        }
+        message
+    }
+
+    /// Step until the source line changes, a function is called or returns, or execution stops
+    /// for another reason, and report the new location. Without line information for the
+    /// current PC, this steps a single instruction.
+    pub fn step_line(&mut self) -> DebuggerResult<DebugEvent> {
+        let start_line = self.get_current_line();
+        let start_depth = self.interpreter.vm.call_depth;
+        self.set_debug_mode(DebugMode::Step);
+        loop {
+            let event = self.run()?;
+            if start_line.is_none() || !matches!(event, DebugEvent::Step(..)) {
+                return Ok(event);
+            }
+            let line = self.get_current_line();
+            if line != start_line || self.interpreter.vm.call_depth != start_depth {
+                return Ok(DebugEvent::Step(self.get_pc(), line));
+            }
+        }
+    }
+
+    /// Step a single instruction and report what it did.
+    pub fn step_detailed(&mut self) -> DebuggerResult<StepInfo> {
+        let pc = self.get_pc();
+        let disassembly = self.disassemble_instruction(pc).unwrap_or_default();
+        let (mnemonic, operands) = disassembly
+            .split_once(' ')
+            .unwrap_or((disassembly.as_str(), ""));
+        let (mnemonic, operands) = (mnemonic.to_string(), operands.trim().to_string());
+        let line = self.get_line_for_pc(pc);
+        let registers_before = self.interpreter.reg;
+        let remaining_before = self.interpreter.vm.context_object_pointer.get_remaining();
+
+        self.set_debug_mode(DebugMode::Step);
+        let event = self.run()?;
+
+        // r11 holds the PC, which changes on every step.
+        let changed_registers = registers_before[..11]
+            .iter()
+            .zip(&self.interpreter.reg[..11])
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(i, (&old, &new))| (i, old, new))
+            .collect();
+        let compute_units = remaining_before
+            .saturating_sub(self.interpreter.vm.context_object_pointer.get_remaining());
+
+        Ok(StepInfo {
+            pc,
+            mnemonic,
+            operands,
+            line,
+            changed_registers,
+            compute_units,
+            event,
+        })
+    }
+
     /// Check if DWARF line mapping is available
     pub fn has_line_mapping(&self) -> bool {
         self.dwarf_line_map.is_some()
@@ -341,31 +1098,474 @@ impl<'a, 'b, C: ContextObject> Debugger<'a, 'b, C> {
         }
     }
 
-    pub fn get_rodata(&self) -> Option<&Vec<ROData>> {
+    /// Returns the value of a register by name, with `pc` in bytes.
+    pub fn read_register(&self, register: RegisterName) -> Option<u64> {
+        match register {
+            RegisterName::Index(idx) => self.get_register(idx),
+            RegisterName::Pc => Some(self.get_pc()),
+        }
+    }
+
+    /// Sets the value of a register by name. `pc` takes a byte address, which must be
+    /// a multiple of the instruction size.
+    pub fn write_register(&mut self, register: RegisterName, value: u64) -> Result<(), String> {
+        match register {
+            RegisterName::Index(idx) => self.set_register(idx, value),
+            RegisterName::Pc => {
+                let insn_size = ebpf::INSN_SIZE as u64;
+                if value % insn_size != 0 {
+                    return Err(format!(
+                        "PC 0x{:x} is not a multiple of the instruction size ({})",
+                        value, insn_size
+                    ));
+                }
+                self.set_register(11, value / insn_size)
+            }
+        }
+    }
+
+    pub fn get_rodata(&self) -> Option<&Vec<Symbol>> {
         self.rodata.as_ref()
     }
+
+    /// Returns the `.data` symbols with their current values, or their initial values if the
+    /// symbol is not mapped.
+    pub fn get_data(&self) -> Option<Vec<Symbol>> {
+        self.data.as_ref().map(|symbols| {
+            symbols
+                .iter()
+                .map(|symbol| {
+                    let mut symbol = symbol.clone();
+                    if let Ok(bytes) = self.read_memory(symbol.address, symbol.size) {
+                        symbol.content = format_symbol_content(bytes, symbol.kind);
+                    }
+                    symbol
+                })
+                .collect()
+        })
+    }
+
+    /// Returns the context object of the VM.
+    pub fn get_context_object(&self) -> &C {
+        &*self.interpreter.vm.context_object_pointer
+    }
+
+    /// Returns the context object of the VM for modification.
+    pub fn get_context_object_mut(&mut self) -> &mut C {
+        &mut *self.interpreter.vm.context_object_pointer
+    }
+
+    /// Reads `len` bytes of VM memory starting at `addr`.
+    pub fn read_memory(&self, addr: u64, len: u64) -> Result<&[u8], String> {
+        if len == 0 {
+            return Ok(&[]);
+        }
+        match self
+            .interpreter
+            .vm
+            .memory_mapping
+            .map(AccessType::Load, addr, len)
+        {
+            ProgramResult::Ok(host_addr) => {
+                Ok(unsafe { std::slice::from_raw_parts(host_addr as *const u8, len as usize) })
+            }
+            ProgramResult::Err(err) => Err(format!(
+                "Cannot read {} bytes at 0x{:016x}: {:?}",
+                len, addr, err
+            )),
+        }
+    }
+
+    /// Write `data` at `addr`. The whole range must be mapped and writable.
+    pub fn write_memory(&mut self, addr: u64, data: &[u8]) -> Result<(), String> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        match self
+            .interpreter
+            .vm
+            .memory_mapping
+            .map(AccessType::Store, addr, data.len() as u64)
+        {
+            ProgramResult::Ok(host_addr) => {
+                unsafe {
+                    std::ptr::copy_nonoverlapping(data.as_ptr(), host_addr as *mut u8, data.len())
+                };
+                Ok(())
+            }
+            ProgramResult::Err(err) => Err(format!(
+                "Cannot write {} bytes at 0x{:016x}: {:?}",
+                data.len(),
+                addr,
+                err
+            )),
+        }
+    }
+
+    /// Watch `len` bytes at `address`, and return the id of the watch.
+    pub fn add_watch(&mut self, address: u64, len: u64) -> Result<usize, String> {
+        if len == 0 {
+            return Err("Watch length must be greater than 0".to_string());
+        }
+        self.read_memory(address, len)?;
+        let id = self.next_watch_id;
+        self.next_watch_id += 1;
+        self.watches.insert(id, Watch { address, len });
+        Ok(id)
+    }
+
+    /// Stop execution whenever the `len` bytes at `address` change, and return the id of the
+    /// watchpoint. Watches and watchpoints share their ids.
+    pub fn add_watchpoint(&mut self, address: u64, len: u64) -> Result<usize, String> {
+        if len == 0 {
+            return Err("Watch length must be greater than 0".to_string());
+        }
+        let value = self.read_memory(address, len)?.to_vec();
+        let id = self.next_watch_id;
+        self.next_watch_id += 1;
+        self.watchpoints.insert(id, (Watch { address, len }, value));
+        Ok(id)
+    }
+
+    /// Remove the watch or watchpoint with the given id.
+    pub fn remove_watch(&mut self, id: usize) -> Result<(), String> {
+        if self.watchpoints.remove(&id).is_some() {
+            return Ok(());
+        }
+        self.watches
+            .remove(&id)
+            .map(|_| ())
+            .ok_or_else(|| format!("No watch {}", id))
+    }
+
+    /// Check the watchpoints after an instruction, and report the first one whose bytes changed.
+    fn watchpoint_hit(&mut self) -> Option<DebugEvent> {
+        // Reading memory after every instruction is slow, so skip it without watchpoints.
+        if self.watchpoints.is_empty() {
+            return None;
+        }
+        let (id, new) = self.watchpoints.iter().find_map(|(id, (watch, old))| {
+            let new = self.read_memory(watch.address, watch.len).ok()?;
+            (new != old.as_slice()).then(|| (*id, new.to_vec()))
+        })?;
+        let (watch, value) = self.watchpoints.get_mut(&id)?;
+        let old = std::mem::replace(value, new.clone());
+        Some(DebugEvent::Watchpoint(watch.address, old, new))
+    }
+
+    /// Evaluate an expression over the registers and memory (see [`crate::expression`]).
+    pub fn evaluate(&self, expr: &str) -> Result<u64, String> {
+        expression::evaluate(expr, self.get_registers(), |address| {
+            self.read_memory(address, 8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        })
+    }
+
+    /// Returns the local variables and parameters in scope at the current PC with their values.
+    ///
+    /// Variables in registers or at an offset from a register (such as stack slots relative to the
+    /// frame pointer) are supported. Other locations are reported as errors.
+    pub fn get_locals(&self) -> Vec<(String, Result<u64, String>)> {
+        let Some(dwarf_map) = &self.dwarf_line_map else {
+            return Vec::new();
+        };
+        dwarf_map
+            .get_variables_for_pc(self.get_pc())
+            .into_iter()
+            .map(|variable| {
+                let size = variable.size.unwrap_or(8);
+                let value = if size > 8 {
+                    Err(format!("{}-byte values are not supported", size))
+                } else {
+                    match variable.location {
+                        VariableLocation::Register(register) => self
+                            .get_register(register as usize)
+                            .map(|value| value & (u64::MAX >> (64 - 8 * size.max(1))))
+                            .ok_or_else(|| format!("Unknown register {}", register)),
+                        VariableLocation::RegisterOffset(register, offset) => self
+                            .get_register(register as usize)
+                            .ok_or_else(|| format!("Unknown register {}", register))
+                            .and_then(|base| {
+                                self.read_memory(base.wrapping_add(offset as u64), size)
+                            })
+                            .map(|bytes| {
+                                let mut value = [0u8; 8];
+                                value[..bytes.len()].copy_from_slice(bytes);
+                                u64::from_le_bytes(value)
+                            }),
+                        VariableLocation::Unsupported => {
+                            Err("Unsupported location expression".to_string())
+                        }
+                    }
+                };
+                (variable.name.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Returns the serialized program input mapped at `MM_INPUT_START`.
+    pub fn get_input(&self) -> Option<&[u8]> {
+        let region = self
+            .interpreter
+            .vm
+            .memory_mapping
+            .get_regions()
+            .iter()
+            .find(|region| region.vm_addr == ebpf::MM_INPUT_START && region.len > 0)?;
+        self.read_memory(region.vm_addr, region.len).ok()
+    }
+
+    /// Returns the mapped memory regions sorted by address.
+    pub fn get_memory_maps(&self) -> Vec<MemoryMap> {
+        let mut maps: Vec<MemoryMap> = self
+            .interpreter
+            .vm
+            .memory_mapping
+            .get_regions()
+            .iter()
+            .filter(|region| region.len > 0)
+            .map(|region| {
+                let writable = matches!(region.state.get(), MemoryState::Writable);
+                let name = match region.vm_addr {
+                    ebpf::MM_STACK_START => "stack",
+                    ebpf::MM_HEAP_START => "heap",
+                    ebpf::MM_INPUT_START => "input",
+                    ebpf::MM_RODATA_START => "program",
+                    // The .bss region is the only writable one next to the program.
+                    _ if writable => ".bss",
+                    _ => "program",
+                };
+                MemoryMap {
+                    name,
+                    start: region.vm_addr,
+                    end: region.vm_addr_end,
+                    writable,
+                }
+            })
+            .collect();
+        maps.sort_by_key(|map| map.start);
+        maps
+    }
+}
+
+impl<'a, 'b> Debugger<'a, 'b, DebugContextObject> {
+    /// Save the execution state, to go back to it later with [`Debugger::restore`].
+    pub fn snapshot(&self) -> Snapshot {
+        let vm = &self.interpreter.vm;
+        let memory = writable_memory(&vm.memory_mapping);
+        Snapshot {
+            registers: self.interpreter.reg,
+            call_depth: vm.call_depth,
+            call_frames: vm.call_frames.clone(),
+            due_insn_count: vm.due_insn_count,
+            previous_instruction_meter: vm.previous_instruction_meter,
+            context_object: vm.context_object_pointer.clone_without_trace(),
+            memory,
+            instruction_count: self.instruction_count,
+            at_breakpoint: self.at_breakpoint,
+            last_breakpoint_pc: self.last_breakpoint_pc,
+        }
+    }
+
+    /// Put the VM back in the state saved by [`Debugger::snapshot`]. Breakpoints are kept.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), String> {
+        let vm = &mut self.interpreter.vm;
+        // Check every region first, so that a failed restore leaves the memory untouched.
+        let regions = snapshot
+            .memory
+            .iter()
+            .map(|(address, data)| {
+                vm.memory_mapping
+                    .get_regions()
+                    .iter()
+                    .find(|region| {
+                        region.vm_addr == *address
+                            && region.len == data.len() as u64
+                            && matches!(region.state.get(), MemoryState::Writable)
+                    })
+                    .map(|region| (region.host_addr.get(), data))
+                    .ok_or_else(|| {
+                        format!("Writable region at 0x{:x} is not mapped anymore", address)
+                    })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        for (host_addr, data) in regions {
+            unsafe {
+                std::ptr::copy_nonoverlapping(data.as_ptr(), host_addr as *mut u8, data.len());
+            }
+        }
+        vm.call_depth = snapshot.call_depth;
+        vm.call_frames = snapshot.call_frames.clone();
+        vm.due_insn_count = snapshot.due_insn_count;
+        vm.previous_instruction_meter = snapshot.previous_instruction_meter;
+        vm.registers[11] = snapshot.registers[11];
+        vm.context_object_pointer
+            .restore_without_trace(&snapshot.context_object);
+        self.interpreter.reg = snapshot.registers;
+        self.instruction_count = snapshot.instruction_count;
+        self.at_breakpoint = snapshot.at_breakpoint;
+        self.last_breakpoint_pc = snapshot.last_breakpoint_pc;
+        self.selected_frame = 0;
+        Ok(())
+    }
+
+    /// Run the program again from its entrypoint, without relaunching the debugger.
+    ///
+    /// The registers, the writable memory (stack, heap, input and `.bss`) and the compute meter go
+    /// back to their state before the program ran, and the trace, logs, return data and other run
+    /// state of the context object are cleared. Breakpoints, watches and watchpoints are kept.
+    pub fn restart(&mut self) {
+        let vm = &mut self.interpreter.vm;
+        for region in vm.memory_mapping.get_regions() {
+            let initial = self.initial_memory.iter().find(|(address, data)| {
+                *address == region.vm_addr && data.len() as u64 == region.len
+            });
+            if let Some((_, data)) = initial {
+                unsafe {
+                    std::ptr::copy_nonoverlapping(
+                        data.as_ptr(),
+                        region.host_addr.get() as *mut u8,
+                        data.len(),
+                    );
+                }
+            }
+        }
+        vm.context_object_pointer.reset();
+        vm.registers = self.initial_registers;
+        vm.call_depth = 0;
+        init_vm(vm, self.executable);
+        self.interpreter.reg = vm.registers;
+        self.initial_compute_budget = vm.previous_instruction_meter;
+        self.instruction_count = 0;
+        self.at_breakpoint = false;
+        self.last_breakpoint_pc = None;
+        self.selected_frame = 0;
+        self.stopped = false;
+        self.exit_code = 0;
+        self.pause_requested.store(false, Ordering::Relaxed);
+        // Watchpoints compare against the memory as it is now.
+        let watchpoints: Vec<_> = self.watchpoints.keys().copied().collect();
+        for id in watchpoints {
+            let watch = self.watchpoints[&id].0;
+            let bytes = self
+                .read_memory(watch.address, watch.len)
+                .map(<[u8]>::to_vec)
+                .unwrap_or_default();
+            self.watchpoints.insert(id, (watch, bytes));
+        }
+    }
+
+    /// Returns the stack frames as listed by the adapter's `getStackFrames`, innermost first.
+    pub fn get_frames(&self) -> Vec<Value> {
+        let mut stack = DebuggerInterface::get_stack_frames(self);
+        match stack["frames"].take() {
+            Value::Array(frames) => frames,
+            _ => Vec::new(),
+        }
+    }
+
+    /// Select the stack frame `index` of [`Debugger::get_frames`]. The selected frame only
+    /// changes the location that source commands show: the registers are not saved per frame,
+    /// so they always hold the values of the innermost frame.
+    pub fn select_frame(&mut self, index: usize) -> Result<(), String> {
+        let count = self.get_frames().len();
+        if index >= count {
+            return Err(format!(
+                "No frame {}, the stack has {} frame{}",
+                index,
+                count,
+                if count == 1 { "" } else { "s" }
+            ));
+        }
+        self.selected_frame = index;
+        Ok(())
+    }
+
+    /// Returns the PC of the selected stack frame.
+    pub fn get_selected_pc(&self) -> u64 {
+        self.get_frames()
+            .get(self.selected_frame)
+            .and_then(|frame| frame["instruction"].as_u64())
+            .unwrap_or_else(|| self.get_pc())
+    }
+
+    /// Get the `top` most executed instructions, most executed first, along with the total
+    /// number of executed instructions.
+    pub fn get_profile(&self, top: usize) -> (Vec<ProfileEntry>, u64) {
+        let counts = self
+            .interpreter
+            .vm
+            .context_object_pointer
+            .get_instruction_counts();
+        let total: u64 = counts.values().sum();
+        let mut counts: Vec<(u64, u64)> = counts
+            .iter()
+            .map(|(&index, &count)| (index * ebpf::INSN_SIZE as u64, count))
+            .collect();
+        counts
+            .sort_by(|(pc_a, count_a), (pc_b, count_b)| count_b.cmp(count_a).then(pc_a.cmp(pc_b)));
+        let entries = counts
+            .into_iter()
+            .take(top)
+            .map(|(pc, count)| ProfileEntry {
+                pc,
+                count,
+                percent: count as f64 * 100.0 / total as f64,
+                line: self.get_line_for_pc(pc),
+            })
+            .collect();
+        (entries, total)
+    }
+
+    /// Reset the compute meter to the initial budget without touching registers or memory.
+    pub fn reset_compute_meter(&mut self) {
+        let vm = &mut self.interpreter.vm;
+        vm.context_object_pointer.reset_compute_meter();
+        vm.due_insn_count = 0;
+        vm.previous_instruction_meter = vm.context_object_pointer.get_remaining();
+        self.initial_compute_budget = vm.previous_instruction_meter;
+    }
 }
 
-impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
+impl<'a, 'b> DebuggerInterface for Debugger<'a, 'b, DebugContextObject> {
     fn step(&mut self) -> Value {
         self.set_debug_mode(DebugMode::Step);
         match self.run() {
             Ok(event) => match event {
                 DebugEvent::Step(pc, line) => json!({
                     "type": "step",
-                    "pc": pc,
+                    "pc": json_u64(pc),
                     "line": line
                 }),
                 DebugEvent::Breakpoint(pc, line) => json!({
                     "type": "breakpoint",
-                    "pc": pc,
+                    "pc": json_u64(pc),
                     "line": line
                 }),
                 DebugEvent::Exit(code) => json!({
                     "type": "exit",
-                    "code": code,
+                    "code": json_u64(code),
                     "compute_units": self.get_compute_units()
                 }),
+                DebugEvent::LimitReached(pc, count) => json!({
+                    "type": "limitReached",
+                    "pc": json_u64(pc),
+                    "line": self.get_line_for_pc(pc),
+                    "count": json_u64(count)
+                }),
+                DebugEvent::Watchpoint(address, old, new) => json!({
+                    "type": "watchpoint",
+                    "pc": json_u64(self.get_pc()),
+                    "line": self.get_line_for_pc(self.get_pc()),
+                    "address": json_u64(address),
+                    "old": json_bytes(&old),
+                    "new": json_bytes(&new)
+                }),
+                DebugEvent::Pause(pc, line) => json!({
+                    "type": "paused",
+                    "pc": json_u64(pc),
+                    "line": line
+                }),
                 DebugEvent::Error(msg) => json!({
                     "type": "error",
                     "message": msg
@@ -384,19 +1584,38 @@ impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
             Ok(event) => match event {
                 DebugEvent::Step(pc, line) => json!({
                     "type": "step",
-                    "pc": pc,
+                    "pc": json_u64(pc),
                     "line": line
                 }),
                 DebugEvent::Breakpoint(pc, line) => json!({
                     "type": "breakpoint",
-                    "pc": pc,
+                    "pc": json_u64(pc),
                     "line": line
                 }),
                 DebugEvent::Exit(code) => json!({
                     "type": "exit",
-                    "code": code,
+                    "code": json_u64(code),
                     "compute_units": self.get_compute_units()
                 }),
+                DebugEvent::LimitReached(pc, count) => json!({
+                    "type": "limitReached",
+                    "pc": json_u64(pc),
+                    "line": self.get_line_for_pc(pc),
+                    "count": json_u64(count)
+                }),
+                DebugEvent::Watchpoint(address, old, new) => json!({
+                    "type": "watchpoint",
+                    "pc": json_u64(self.get_pc()),
+                    "line": self.get_line_for_pc(self.get_pc()),
+                    "address": json_u64(address),
+                    "old": json_bytes(&old),
+                    "new": json_bytes(&new)
+                }),
+                DebugEvent::Pause(pc, line) => json!({
+                    "type": "paused",
+                    "pc": json_u64(pc),
+                    "line": line
+                }),
                 DebugEvent::Error(msg) => json!({
                     "type": "error",
                     "message": msg
@@ -410,7 +1629,7 @@ impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
     }
 
     fn set_breakpoint(&mut self, file: String, line: usize) -> Value {
-        match self.set_breakpoint_at_line(line) {
+        match self.set_breakpoint_at_line(&file, line) {
             Ok(()) => json!({
                 "type": "setBreakpoint",
                 "file": file,
@@ -428,7 +1647,7 @@ impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
     }
 
     fn remove_breakpoint(&mut self, file: String, line: usize) -> Value {
-        match self.remove_breakpoint_at_line(line) {
+        match self.remove_breakpoint_at_line(&file, line) {
             Ok(()) => json!({
                 "type": "removeBreakpoint",
                 "file": file,
@@ -445,36 +1664,76 @@ impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
         }
     }
 
-    fn clear_breakpoints(&mut self, _file: String) -> Value {
-        // Remove all line-based breakpoints.
+    fn clear_breakpoints(&mut self, file: String) -> Value {
+        // Remove the line-based breakpoints of the file, or of all files if none is given.
         if let Some(dwarf_map) = &self.dwarf_line_map {
-            let lines: Vec<usize> = self.line_breakpoints.iter().copied().collect();
-            for line in lines {
-                let pcs = dwarf_map.get_pcs_for_line(line);
+            let file = if file.is_empty() {
+                None
+            } else {
+                Some(dwarf_map.find_file(&file).unwrap_or(&file).to_string())
+            };
+            let lines: Vec<(String, usize)> = self
+                .line_breakpoints
+                .iter()
+                .filter(|(f, _)| file.as_ref().map_or(true, |file| f == file))
+                .cloned()
+                .collect();
+            for (f, line) in lines {
+                let pcs = dwarf_map.get_pcs_for_file_line(&f, line);
                 for pc in pcs {
                     self.breakpoints.remove(&pc);
+                    self.disabled.remove(&pc);
+                    self.conditions.remove(&pc);
                 }
-                self.line_breakpoints.remove(&line);
+                self.line_breakpoints.remove(&(f, line));
             }
         } else {
             self.breakpoints.clear();
             self.line_breakpoints.clear();
+            self.function_breakpoints.clear();
+            self.temporary_breakpoints.clear();
+            self.disabled.clear();
+            self.conditions.clear();
         }
         json!({"result": "ok"})
     }
 
+    fn set_function_breakpoint(&mut self, name: String) -> Value {
+        match self.set_breakpoint_at_function(&name) {
+            Ok((symbol, pc)) => json!({
+                "type": "setFunctionBreakpoint",
+                "name": name,
+                "symbol": symbol,
+                "pc": pc,
+                "line": self.get_line_for_pc(pc),
+                "verified": true
+            }),
+            Err(e) => json!({
+                "type": "setFunctionBreakpoint",
+                "name": name,
+                "verified": false,
+                "error": e
+            }),
+        }
+    }
+
+    fn clear_function_breakpoints(&mut self) -> Value {
+        self.clear_function_breakpoints();
+        json!({"result": "ok"})
+    }
+
     fn get_stack_frames(&self) -> Value {
         let vm = &self.interpreter.vm;
         let mut frames = Vec::new();
         let dwarf_map = self.dwarf_line_map.as_ref();
-        let mut index = 0;
 
         // Helper to get function name, file, line, and column from PC.
         let lookup = |pc: u64| {
             if let Some(dwarf) = dwarf_map {
+                let function = dwarf.function_for_pc(pc).map(str::to_string);
                 // Try to get source location
-                if let Some(loc) = dwarf.get_source_location(pc) {
-                    let name = format!("{}", loc.file);
+                if let Some(loc) = dwarf.get_source_location_for_pc(pc) {
+                    let name = function.unwrap_or_else(|| loc.file.clone());
                     let file = loc.file.clone();
                     let line = loc.line as usize;
                     let column = loc.column as usize;
@@ -482,44 +1741,57 @@ impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
                 }
                 // Fallback to just line..
                 if let Some(line) = dwarf.get_line_for_pc(pc) {
-                    return ("?".to_string(), "?".to_string(), line, 0);
+                    let name = function.unwrap_or_else(|| "?".to_string());
+                    return (name, "?".to_string(), line, 0);
+                }
+                if let Some(name) = function {
+                    return (name, "?".to_string(), 0, 0);
                 }
             }
             ("?".to_string(), "?".to_string(), 0, 0)
         };
 
+        // Helper to add the frames of a PC: the functions inlined at the PC, innermost first, then
+        // the physical frame. Inline frames have no registers of their own, they share those of the
+        // physical frame.
+        let push_frames = |pc: u64, frames: &mut Vec<Value>| {
+            let (name, mut file, mut line, mut column) = lookup(pc);
+            if let Some(dwarf) = dwarf_map {
+                for inline in dwarf.get_inline_frames(pc) {
+                    frames.push(json!({
+                        "index": frames.len(),
+                        "name": demangle(&inline.name),
+                        "rawName": inline.name,
+                        "file": file,
+                        "line": line,
+                        "column": column,
+                        "instruction": json_u64(pc),
+                        "inlined": true
+                    }));
+                    // The caller continues at the call site of the inlined function.
+                    file = inline.call_file.clone();
+                    line = inline.call_line;
+                    column = 0;
+                }
+            }
+            frames.push(json!({
+                "index": frames.len(),
+                "name": demangle(&name),
+                "rawName": name,
+                "file": file,
+                "line": line,
+                "column": column,
+                "instruction": json_u64(pc)
+            }));
+        };
+
         // Add the current frame first (top of stack)
-        let current_pc = self.get_pc();
-        let (name, file, line, column) = lookup(current_pc);
-        frames.push(json!({
-            "index": index,
-            "name": name,
-            "file": file,
-            "line": line,
-            "column": column,
-            "instruction": current_pc
-        }));
-        index += 1;
+        push_frames(self.get_pc(), &mut frames);
 
         // Add call frames in reverse order (oldest first)
         if vm.call_depth > 0 {
-            for (_i, frame) in vm.call_frames[..vm.call_depth as usize]
-                .iter()
-                .enumerate()
-                .rev()
-            {
-                let pc = frame.target_pc;
-                let pc_bytes = pc * ebpf::INSN_SIZE as u64;
-                let (name, file, line, column) = lookup(pc_bytes);
-                frames.push(json!({
-                    "index": index,
-                    "name": name,
-                    "file": file,
-                    "line": line,
-                    "column": column,
-                    "instruction": pc_bytes
-                }));
-                index += 1;
+            for frame in vm.call_frames[..vm.call_depth as usize].iter().rev() {
+                push_frames(frame.target_pc * ebpf::INSN_SIZE as u64, &mut frames);
             }
         }
 
@@ -527,16 +1799,12 @@ impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
     }
 
     fn get_registers(&self) -> Value {
-        let registers = self.get_registers();
-        let mut regs = Vec::new();
-
-        for (i, &value) in registers.iter().enumerate() {
-            regs.push(json!({
-                "name": format!("r{}", i),
-                "value": format!("0x{:016x}", value),
-                "type": "u64"
-            }));
-        }
+        let regs: Vec<_> = self
+            .get_registers()
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| register_json(RegisterName::Index(i), value))
+            .collect();
 
         json!({
             "registers": regs
@@ -544,33 +1812,135 @@ impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
     }
 
     fn get_memory(&self, address: u64, size: usize) -> Value {
-        // For now, return empty memory data
-        // TODO: should probably read from input register
+        match self.read_memory(address, size as u64) {
+            Ok(data) => json!({
+                "address": json_u64(address),
+                "size": size,
+                "data": data
+            }),
+            Err(e) => json!({
+                "type": "error",
+                "message": e
+            }),
+        }
+    }
+
+    fn get_memory_map(&self) -> Value {
+        let regions: Vec<Value> = self
+            .get_memory_maps()
+            .iter()
+            .map(|map| {
+                json!({
+                    "name": map.name,
+                    "start": json_u64(map.start),
+                    "end": json_u64(map.end),
+                    "length": map.end - map.start,
+                    "writable": map.writable
+                })
+            })
+            .collect();
         json!({
-            "address": address,
-            "size": size,
-            "data": []
+            "type": "memoryMap",
+            "regions": regions
         })
     }
 
-    fn set_register(&mut self, index: usize, value: u64) -> Value {
-        match self.set_register(index, value) {
+    fn evaluate(&self, expression: String) -> Value {
+        match self.evaluate(&expression) {
+            Ok(value) => json!({
+                "type": "evaluate",
+                "expression": expression,
+                "value": json_u64(value),
+                "result": format!("0x{:x}", value)
+            }),
+            Err(e) => json!({
+                "type": "error",
+                "expression": expression,
+                "message": e
+            }),
+        }
+    }
+
+    fn set_register(&mut self, register: RegisterName, value: u64) -> Value {
+        let index = register.index();
+        match self.write_register(register, value) {
             Ok(()) => json!({
                 "type": "setRegister",
                 "index": index,
-                "value": value,
+                "value": json_u64(value),
                 "success": true
             }),
             Err(e) => json!({
                 "type": "setRegister",
                 "index": index,
-                "value": value,
+                "value": json_u64(value),
                 "success": false,
                 "error": e
             }),
         }
     }
 
+    fn set_pc(&mut self, pc: u64) -> Value {
+        match self.jump(pc) {
+            Ok(warning) => json!({
+                "type": "setPc",
+                "pc": json_u64(pc),
+                "line": self.get_line_for_pc(pc),
+                "warning": warning
+            }),
+            Err(e) => json!({
+                "type": "error",
+                "message": e
+            }),
+        }
+    }
+
+    fn get_goto_targets(&self, file: String, line: usize) -> Value {
+        match self.jump_targets(&file, line) {
+            Ok(pcs) => {
+                // Target ids are the PCs, so that `goto` takes them as they are.
+                let targets: Vec<Value> = pcs
+                    .iter()
+                    .map(|&pc| {
+                        json!({
+                            "id": json_u64(pc),
+                            "label": format!("0x{:x}", pc),
+                            "line": line,
+                            "instructionPointerReference": format!("0x{:x}", pc)
+                        })
+                    })
+                    .collect();
+                json!({ "targets": targets })
+            }
+            Err(e) => json!({
+                "type": "error",
+                "message": e
+            }),
+        }
+    }
+
+    fn restart(&mut self) -> Value {
+        self.restart();
+        let pc = self.get_pc();
+        json!({
+            "type": "restart",
+            "pc": json_u64(pc),
+            "line": self.get_line_for_pc(pc)
+        })
+    }
+
+    fn pause(&mut self) -> Value {
+        // A pause that arrives while the program is not running has nothing to interrupt.
+        self.pause_requested.store(false, Ordering::Relaxed);
+        json!({
+            "type": "pause"
+        })
+    }
+
+    fn pause_handle(&self) -> Arc<AtomicBool> {
+        self.pause_handle()
+    }
+
     fn quit(&mut self) -> Value {
         json!({
             "type": "quit"
@@ -579,22 +1949,37 @@ impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
 
     fn get_rodata(&self) -> Value {
         if let Some(rodata_syms) = self.get_rodata() {
-            let arr: Vec<_> = rodata_syms
-                .iter()
-                .map(|sym| {
-                    json!({
-                        "name": sym.name,
-                        "address": format!("0x{:016x}", sym.address),
-                        "value": sym.content,
-                    })
-                })
-                .collect();
+            let arr: Vec<_> = rodata_syms.iter().map(symbol_json).collect();
             json!({ "rodata": arr })
         } else {
             json!({ "rodata": [] })
         }
     }
 
+    fn get_data(&self) -> Value {
+        let symbols: Vec<_> = self
+            .get_data()
+            .unwrap_or_default()
+            .iter()
+            .map(symbol_json)
+            .collect();
+        json!({ "data": symbols })
+    }
+
+    fn get_capabilities(&self) -> Value {
+        let sbpf_version = self.executable.get_sbpf_version();
+        let config = self.executable.get_config();
+        json!({
+            "type": "capabilities",
+            "sbpfVersion": format!("{:?}", sbpf_version),
+            "dynamicStackFrames": sbpf_version.dynamic_stack_frames(),
+            "enableStackFrameGaps": config.enable_stack_frame_gaps,
+            "stackFrameSize": config.stack_frame_size,
+            "maxCallDepth": config.max_call_depth,
+            "enableInstructionMeter": config.enable_instruction_meter,
+        })
+    }
+
     fn get_compute_units(&self) -> Value {
         let context = &self.interpreter.vm.context_object_pointer;
         let remaining = context.get_remaining();
@@ -602,9 +1987,861 @@ impl<'a, 'b, C: ContextObject> DebuggerInterface for Debugger<'a, 'b, C> {
         let used = total.saturating_sub(remaining);
 
         json!({
-            "total": total,
-            "used": used,
-            "remaining": remaining,
+            "total": json_u64(total),
+            "used": json_u64(used),
+            "remaining": json_u64(remaining),
+        })
+    }
+
+    fn get_profile(&self, top: usize) -> Value {
+        let (entries, total) = self.get_profile(top);
+        let entries: Vec<Value> = entries
+            .iter()
+            .map(|entry| {
+                json!({
+                    "pc": json_u64(entry.pc),
+                    "count": json_u64(entry.count),
+                    "percent": entry.percent,
+                    "line": entry.line,
+                })
+            })
+            .collect();
+        json!({
+            "profile": entries,
+            "total": json_u64(total),
+        })
+    }
+
+    fn get_variables(&self) -> Value {
+        let variables: Vec<Value> = self
+            .get_locals()
+            .into_iter()
+            .map(|(name, value)| match value {
+                Ok(value) => json!({
+                    "name": name,
+                    "value": format!("0x{:x}", value),
+                }),
+                Err(e) => json!({
+                    "name": name,
+                    "value": null,
+                    "message": e,
+                }),
+            })
+            .collect();
+        json!({ "variables": variables })
+    }
+
+    fn get_syscall_trace(&self) -> Value {
+        let trace: Vec<Value> = self
+            .get_context_object()
+            .get_syscall_trace()
+            .iter()
+            .map(|entry| {
+                json!({
+                    "name": entry.name,
+                    "pc": json_u64(entry.pc),
+                    "args": entry.args.map(json_u64),
+                    "returnValue": entry.return_value.map(json_u64),
+                })
+            })
+            .collect();
+        json!({ "syscalls": trace })
+    }
+
+    fn take_logs(&mut self) -> Vec<String> {
+        self.get_context_object_mut().take_logs()
+    }
+
+    fn get_logs(&self) -> Value {
+        let context = self.get_context_object();
+        json!({
+            "logs": context.get_logs(),
+            "dropped": json_u64(context.get_logs_dropped())
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        with_debugger, with_debugger_and_bss, with_debugger_and_input, BSS_START,
+    };
+    use crate::{LogSink, LOG_CAPACITY};
+
+    #[test]
+    fn test_syscall_trace() {
+        let source = "
+            mov64 r1, 42
+            call sol_log_64_
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(_)));
+            let trace = dbg.get_syscall_trace();
+            assert_eq!(trace["syscalls"][0]["name"], "sol_log_64_");
+            assert_eq!(trace["syscalls"][0]["pc"], 8);
+            assert_eq!(trace["syscalls"][0]["args"][0], 42);
+            assert_eq!(trace["syscalls"][0]["returnValue"], 0);
+        });
+    }
+
+    #[test]
+    fn test_logs() {
+        let source = "
+            mov64 r1, 1
+            call sol_log_64_
+            mov64 r1, 2
+            call sol_log_64_
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.get_context_object_mut().set_log_sink(LogSink::Buffer);
+            dbg.set_debug_mode(DebugMode::Step);
+            for _ in 0..2 {
+                dbg.run().unwrap();
+            }
+            assert_eq!(dbg.take_logs().len(), 1);
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(_)));
+            // Taking logs does not remove them from the history.
+            assert_eq!(dbg.take_logs().len(), 1);
+            let logs = DebuggerInterface::get_logs(&dbg);
+            assert_eq!(logs["logs"].as_array().unwrap().len(), 2);
+            assert!(logs["logs"][1]
+                .as_str()
+                .unwrap()
+                .starts_with("Program log: 0x2"));
+        });
+    }
+
+    #[test]
+    fn test_log_capacity() {
+        with_debugger("exit", |mut dbg| {
+            let context = dbg.get_context_object_mut();
+            context.set_log_sink(LogSink::Buffer);
+            for i in 0..LOG_CAPACITY + 2 {
+                context.log(format!("Program log: {}", i));
+            }
+            // The oldest lines are dropped, taken or not.
+            assert_eq!(context.get_logs().len(), LOG_CAPACITY);
+            assert_eq!(context.get_logs()[0], "Program log: 2");
+            assert_eq!(context.get_logs_dropped(), 2);
+            assert_eq!(dbg.take_logs().len(), LOG_CAPACITY);
+            assert_eq!(DebuggerInterface::get_logs(&dbg)["dropped"], 2);
+        });
+    }
+
+    #[test]
+    fn test_log_channel() {
+        let source = "
+            mov64 r1, 42
+            call sol_log_64_
+            exit";
+        let (sender, receiver) = std::sync::mpsc::channel();
+        with_debugger(source, |mut dbg| {
+            dbg.get_context_object_mut()
+                .set_log_sink(LogSink::Channel(sender));
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(_)));
+            // Sent logs are not buffered again, but stay in the history.
+            assert!(dbg.take_logs().is_empty());
+            assert_eq!(dbg.get_context_object().get_logs().len(), 1);
+        });
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            ["Program log: 0x2a, 0x0, 0x0, 0x0, 0x0"]
+        );
+    }
+
+    #[test]
+    fn test_profile() {
+        let source = "
+            mov64 r1, 3
+            sub64 r1, 1
+            jne r1, 0, -2
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(_)));
+
+            let (entries, total) = dbg.get_profile(2);
+            assert_eq!(total, 8);
+            let counts: Vec<_> = entries
+                .iter()
+                .map(|entry| (entry.pc, entry.count))
+                .collect();
+            assert_eq!(counts, vec![(8, 3), (16, 3)]);
+            assert_eq!(entries[0].percent, 37.5);
+
+            let profile = DebuggerInterface::get_profile(&dbg, DEFAULT_PROFILE_TOP);
+            assert_eq!(profile["total"], 8);
+            assert_eq!(profile["profile"].as_array().unwrap().len(), 4);
+            assert_eq!(profile["profile"][3]["pc"], 24);
+        });
+    }
+
+    #[test]
+    fn test_trace_limit() {
+        let source = "
+            mov64 r1, 3
+            sub64 r1, 1
+            jne r1, 0, -2
+            exit";
+        for (limit, expected) in [(3, vec![1, 2, 3]), (0, vec![])] {
+            with_debugger(source, |mut dbg| {
+                dbg.get_context_object_mut().set_trace_limit(limit);
+                dbg.set_debug_mode(DebugMode::Continue);
+                assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(_)));
+
+                // Only the most recent instructions are kept.
+                let pcs: Vec<u64> = dbg
+                    .get_context_object()
+                    .get_trace_log()
+                    .iter()
+                    .map(|state| state[11])
+                    .collect();
+                assert_eq!(pcs, expected);
+                // The profile still covers the whole run.
+                assert_eq!(dbg.get_profile(DEFAULT_PROFILE_TOP).1, 8);
+            });
+        }
+    }
+
+    #[test]
+    fn test_instruction_limit() {
+        let source = "
+            mov64 r1, 1
+            add64 r1, 1
+            ja -2";
+        with_debugger(source, |mut dbg| {
+            dbg.set_max_instructions(Some(5));
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::LimitReached(8, 5)));
+            // The VM stays stopped with its state intact.
+            assert_eq!(dbg.get_register(1), Some(3));
+            dbg.set_debug_mode(DebugMode::Step);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::LimitReached(8, 5)));
+
+            let result = dbg.r#continue();
+            assert_eq!(result["type"], "limitReached");
+            assert_eq!(result["count"], 5);
+        });
+    }
+
+    #[test]
+    fn test_restart() {
+        let source = "
+            ldxdw r2, [r10 - 8]
+            add64 r2, 1
+            stxdw [r10 - 8], r2
+            lddw r3, 0x300000000
+            ldxdw r4, [r3]
+            add64 r4, 2
+            stxdw [r3], r4
+            mov64 r0, r2
+            add64 r0, r4
+            exit";
+        with_debugger(source, |mut dbg| {
+            let registers = *dbg.get_registers();
+            dbg.set_breakpoint(0x38);
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(
+                dbg.run().unwrap(),
+                DebugEvent::Breakpoint(0x38, _)
+            ));
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(3)));
+            let used = dbg.initial_compute_budget - dbg.get_context_object().get_remaining();
+            assert!(used > 0);
+
+            // The stack and the heap are zeroed again, so the second run exits with the same code.
+            dbg.restart();
+            assert_eq!(dbg.get_pc(), 0);
+            assert_eq!(dbg.get_registers(), &registers);
+            assert_eq!(dbg.instruction_count, 0);
+            assert!(dbg.get_context_object().get_trace_log().is_empty());
+            assert!(matches!(
+                dbg.run().unwrap(),
+                DebugEvent::Breakpoint(0x38, _)
+            ));
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(3)));
+            assert_eq!(
+                dbg.initial_compute_budget - dbg.get_context_object().get_remaining(),
+                used
+            );
+
+            let result = DebuggerInterface::restart(&mut dbg);
+            assert_eq!(result["type"], "restart");
+            assert_eq!(result["pc"], 0);
+        });
+    }
+
+    #[test]
+    fn test_jump() {
+        let source = "
+            mov64 r1, 1
+            call function_foo
+            exit
+            function_foo:
+            mov64 r1, 2
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_breakpoint(8);
+            // Jumping within a function is fine; the instruction at the target runs on resume,
+            // even though it has a breakpoint.
+            assert_eq!(dbg.jump(8), Ok(None));
+            assert_eq!(dbg.get_pc(), 8);
+            assert_eq!(dbg.get_register(11), Some(1));
+            dbg.set_debug_mode(DebugMode::Step);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Step(0x18, _)));
+            assert_eq!(dbg.get_pc(), 0x18);
+
+            let warning = dbg.jump(0).unwrap().unwrap();
+            assert!(warning.contains("function_foo"), "{}", warning);
+            assert!(warning.contains("inconsistent"), "{}", warning);
+
+            assert!(dbg.jump(0x28).unwrap_err().contains("outside the program"));
+            assert!(dbg.jump(4).unwrap_err().contains("multiple"));
+            assert_eq!(dbg.get_pc(), 0);
+
+            let result = dbg.set_pc(0x10);
+            assert_eq!(result["type"], "setPc");
+            assert_eq!(result["pc"], 0x10);
+            assert_eq!(result["warning"], Value::Null);
+            assert_eq!(dbg.set_pc(0x100)["type"], "error");
+        });
+    }
+
+    #[test]
+    fn test_pause() {
+        let source = "
+            mov64 r1, 0
+            add64 r1, 1
+            ja -2";
+        with_debugger(source, |mut dbg| {
+            dbg.set_debug_mode(DebugMode::Continue);
+            let pause = dbg.pause_handle();
+            pause.store(true, Ordering::Relaxed);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Pause(0, _)));
+            // The flag is cleared once the debugger has paused.
+            assert!(!pause.load(Ordering::Relaxed));
+
+            dbg.set_max_instructions(Some(10));
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::LimitReached(..)));
+            pause.store(true, Ordering::Relaxed);
+            let result = DebuggerInterface::pause(&mut dbg);
+            assert_eq!(result["type"], "pause");
+            assert!(!pause.load(Ordering::Relaxed));
+        });
+    }
+
+    #[test]
+    fn test_large_u64_values() {
+        let source = "
+            lddw r0, 0x1000000000000001
+            exit";
+        with_debugger(source, |mut dbg| {
+            let value = 0x2000000000000003;
+            let response = DebuggerInterface::set_register(&mut dbg, RegisterName::Index(1), value);
+            assert_eq!(response["value"], "0x2000000000000003");
+            let registers = DebuggerInterface::get_registers(&dbg);
+            assert_eq!(registers["registers"][1]["value"], "0x2000000000000003");
+            assert_eq!(registers["registers"][1]["signed"], 0x2000000000000003i64);
+            assert_eq!(
+                registers["registers"][1]["binary"],
+                "0b10000000000000000000000000000000000000000000000000000000000011"
+            );
+            assert_eq!(registers["registers"][1]["ascii"], "....... ");
+
+            let event = dbg.r#continue();
+            assert_eq!(event["type"], "exit");
+            assert_eq!(event["code"], "0x1000000000000001");
+            assert!(event["compute_units"]["used"].is_u64());
+        });
+    }
+
+    #[test]
+    fn test_exit_event_shape() {
+        let source = "
+            mov64 r0, 3
+            exit";
+        let stepped = with_debugger(source, |mut dbg| loop {
+            let event = DebuggerInterface::step(&mut dbg);
+            if event["type"] != "step" {
+                break event;
+            }
+        });
+        let continued = with_debugger(source, |mut dbg| dbg.r#continue());
+        assert_eq!(stepped["type"], "exit");
+        assert_eq!(stepped["code"], 3);
+        assert!(continued["compute_units"]["used"].is_u64());
+        // Both paths report the same exit, including the compute units.
+        assert_eq!(stepped, continued);
+    }
+
+    #[test]
+    fn test_data_current_values() {
+        let symbol = |name: &str, address: u64| Symbol {
+            name: name.to_string(),
+            address,
+            size: 2,
+            kind: object::SymbolKind::Data,
+            content: "01 02".to_string(),
+        };
+        let mut input = [0x2a, 0x00];
+        with_debugger_and_input("exit", &mut input, |mut dbg| {
+            dbg.set_data(vec![
+                symbol("mapped", ebpf::MM_INPUT_START),
+                symbol("unmapped", 0x600000000),
+            ]);
+            let data = dbg.get_data().unwrap();
+            // Mapped globals show their current value, others their initial value.
+            assert_eq!(data[0].content, "2a 00");
+            assert_eq!(data[1].content, "01 02");
+
+            let response = DebuggerInterface::get_data(&dbg);
+            assert_eq!(response["data"][0]["name"], "mapped");
+            assert_eq!(response["data"][0]["size"], 2);
+        });
+    }
+
+    #[test]
+    fn test_bss_region() {
+        let source = format!(
+            "
+            lddw r1, 0x{:x}
+            ldxdw r0, [r1+0]
+            add64 r0, 1
+            stxdw [r1+0], r0
+            exit",
+            BSS_START
+        );
+        let mut bss = [0u8; 8];
+        with_debugger_and_bss(&source, &mut bss, |mut dbg| {
+            let maps = dbg.get_memory_maps();
+            let bss_map = maps.iter().find(|map| map.name == ".bss").unwrap();
+            assert_eq!(bss_map.start..bss_map.end, BSS_START..BSS_START + 8);
+            assert!(bss_map.writable);
+            assert!(maps
+                .iter()
+                .any(|map| map.name == "program" && !map.writable));
+            let regions = DebuggerInterface::get_memory_map(&dbg)["regions"].clone();
+            assert!(regions
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|region| region["name"] == ".bss"
+                    && region["length"] == 8
+                    && region["writable"] == true));
+
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(1)));
+        });
+        assert_eq!(bss, 1u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut input = 0x1122u64.to_le_bytes();
+        with_debugger_and_input("exit", &mut input, |dbg| {
+            assert_eq!(dbg.evaluate("*[r1] + 1"), Ok(0x1123));
+
+            let response = DebuggerInterface::evaluate(&dbg, "*[r1 + 8]".to_string());
+            assert_eq!(response["type"], "error");
+            assert!(response["message"]
+                .as_str()
+                .unwrap()
+                .contains("Cannot read"));
+
+            let memory = DebuggerInterface::get_memory(&dbg, ebpf::MM_INPUT_START, 2);
+            assert_eq!(memory["data"], json!([0x22, 0x11]));
+        });
+    }
+
+    #[test]
+    fn test_line_breakpoints_per_file() {
+        // Two compilation units with instructions at the same line number.
+        let mut line_map = LineMap::new();
+        line_map.add_row(0x0, "/src/a.s".to_string(), 10, 0);
+        line_map.add_row(0x8, "/src/a.s".to_string(), 11, 0);
+        line_map.add_row(0x10, "/src/b.s".to_string(), 10, 0);
+        line_map.add_row(0x18, "/src/b.s".to_string(), 12, 0);
+
+        with_debugger("exit", |mut dbg| {
+            dbg.set_dwarf_line_map(line_map);
+            assert_eq!(dbg.get_pcs_for_file_line("/src/a.s", 10), vec![0x0]);
+            assert_eq!(dbg.get_pcs_for_file_line("/src/b.s", 10), vec![0x10]);
+
+            dbg.set_breakpoint_at_line("b.s", 10).unwrap();
+            assert_eq!(dbg.breakpoints, HashSet::from([0x10]));
+            assert!(dbg.line_breakpoints.contains(&("/src/b.s".to_string(), 10)));
+
+            // A bare line number must be unambiguous.
+            assert!(dbg.set_breakpoint_at_line("", 10).is_err());
+            dbg.set_breakpoint_at_line("", 11).unwrap();
+            assert!(dbg.breakpoints.contains(&0x8));
+
+            dbg.remove_breakpoint_at_line("/src/b.s", 10).unwrap();
+            assert_eq!(dbg.breakpoints, HashSet::from([0x8]));
+        });
+    }
+
+    #[test]
+    fn test_save_and_restore_breakpoints() {
+        let mut line_map = LineMap::new();
+        line_map.add_row(0x0, "/src/a.s".to_string(), 10, 0);
+        line_map.add_row(0x8, "/src/a.s".to_string(), 11, 0);
+        line_map.add_row(0x10, "/src/a.s".to_string(), 12, 0);
+        // The same source after an instruction was added before line 11.
+        let mut rebuilt_line_map = LineMap::new();
+        rebuilt_line_map.add_row(0x0, "/src/a.s".to_string(), 10, 0);
+        rebuilt_line_map.add_row(0x8, "/src/a.s".to_string(), 10, 0);
+        rebuilt_line_map.add_row(0x10, "/src/a.s".to_string(), 11, 0);
+
+        let source = "mov64 r0, 0\nmov64 r0, 1\nmov64 r0, 2\nexit";
+        let saved = with_debugger(source, |mut dbg| {
+            dbg.set_dwarf_line_map(line_map);
+            dbg.set_breakpoint_at_line("a.s", 11).unwrap();
+            dbg.set_breakpoint(0x18);
+            dbg.set_temporary_breakpoint(0x10);
+            dbg.saved_breakpoints()
+        });
+        assert_eq!(
+            saved,
+            SavedBreakpoints {
+                lines: vec![("/src/a.s".to_string(), 11)],
+                functions: Vec::new(),
+                pcs: vec![0x18],
+            }
+        );
+        let json = serde_json::to_string(&saved).unwrap();
+        let loaded: SavedBreakpoints = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, saved);
+
+        with_debugger(source, |mut dbg| {
+            dbg.set_dwarf_line_map(rebuilt_line_map);
+            assert!(dbg.restore_breakpoints(&loaded).is_empty());
+            assert_eq!(dbg.breakpoints, HashSet::from([0x10, 0x18]));
+            assert_eq!(dbg.saved_breakpoints(), saved);
+
+            let missing = SavedBreakpoints {
+                lines: vec![("/src/a.s".to_string(), 99)],
+                ..Default::default()
+            };
+            assert_eq!(dbg.restore_breakpoints(&missing).len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_step_line() {
+        let mut line_map = LineMap::new();
+        line_map.add_row(0x0, "/src/a.s".to_string(), 10, 0);
+        line_map.add_row(0x8, "/src/a.s".to_string(), 10, 0);
+        line_map.add_row(0x10, "/src/a.s".to_string(), 11, 0);
+        line_map.add_row(0x18, "/src/a.s".to_string(), 11, 0);
+        line_map.add_row(0x20, "/src/a.s".to_string(), 12, 0);
+        let source = "
+            mov64 r1, 1
+            mov64 r2, 2
+            call function_foo
+            function_foo:
+            mov64 r0, 0
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_dwarf_line_map(line_map);
+            assert!(matches!(
+                dbg.step_line().unwrap(),
+                DebugEvent::Step(0x10, Some(11))
+            ));
+            // The callee is on the same line as the caller, but entering it stops the step.
+            assert!(matches!(
+                dbg.step_line().unwrap(),
+                DebugEvent::Step(0x18, Some(11))
+            ));
+            assert_eq!(dbg.interpreter.vm.call_depth, 1);
+        });
+
+        // Without line information, a single instruction is stepped.
+        with_debugger("mov64 r1, 1\nexit", |mut dbg| {
+            assert!(matches!(
+                dbg.step_line().unwrap(),
+                DebugEvent::Step(0x0, None)
+            ));
+            assert_eq!(dbg.get_pc(), 8);
+        });
+    }
+
+    #[test]
+    fn test_function_breakpoints() {
+        let source = "
+            call function_foo
+            call function_bar
+            exit
+            function_foo:
+            mov64 r0, 2
+            exit
+            function_bar:
+            mov64 r0, 3
+            exit";
+        with_debugger(source, |mut dbg| {
+            assert_eq!(
+                dbg.find_functions("foo"),
+                vec![("function_foo".to_string(), 24)]
+            );
+            assert!(dbg.find_functions("baz").is_empty());
+            let err = dbg.set_breakpoint_at_function("function_").unwrap_err();
+            assert!(err.contains("function_foo") && err.contains("function_bar"));
+
+            let result =
+                DebuggerInterface::set_function_breakpoint(&mut dbg, "function_bar".into());
+            assert_eq!(result["pc"], 40);
+            assert_eq!(result["verified"], true);
+            assert!(dbg
+                .get_breakpoints_info()
+                .contains("function_bar (PC 0x28)"));
+
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Breakpoint(40, _)));
+
+            DebuggerInterface::clear_function_breakpoints(&mut dbg);
+            assert!(dbg.breakpoints.is_empty());
+            assert!(dbg.function_breakpoints.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_temporary_breakpoints() {
+        let source = "
+            mov64 r1, 0
+            add64 r1, 1
+            jlt r1, 3, -2
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_temporary_breakpoint(8);
+            assert!(dbg.get_breakpoints_info().contains("PC 0x8 [temporary]"));
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Breakpoint(8, _)));
+            assert!(dbg.breakpoints.is_empty());
+            assert!(dbg.temporary_breakpoints.is_empty());
+            // The loop comes back to PC 8 without stopping again.
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(_)));
+            assert_eq!(dbg.get_register(1), Some(3));
+
+            // An existing breakpoint is not made temporary.
+            dbg.set_breakpoint(16);
+            dbg.set_temporary_breakpoint(16);
+            assert!(dbg.temporary_breakpoints.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_disabled_breakpoints() {
+        let source = "
+            mov64 r1, 1
+            mov64 r2, 2
+            mov64 r3, 3
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_breakpoint(8);
+            dbg.set_breakpoint(16);
+            assert!(dbg.set_breakpoint_enabled(24, false).is_err());
+            dbg.set_breakpoint_enabled(8, false).unwrap();
+            assert!(dbg.get_breakpoints_info().contains("PC 0x8 [disabled]"));
+
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Breakpoint(16, _)));
+            // Disabled breakpoints stay in the list.
+            assert!(dbg.breakpoints.contains(&8));
+
+            dbg.set_breakpoint_enabled(8, true).unwrap();
+            assert!(!dbg.get_breakpoints_info().contains("[disabled]"));
+        });
+    }
+
+    #[test]
+    fn test_conditional_breakpoints() {
+        let source = "
+            mov64 r2, 0
+            add64 r2, 1
+            jlt r2, 5, -2
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_breakpoint(16);
+            let condition = "r2 == 3".parse().unwrap();
+            dbg.set_breakpoint_condition(16, Some(condition)).unwrap();
+            assert!(dbg.set_breakpoint_condition(8, Some(condition)).is_err());
+            assert!(dbg.get_breakpoints_info().contains("PC 0x10 if r2 == 0x3"));
+
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Breakpoint(16, _)));
+            assert_eq!(dbg.get_register(2), Some(3));
+
+            dbg.set_breakpoint_condition(16, None).unwrap();
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Breakpoint(16, _)));
+            assert_eq!(dbg.get_register(2), Some(4));
+        });
+    }
+
+    #[test]
+    fn test_capabilities() {
+        with_debugger("exit", |dbg| {
+            let capabilities = dbg.get_capabilities();
+            assert_eq!(capabilities["sbpfVersion"], "V0");
+            assert_eq!(capabilities["dynamicStackFrames"], false);
+        });
+    }
+
+    #[test]
+    fn test_reset_compute_meter() {
+        let program = "
+            mov64 r0, 0
+            mov64 r1, 1
+            mov64 r2, 2
+            exit";
+        with_debugger(program, |mut dbg| {
+            dbg.set_debug_mode(DebugMode::Step);
+            dbg.run().unwrap();
+            dbg.run().unwrap();
+            assert_eq!(dbg.get_compute_units()["used"], 2);
+
+            dbg.reset_compute_meter();
+            assert_eq!(dbg.get_compute_units()["used"], 0);
+            assert_eq!(dbg.get_register(1), Some(1));
+            assert_eq!(dbg.get_pc(), 2 * 8);
+        });
+    }
+
+    #[test]
+    fn test_compute_budget_exhausted() {
+        let source = "
+            mov64 r1, 1
+            add64 r1, 1
+            ja -2";
+        with_debugger(source, |mut dbg| {
+            // Leave a budget of 5 compute units.
+            let remaining = dbg.get_context_object().get_remaining();
+            dbg.get_context_object_mut().consume(remaining - 5);
+            dbg.set_debug_mode(DebugMode::Continue);
+            match dbg.run().unwrap() {
+                DebugEvent::Error(message) => {
+                    assert!(
+                        message.contains("Computational budget exceeded"),
+                        "{}",
+                        message
+                    )
+                }
+                event => panic!("Unexpected event: {:?}", event),
+            }
+            assert_eq!(dbg.instruction_count, 5);
+            assert_eq!(dbg.get_context_object().get_remaining(), 0);
+        });
+    }
+
+    #[test]
+    fn test_call_depth_exceeded() {
+        let source = "
+            call function_recurse
+            exit
+            function_recurse:
+            call function_recurse
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_debug_mode(DebugMode::Continue);
+            match dbg.run().unwrap() {
+                DebugEvent::Error(message) => {
+                    assert!(message.contains("Call stack depth exceeded"), "{}", message);
+                    assert!(message.contains("maximum 64 frames"), "{}", message);
+                    assert!(
+                        message.contains("function_recurse at PC 0x0000000000000010"),
+                        "{}",
+                        message
+                    );
+                    assert!(message.contains("more"), "{}", message);
+                }
+                event => panic!("Unexpected event: {:?}", event),
+            }
+        });
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let source = "
+            mov64 r1, 7
+            stxdw [r10-8], r1
+            mov64 r1, 9
+            stxdw [r10-8], r1
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_debug_mode(DebugMode::Step);
+            dbg.run().unwrap();
+            dbg.run().unwrap();
+            let snapshot = dbg.snapshot();
+            let stack_value = dbg.evaluate("*[r10 - 8]").unwrap();
+            let remaining = dbg.get_context_object().get_remaining();
+            assert_eq!(snapshot.pc(), 16);
+            assert_eq!(stack_value, 7);
+
+            dbg.run().unwrap();
+            dbg.run().unwrap();
+            assert_eq!(dbg.evaluate("*[r10 - 8]"), Ok(9));
+
+            let trace_len = dbg.get_context_object().get_trace_log().len();
+            dbg.restore(&snapshot).unwrap();
+            // The trace is not part of the snapshot.
+            assert_eq!(dbg.get_context_object().get_trace_log().len(), trace_len);
+            assert_eq!(dbg.get_pc(), 16);
+            assert_eq!(dbg.get_register(1), Some(7));
+            assert_eq!(dbg.evaluate("*[r10 - 8]"), Ok(7));
+            assert_eq!(dbg.get_context_object().get_remaining(), remaining);
+            assert_eq!(dbg.instruction_count, 2);
+            // Execution carries on from the restored state.
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(_)));
+        });
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let source = "
+            lddw r1, 0x300000000
+            mov64 r2, 7
+            stxdw [r1+8], r2
+            stxdw [r1+8], r2
+            mov64 r0, 0
+            exit";
+        with_debugger(source, |mut dbg| {
+            let address = ebpf::MM_HEAP_START + 8;
+            assert!(dbg.add_watchpoint(address, 0).is_err());
+            let id = dbg.add_watchpoint(address, 8).unwrap();
+
+            dbg.set_debug_mode(DebugMode::Continue);
+            match dbg.run().unwrap() {
+                DebugEvent::Watchpoint(hit, old, new) => {
+                    assert_eq!(hit, address);
+                    assert_eq!(old, vec![0; 8]);
+                    assert_eq!(new, 7u64.to_le_bytes());
+                }
+                event => panic!("Unexpected event {:?}", event),
+            }
+            // Stopped right after the first store.
+            assert_eq!(dbg.get_pc(), 32);
+
+            // Storing the same value again does not change the memory.
+            assert!(matches!(dbg.run().unwrap(), DebugEvent::Exit(0)));
+            dbg.remove_watch(id).unwrap();
+            assert!(dbg.watchpoints.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_step_detailed() {
+        with_debugger("mov64 r1, 5\nadd64 r1, 3\nexit", |mut dbg| {
+            dbg.step_detailed().unwrap();
+            let info = dbg.step_detailed().unwrap();
+            assert_eq!(info.pc, 8);
+            assert_eq!(info.mnemonic, "add64");
+            assert_eq!(info.operands, "r1, 3");
+            assert_eq!(info.changed_registers, vec![(1, 5, 8)]);
+            assert_eq!(info.compute_units, 1);
+            assert!(matches!(info.event, DebugEvent::Step(8, _)));
+        });
+    }
+}
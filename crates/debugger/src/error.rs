@@ -1,3 +1,4 @@
+use solana_sbpf::elf_parser::ElfParserError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -10,8 +11,12 @@ pub enum DebuggerError {
     FileError(#[from] std::io::Error),
     #[error("Failed to parse object: {0}")]
     ObjectError(#[from] object::Error),
+    #[error("Failed to parse ELF: {0}")]
+    ElfError(#[from] ElfParserError),
     #[error("Computational budget exceeded")]
     ComputationalBudgetExceeded,
+    #[error("Call stack depth exceeded")]
+    CallDepthExceeded,
 }
 
 pub type DebuggerResult<T> = Result<T, DebuggerError>;
@@ -0,0 +1,249 @@
+//! Evaluation of simple expressions over registers and memory, used by the `eval`, `break` and
+//! `setreg` REPL commands and the adapter's `evaluate` command.
+//!
+//! An expression is a sum of products separated by `+` or `-`, and a product is a sequence of
+//! terms separated by `*`. A term is a register (`r0` to `r11`, `sp`/`fp`, or `pc` in bytes), a
+//! decimal or `0x` hex literal, a parenthesized expression or a dereference `*[expr]`, which
+//! reads the u64 stored at the address `expr`. Arithmetic wraps like in the VM.
+
+use std::fmt;
+
+use crate::debugger::RegisterName;
+
+/// A token of an expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u64),
+    Register(usize),
+    Pc,
+    Plus,
+    Minus,
+    Star,
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "'{}'", value),
+            Token::Register(index) => write!(f, "'r{}'", index),
+            Token::Pc => write!(f, "'pc'"),
+            Token::Plus => write!(f, "'+'"),
+            Token::Minus => write!(f, "'-'"),
+            Token::Star => write!(f, "'*'"),
+            Token::LeftBracket => write!(f, "'['"),
+            Token::RightBracket => write!(f, "']'"),
+            Token::LeftParen => write!(f, "'('"),
+            Token::RightParen => write!(f, "')'"),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            c if c.is_ascii_alphanumeric() => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                tokens.push(parse_word(&expr[start..end])?);
+                continue;
+            }
+            c => {
+                return Err(format!(
+                    "Unexpected character '{}' at column {}",
+                    c,
+                    start + 1
+                ))
+            }
+        };
+        chars.next();
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn parse_word(word: &str) -> Result<Token, String> {
+    if !word.starts_with(|c: char| c.is_ascii_digit()) {
+        return match RegisterName::parse(word) {
+            Some(RegisterName::Index(index)) if index <= 11 => Ok(Token::Register(index)),
+            Some(RegisterName::Pc) => Ok(Token::Pc),
+            _ => Err(format!("Unknown register '{}'", word)),
+        };
+    }
+    let value = match word.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => word.parse::<u64>(),
+    };
+    value
+        .map(Token::Number)
+        .map_err(|_| format!("Invalid number '{}'", word))
+}
+
+struct Parser<'a, F> {
+    tokens: &'a [Token],
+    position: usize,
+    registers: &'a [u64],
+    read_u64: F,
+}
+
+impl<F: Fn(u64) -> Result<u64, String>> Parser<'_, F> {
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if *token == expected => Ok(()),
+            Some(token) => Err(format!("Expected {}, found {}", expected, token)),
+            None => Err(format!(
+                "Expected {} at the end of the expression",
+                expected
+            )),
+        }
+    }
+
+    fn expression(&mut self) -> Result<u64, String> {
+        let mut value = self.product()?;
+        loop {
+            match self.tokens.get(self.position) {
+                Some(Token::Plus) => {
+                    self.position += 1;
+                    value = value.wrapping_add(self.product()?);
+                }
+                Some(Token::Minus) => {
+                    self.position += 1;
+                    value = value.wrapping_sub(self.product()?);
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    /// A `*` after a term is a multiplication, while a `*` starting a term is a dereference.
+    fn product(&mut self) -> Result<u64, String> {
+        let mut value = self.term()?;
+        while self.tokens.get(self.position) == Some(&Token::Star) {
+            self.position += 1;
+            value = value.wrapping_mul(self.term()?);
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<u64, String> {
+        match self.next().cloned() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::Register(index)) => self
+                .registers
+                .get(index)
+                .copied()
+                .ok_or_else(|| format!("Register r{} is not available", index)),
+            Some(Token::Pc) => self
+                .registers
+                .get(11)
+                .map(|pc| pc.wrapping_mul(8))
+                .ok_or_else(|| "Register pc is not available".to_string()),
+            Some(Token::LeftParen) => {
+                let value = self.expression()?;
+                self.expect(Token::RightParen)?;
+                Ok(value)
+            }
+            Some(Token::Star) => {
+                self.expect(Token::LeftBracket)?;
+                let address = self.expression()?;
+                self.expect(Token::RightBracket)?;
+                (self.read_u64)(address)
+            }
+            Some(token) => Err(format!("Unexpected {}", token)),
+            None => Err("Unexpected end of the expression".to_string()),
+        }
+    }
+}
+
+/// Evaluate `expr` with the given register values, reading memory through `read_u64`.
+pub fn evaluate(
+    expr: &str,
+    registers: &[u64],
+    read_u64: impl Fn(u64) -> Result<u64, String>,
+) -> Result<u64, String> {
+    let tokens = tokenize(expr)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+        registers,
+        read_u64,
+    };
+    let value = parser.expression()?;
+    match tokens.get(parser.position) {
+        None => Ok(value),
+        Some(token) => Err(format!("Unexpected {} after the expression", token)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expr: &str) -> Result<u64, String> {
+        let registers = [0, 0x400000000, 2, 3, 0, 0, 0, 0, 0, 0, 0x200001000, 4];
+        evaluate(expr, &registers, |address| match address {
+            0x400000008 => Ok(0x400000010),
+            0x400000010 => Ok(42),
+            _ => Err(format!("Cannot read 8 bytes at 0x{:016x}", address)),
+        })
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(eval("0x10"), Ok(16));
+        assert_eq!(eval("r2 + r3 - 1"), Ok(4));
+        assert_eq!(eval("r10 - 0x8"), Ok(0x200000ff8));
+        assert_eq!(eval("0 - 1"), Ok(u64::MAX));
+        assert_eq!(eval("r2 - (r3 - 1)"), Ok(0));
+        assert_eq!(eval("*[r1 + 8]"), Ok(0x400000010));
+        assert_eq!(eval("*[*[r1 + 8]] + 1"), Ok(43));
+        assert_eq!(eval("r2 * r3 + 1"), Ok(7));
+        assert_eq!(eval("1 + r2 * (r3 - 1)"), Ok(5));
+        assert_eq!(eval("r2 * *[r1 + 8]"), Ok(0x800000020));
+        assert_eq!(eval("fp - 16"), Ok(0x200000ff0));
+        assert_eq!(eval("r11 * 8"), Ok(0x20));
+        assert_eq!(eval("pc + 8"), Ok(0x28));
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        assert!(eval("").unwrap_err().contains("end of the expression"));
+        assert!(eval("r12").unwrap_err().contains("Unknown register"));
+        assert!(eval("foo").unwrap_err().contains("Unknown register"));
+        assert!(eval("r1 *").is_err());
+        assert!(eval("0xzz").unwrap_err().contains("Invalid number"));
+        assert!(eval("r1 +").is_err());
+        assert!(eval("*[r1").is_err());
+        assert!(eval("r1 r2").unwrap_err().contains("after the expression"));
+        assert!(eval("r1 & 1").unwrap_err().contains("Unexpected character"));
+        assert!(eval("*[r1]").unwrap_err().contains("Cannot read"));
+    }
+}
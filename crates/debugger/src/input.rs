@@ -0,0 +1,373 @@
+//! Helpers for working with the serialized program input (see the `sbpf-dbg-input` crate for
+//! the layout).
+
+use solana_pubkey::Pubkey;
+use std::{ops::Range, path::Path};
+
+/// Space reserved after each account's data for realloc growth
+const MAX_PERMITTED_DATA_INCREASE: usize = 10240;
+/// Alignment of the account data region
+const BPF_ALIGN_OF_U128: usize = 16;
+/// Marker for non-duplicate accounts
+const NON_DUP_MARKER: u8 = 0xff;
+
+/// Byte ranges of a serialized (non-duplicate) account within the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountLayout {
+    /// Position of the account in the instruction
+    pub index: usize,
+    /// Offset of the duplicate marker, which starts the account
+    pub offset: usize,
+    /// Range of the account public key
+    pub key: Range<usize>,
+    /// Range of the account data, without the realloc padding
+    pub data: Range<usize>,
+    /// Range of the owner, lamports, data length, data and realloc padding
+    pub state: Range<usize>,
+}
+
+/// An account deserialized from the program input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputAccount {
+    Account {
+        index: usize,
+        is_signer: bool,
+        is_writable: bool,
+        executable: bool,
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+        rent_epoch: u64,
+    },
+    /// Duplicate of the account at position `original`
+    Duplicate { index: usize, original: usize },
+}
+
+enum SerializedAccount {
+    Account(AccountLayout),
+    Duplicate { index: usize, original: usize },
+}
+
+fn read_u64(input: &[u8], offset: usize) -> Result<u64, String> {
+    input
+        .get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| format!("Input truncated at offset {}", offset))
+}
+
+/// Walk the serialized accounts, skipping the realloc padding and the alignment tail after each
+/// account's data.
+fn walk_accounts(input: &[u8]) -> Result<Vec<SerializedAccount>, String> {
+    let num_accounts = read_u64(input, 0)? as usize;
+    let mut offset = 8;
+    let mut accounts = Vec::new();
+
+    for index in 0..num_accounts {
+        let marker = *input
+            .get(offset)
+            .ok_or_else(|| format!("Input truncated at account {}", index))?;
+        if marker != NON_DUP_MARKER {
+            let original = marker as usize;
+            if original >= index {
+                return Err(format!(
+                    "Account {} is a duplicate of account {}, which does not precede it",
+                    index, original
+                ));
+            }
+            accounts.push(SerializedAccount::Duplicate { index, original });
+            // Marker and 7 bytes of padding.
+            offset += 8;
+            continue;
+        }
+
+        // Marker, signer, writable, executable and 4 bytes of padding.
+        let key_start = offset + 8;
+        let owner_start = key_start + 32;
+        let data_len = read_u64(input, owner_start + 32 + 8)? as usize;
+        let data_start = owner_start + 32 + 8 + 8;
+        let data_end = data_start
+            .checked_add(data_len)
+            .filter(|&end| end <= input.len())
+            .ok_or_else(|| {
+                format!(
+                    "Input truncated in the data of account {} ({} bytes)",
+                    index, data_len
+                )
+            })?;
+        // The realloc padding is followed by enough bytes to align to 16 bytes.
+        let padded_end = data_end + MAX_PERMITTED_DATA_INCREASE;
+        let aligned_end =
+            padded_end + (BPF_ALIGN_OF_U128 - padded_end % BPF_ALIGN_OF_U128) % BPF_ALIGN_OF_U128;
+        if aligned_end > input.len() {
+            return Err(format!(
+                "Input truncated in the realloc padding of account {}",
+                index
+            ));
+        }
+        // Rent epoch.
+        if aligned_end + 8 > input.len() {
+            return Err(format!(
+                "Input truncated in the rent epoch of account {}",
+                index
+            ));
+        }
+
+        accounts.push(SerializedAccount::Account(AccountLayout {
+            index,
+            offset,
+            key: key_start..owner_start,
+            data: data_start..data_end,
+            state: owner_start..aligned_end,
+        }));
+        offset = aligned_end + 8;
+    }
+
+    Ok(accounts)
+}
+
+/// Walk the serialized input and return the layout of every non-duplicate account.
+pub fn parse_account_layouts(input: &[u8]) -> Result<Vec<AccountLayout>, String> {
+    Ok(walk_accounts(input)?
+        .into_iter()
+        .filter_map(|account| match account {
+            SerializedAccount::Account(layout) => Some(layout),
+            SerializedAccount::Duplicate { .. } => None,
+        })
+        .collect())
+}
+
+/// Deserialize the accounts of the serialized input.
+pub fn parse_accounts(input: &[u8]) -> Result<Vec<InputAccount>, String> {
+    let pubkey = |range: Range<usize>| Pubkey::try_from(&input[range]).unwrap();
+    Ok(walk_accounts(input)?
+        .into_iter()
+        .map(|account| match account {
+            SerializedAccount::Account(layout) => {
+                let owner_start = layout.key.end;
+                InputAccount::Account {
+                    index: layout.index,
+                    is_signer: input[layout.offset + 1] != 0,
+                    is_writable: input[layout.offset + 2] != 0,
+                    executable: input[layout.offset + 3] != 0,
+                    key: pubkey(layout.key.clone()),
+                    owner: pubkey(owner_start..owner_start + 32),
+                    lamports: read_u64(input, owner_start + 32).unwrap(),
+                    data: input[layout.data.clone()].to_vec(),
+                    rent_epoch: read_u64(input, layout.state.end).unwrap(),
+                }
+            }
+            SerializedAccount::Duplicate { index, original } => {
+                InputAccount::Duplicate { index, original }
+            }
+        })
+        .collect())
+}
+
+/// Restore the account state (owner, lamports, data) in `input` from a dump of the input
+/// region taken during a previous run.
+///
+/// The dump must have been taken from an input with the same account layout. Returns the number
+/// of restored accounts.
+pub fn load_accounts_dump(input: &mut [u8], dump: &[u8]) -> Result<usize, String> {
+    if dump.len() != input.len() {
+        return Err(format!(
+            "Dump size ({} bytes) does not match the input size ({} bytes)",
+            dump.len(),
+            input.len()
+        ));
+    }
+
+    let layouts = parse_account_layouts(input)?;
+    for layout in &layouts {
+        if dump[layout.key.clone()] != input[layout.key.clone()] {
+            return Err(format!(
+                "Account {} in the dump does not match the input",
+                layout.index
+            ));
+        }
+    }
+
+    for layout in &layouts {
+        input[layout.state.clone()].copy_from_slice(&dump[layout.state.clone()]);
+    }
+
+    Ok(layouts.len())
+}
+
+/// Decode a hex string, as written to `.hex` files by the `sbpf-dbg-input` crate. Whitespace is
+/// ignored and an optional `0x` prefix is accepted.
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex: String = hex.split_whitespace().collect();
+    let hex = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(&hex);
+
+    if !hex.is_ascii() {
+        return Err("Hex string contains non-ASCII characters".to_string());
+    }
+    if hex.len() % 2 != 0 {
+        return Err(format!(
+            "Hex string must have even length, found {} digits",
+            hex.len()
+        ));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| format!("Invalid hex digits '{}' at offset {}", &hex[i..i + 2], i))
+        })
+        .collect()
+}
+
+/// Read serialized program input from a `.bin` file holding the raw bytes, or from a hex file
+/// (any other extension, usually `.hex`).
+pub fn read_input_file(path: &Path) -> Result<Vec<u8>, String> {
+    if path.extension().is_some_and(|extension| extension == "bin") {
+        return std::fs::read(path)
+            .map_err(|e| format!("Failed to read file '{}': {}", path.display(), e));
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read file '{}': {}", path.display(), e))?;
+    decode_hex(&content).map_err(|e| format!("Invalid hex file '{}': {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serialize accounts given as (key, data) along with a duplicate of the first one.
+    fn serialize(accounts: &[([u8; 32], Vec<u8>)]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&(accounts.len() as u64 + 1).to_le_bytes());
+        for (key, data) in accounts {
+            buffer.extend_from_slice(&[NON_DUP_MARKER, 1, 1, 0, 0, 0, 0, 0]);
+            buffer.extend_from_slice(key);
+            buffer.extend_from_slice(&[7u8; 32]);
+            buffer.extend_from_slice(&100u64.to_le_bytes());
+            buffer.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            buffer.extend_from_slice(data);
+            buffer.resize(buffer.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            let padding =
+                (BPF_ALIGN_OF_U128 - buffer.len() % BPF_ALIGN_OF_U128) % BPF_ALIGN_OF_U128;
+            buffer.resize(buffer.len() + padding, 0);
+            buffer.extend_from_slice(&0u64.to_le_bytes());
+        }
+        buffer.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);
+        buffer.extend_from_slice(&0u64.to_le_bytes());
+        buffer.extend_from_slice(&[9u8; 32]);
+        buffer
+    }
+
+    #[test]
+    fn test_load_accounts_dump() {
+        let mut input = serialize(&[([1u8; 32], vec![1, 2, 3]), ([2u8; 32], vec![4, 5])]);
+        let layouts = parse_account_layouts(&input).unwrap();
+        assert_eq!(layouts.len(), 2);
+
+        // Simulate a previous run that changed the data of the second account.
+        let mut dump = input.clone();
+        let data_start = layouts[1].state.start + 32 + 8 + 8;
+        dump[data_start..data_start + 2].copy_from_slice(&[42, 43]);
+
+        assert_eq!(load_accounts_dump(&mut input, &dump).unwrap(), 2);
+        assert_eq!(&input[data_start..data_start + 2], &[42, 43]);
+        assert_eq!(input, dump);
+    }
+
+    #[test]
+    fn test_load_accounts_dump_mismatch() {
+        let mut input = serialize(&[([1u8; 32], vec![1, 2, 3])]);
+        assert!(load_accounts_dump(&mut input, &[0u8; 16]).is_err());
+
+        let dump = serialize(&[([3u8; 32], vec![1, 2, 3])]);
+        assert!(load_accounts_dump(&mut input, &dump).is_err());
+    }
+
+    #[test]
+    fn test_parse_accounts_alignment() {
+        // Data lengths that leave different alignment tails.
+        let accounts = [
+            ([1u8; 32], vec![]),
+            ([2u8; 32], vec![5u8; 5]),
+            ([3u8; 32], vec![6u8; 17]),
+        ];
+        let input = serialize(&accounts);
+
+        let parsed = parse_accounts(&input).unwrap();
+        assert_eq!(parsed.len(), 4);
+        for (i, (key, data)) in accounts.iter().enumerate() {
+            match &parsed[i] {
+                InputAccount::Account {
+                    index,
+                    key: parsed_key,
+                    owner,
+                    lamports,
+                    data: parsed_data,
+                    rent_epoch,
+                    ..
+                } => {
+                    assert_eq!(*index, i);
+                    assert_eq!(parsed_key.as_ref(), key);
+                    assert_eq!(owner.as_ref(), &[7u8; 32]);
+                    assert_eq!(*lamports, 100);
+                    assert_eq!(parsed_data, data);
+                    assert_eq!(*rent_epoch, 0);
+                }
+                other => panic!("Unexpected account {:?}", other),
+            }
+        }
+        assert_eq!(
+            parsed[3],
+            InputAccount::Duplicate {
+                index: 3,
+                original: 0
+            }
+        );
+
+        // Truncating the input inside the alignment tail is reported.
+        let layouts = parse_account_layouts(&input).unwrap();
+        let truncated = &input[..layouts[2].state.end - 1];
+        assert!(parse_accounts(truncated)
+            .unwrap_err()
+            .contains("realloc padding of account 2"));
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(
+            decode_hex("0a0B ff\n10\r\n"),
+            Ok(vec![0x0a, 0x0b, 0xff, 0x10])
+        );
+        assert_eq!(decode_hex("0x0102"), Ok(vec![1, 2]));
+        assert_eq!(decode_hex(""), Ok(vec![]));
+        assert!(decode_hex("012").unwrap_err().contains("even length"));
+        assert!(decode_hex("0g").unwrap_err().contains("Invalid hex"));
+        assert!(decode_hex("é0").is_err());
+    }
+
+    #[test]
+    fn test_read_input_file() {
+        let input = serialize(&[([1u8; 32], vec![1, 2, 3])]);
+        let dir = std::env::temp_dir().join(format!("sbpf-dbg-input-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let bin = dir.join("input.bin");
+        std::fs::write(&bin, &input).unwrap();
+        assert_eq!(read_input_file(&bin).unwrap(), input);
+
+        let hex = dir.join("input.hex");
+        let content: String = input.iter().map(|byte| format!("{:02x}", byte)).collect();
+        std::fs::write(&hex, format!("{}\n", content)).unwrap();
+        assert_eq!(read_input_file(&hex).unwrap(), input);
+
+        std::fs::write(&hex, "abc").unwrap();
+        assert!(read_input_file(&hex).unwrap_err().contains("even length"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(read_input_file(&bin).is_err());
+    }
+}
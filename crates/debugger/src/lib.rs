@@ -0,0 +1,333 @@
+//! Library interface of the sBPF debugger.
+//!
+//! The `sbpf-dbg` binary is built on top of this crate. Embedding applications can use it to
+//! drive the debugger with their own syscall environment (see [`syscalls::SyscallRegistry`]).
+
+use solana_program_runtime::execution_budget::{
+    SVMTransactionExecutionBudget, SVMTransactionExecutionCost,
+};
+use solana_pubkey::Pubkey;
+use solana_sbpf::{ebpf, static_analysis::TraceLogEntry, vm::ContextObject};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    sync::mpsc::Sender,
+};
+
+use crate::error::DebuggerError;
+
+pub mod adapter;
+pub mod build;
+pub mod condition;
+pub mod debugger;
+pub mod error;
+pub mod expression;
+pub mod input;
+pub mod parser;
+pub mod repl;
+pub mod syscalls;
+#[cfg(test)]
+mod test_utils;
+
+/// Number of syscall invocations kept in the syscall trace
+pub const SYSCALL_TRACE_CAPACITY: usize = 1024;
+
+/// Number of program log lines kept in the log history
+pub const LOG_CAPACITY: usize = 10_000;
+
+/// Number of instructions kept in the trace log unless configured otherwise
+pub const DEFAULT_TRACE_LIMIT: usize = 250_000;
+
+/// Where program logs are written as they are emitted
+#[derive(Debug, Clone, Default)]
+pub enum LogSink {
+    /// Print each line to stdout
+    #[default]
+    Stdout,
+    /// Keep lines in memory until they are taken with `take_logs`
+    Buffer,
+    /// Send each line to a channel, e.g. to stream them from an embedding application
+    Channel(Sender<String>),
+}
+
+/// Simple instruction meter for testing
+#[derive(Debug, Clone, Default)]
+pub struct DebugContextObject {
+    /// Contains the register state of the most recent instructions in order of execution
+    trace_log: VecDeque<TraceLogEntry>,
+    /// Maximum length of the trace log, 0 disables it
+    trace_limit: usize,
+    /// Number of times each instruction (by index) was executed, for profiling
+    instruction_counts: HashMap<u64, u64>,
+    compute_budget: SVMTransactionExecutionBudget,
+    execution_cost: SVMTransactionExecutionCost,
+    compute_meter: RefCell<u64>,
+    /// Size of the heap region in bytes
+    heap_size: u64,
+    /// Bump allocator position within the heap region
+    heap_pos: u64,
+    /// Id of the program being debugged
+    program_id: Pubkey,
+    /// Return data set by the program and the id of the program that set it
+    return_data: (Pubkey, Vec<u8>),
+    /// Cross-program invocations made by the program
+    invocations: Vec<syscalls::Invocation>,
+    /// Most recent syscall invocations, oldest first
+    syscall_trace: VecDeque<syscalls::SyscallTraceEntry>,
+    /// Where program logs are written as they are emitted
+    log_sink: LogSink,
+    /// Most recent program logs of the run, oldest first
+    logs: VecDeque<String>,
+    /// Number of logs of `logs` already printed or returned by `take_logs`
+    logs_taken: usize,
+    /// Number of logs dropped from `logs` once it was full
+    logs_dropped: u64,
+    /// Sysvars returned by the sysvar syscalls
+    sysvars: syscalls::Sysvars,
+}
+
+impl ContextObject for DebugContextObject {
+    fn trace(&mut self, state: [u64; 12]) {
+        *self.instruction_counts.entry(state[11]).or_default() += 1;
+        if self.trace_limit == 0 {
+            return;
+        }
+        if self.trace_log.len() == self.trace_limit {
+            self.trace_log.pop_front();
+        }
+        self.trace_log.push_back(state);
+    }
+
+    fn consume(&mut self, amount: u64) {
+        let mut compute_meter = self.compute_meter.borrow_mut();
+        *compute_meter = compute_meter.saturating_sub(amount);
+    }
+
+    fn get_remaining(&self) -> u64 {
+        *self.compute_meter.borrow()
+    }
+}
+
+impl DebugContextObject {
+    /// Initialize with instruction meter
+    pub fn new(
+        compute_budget: SVMTransactionExecutionBudget,
+        execution_cost: SVMTransactionExecutionCost,
+        heap_size: u64,
+    ) -> Self {
+        Self {
+            trace_log: VecDeque::new(),
+            trace_limit: DEFAULT_TRACE_LIMIT,
+            instruction_counts: HashMap::new(),
+            compute_budget,
+            execution_cost,
+            compute_meter: RefCell::new(compute_budget.compute_unit_limit),
+            heap_size,
+            heap_pos: 0,
+            program_id: Pubkey::default(),
+            return_data: (Pubkey::default(), Vec::new()),
+            invocations: Vec::new(),
+            syscall_trace: VecDeque::new(),
+            log_sink: LogSink::Stdout,
+            logs: VecDeque::new(),
+            logs_taken: 0,
+            logs_dropped: 0,
+            sysvars: syscalls::Sysvars::default(),
+        }
+    }
+
+    pub fn consume_checked(&self, amount: u64) -> Result<(), Box<dyn std::error::Error>> {
+        let mut compute_meter = self.compute_meter.borrow_mut();
+        let exceeded = *compute_meter < amount;
+        *compute_meter = compute_meter.saturating_sub(amount);
+        if exceeded {
+            return Err(Box::new(DebuggerError::ComputationalBudgetExceeded));
+        }
+        Ok(())
+    }
+
+    /// Keep at most `limit` instructions in the trace log, dropping the oldest ones first, or
+    /// disable the trace log if `limit` is 0. The instruction counts are kept either way.
+    pub fn set_trace_limit(&mut self, limit: usize) {
+        self.trace_limit = limit;
+        while self.trace_log.len() > limit {
+            self.trace_log.pop_front();
+        }
+    }
+
+    /// Register state of the most recent instructions, oldest first
+    pub fn get_trace_log(&self) -> &VecDeque<TraceLogEntry> {
+        &self.trace_log
+    }
+
+    /// Number of executions of each instruction index, recorded while instruction tracing is
+    /// enabled
+    pub fn get_instruction_counts(&self) -> &HashMap<u64, u64> {
+        &self.instruction_counts
+    }
+
+    pub fn get_execution_cost(&self) -> SVMTransactionExecutionCost {
+        self.execution_cost
+    }
+
+    pub fn get_compute_budget(&self) -> SVMTransactionExecutionBudget {
+        self.compute_budget
+    }
+
+    /// Restore the compute meter to the full compute unit limit
+    pub fn reset_compute_meter(&self) {
+        *self.compute_meter.borrow_mut() = self.compute_budget.compute_unit_limit;
+    }
+
+    /// Clear the state of a run (trace, instruction counts, heap allocations, return data,
+    /// invocations, syscall trace and logs) and refill the compute meter, keeping the
+    /// configuration, to run the program again.
+    pub fn reset(&mut self) {
+        self.trace_log.clear();
+        self.instruction_counts.clear();
+        self.reset_compute_meter();
+        self.heap_pos = 0;
+        self.return_data = (Pubkey::default(), Vec::new());
+        self.invocations.clear();
+        self.syscall_trace.clear();
+        self.logs.clear();
+        self.logs_taken = 0;
+        self.logs_dropped = 0;
+    }
+
+    /// Copy everything but the trace log and the instruction counts, which can hold up to
+    /// [`DEFAULT_TRACE_LIMIT`] entries and are left out of snapshots.
+    pub(crate) fn clone_without_trace(&self) -> Self {
+        Self {
+            trace_log: VecDeque::new(),
+            trace_limit: self.trace_limit,
+            instruction_counts: HashMap::new(),
+            compute_budget: self.compute_budget,
+            execution_cost: self.execution_cost,
+            compute_meter: self.compute_meter.clone(),
+            heap_size: self.heap_size,
+            heap_pos: self.heap_pos,
+            program_id: self.program_id,
+            return_data: self.return_data.clone(),
+            invocations: self.invocations.clone(),
+            syscall_trace: self.syscall_trace.clone(),
+            log_sink: self.log_sink.clone(),
+            logs: self.logs.clone(),
+            logs_taken: self.logs_taken,
+            logs_dropped: self.logs_dropped,
+            sysvars: self.sysvars.clone(),
+        }
+    }
+
+    /// Put back a copy made by [`Self::clone_without_trace`], keeping the current trace log and
+    /// instruction counts.
+    pub(crate) fn restore_without_trace(&mut self, saved: &Self) {
+        let trace_log = std::mem::take(&mut self.trace_log);
+        let instruction_counts = std::mem::take(&mut self.instruction_counts);
+        *self = Self {
+            trace_log,
+            instruction_counts,
+            ..saved.clone()
+        };
+    }
+
+    /// Bump-allocate `size` bytes from the heap region, returning the VM address. An `align` of 0
+    /// is taken as 1, like in the runtime's allocator.
+    pub fn alloc(&mut self, size: u64, align: u64) -> Option<u64> {
+        let align = align.max(1);
+        let bytes_to_align = (align - self.heap_pos % align) % align;
+        let start = self.heap_pos.checked_add(bytes_to_align)?;
+        let end = start.checked_add(size)?;
+        if end > self.heap_size {
+            return None;
+        }
+        self.heap_pos = end;
+        Some(ebpf::MM_HEAP_START + start)
+    }
+
+    pub fn set_program_id(&mut self, program_id: Pubkey) {
+        self.program_id = program_id;
+    }
+
+    /// Set the return data on behalf of the program being debugged
+    pub fn set_return_data(&mut self, data: Vec<u8>) {
+        self.return_data = (self.program_id, data);
+    }
+
+    pub fn get_return_data(&self) -> (&Pubkey, &[u8]) {
+        (&self.return_data.0, &self.return_data.1)
+    }
+
+    pub fn record_invocation(&mut self, invocation: syscalls::Invocation) {
+        self.invocations.push(invocation);
+    }
+
+    pub fn get_invocations(&self) -> &[syscalls::Invocation] {
+        &self.invocations
+    }
+
+    /// Record a syscall invocation, dropping the oldest entry once the trace is full
+    pub fn record_syscall(&mut self, entry: syscalls::SyscallTraceEntry) {
+        if self.syscall_trace.len() == SYSCALL_TRACE_CAPACITY {
+            self.syscall_trace.pop_front();
+        }
+        self.syscall_trace.push_back(entry);
+    }
+
+    pub fn get_syscall_trace(&self) -> &VecDeque<syscalls::SyscallTraceEntry> {
+        &self.syscall_trace
+    }
+
+    pub fn get_sysvars(&self) -> &syscalls::Sysvars {
+        &self.sysvars
+    }
+
+    /// Set the sysvars returned by the sysvar syscalls, e.g. to exercise slot-dependent code
+    pub fn set_sysvars(&mut self, sysvars: syscalls::Sysvars) {
+        self.sysvars = sysvars;
+    }
+
+    /// Write program logs to stdout (the default), a buffer or a channel
+    pub fn set_log_sink(&mut self, log_sink: LogSink) {
+        self.log_sink = log_sink;
+    }
+
+    /// Emit a program log line such as "Program log: ...", dropping the oldest line of the
+    /// history once it is full
+    pub fn log(&mut self, message: String) {
+        match &self.log_sink {
+            LogSink::Stdout => println!("{}", message),
+            LogSink::Buffer => {}
+            // A closed channel only means nobody listens anymore; the history keeps the line.
+            LogSink::Channel(sender) => {
+                let _ = sender.send(message.clone());
+            }
+        }
+        if self.logs.len() == LOG_CAPACITY {
+            self.logs.pop_front();
+            self.logs_taken = self.logs_taken.saturating_sub(1);
+            self.logs_dropped += 1;
+        }
+        self.logs.push_back(message);
+        if !matches!(self.log_sink, LogSink::Buffer) {
+            self.logs_taken = self.logs.len();
+        }
+    }
+
+    /// Take the buffered program logs collected since the last call
+    pub fn take_logs(&mut self) -> Vec<String> {
+        let logs = self.logs.range(self.logs_taken..).cloned().collect();
+        self.logs_taken = self.logs.len();
+        logs
+    }
+
+    /// The last [`LOG_CAPACITY`] program logs of the run, whether printed, taken or not
+    pub fn get_logs(&self) -> &VecDeque<String> {
+        &self.logs
+    }
+
+    /// Number of program logs dropped from the history because it was full
+    pub fn get_logs_dropped(&self) -> u64 {
+        self.logs_dropped
+    }
+}
@@ -1,62 +1,26 @@
 use clap::Parser;
+use sbpf_dbg::{
+    build::{build_assembly, BuildConfig, DEFAULT_IMAGE_BASE},
+    debugger::{init_vm, Debugger},
+    input,
+    parser::{parse_bss, parse_data, parse_rodata, LineMap, ProgramFiles},
+    repl::{OnError, OutputFormat, Repl, BREAKPOINTS_FILE},
+    syscalls::{SyscallRegistry, Sysvars},
+    DebugContextObject, LogSink, DEFAULT_TRACE_LIMIT,
+};
 use solana_program_runtime::execution_budget::{
     SVMTransactionExecutionBudget, SVMTransactionExecutionCost,
 };
+use solana_pubkey::Pubkey;
 use solana_sbpf::{
     aligned_memory::AlignedMemory,
     ebpf,
     elf::Executable,
-    error::ProgramResult,
     memory_region::{MemoryMapping, MemoryRegion},
-    program::BuiltinProgram,
-    static_analysis::TraceLogEntry,
     verifier::RequisiteVerifier,
-    vm::{Config, ContextObject, EbpfVm},
-};
-use std::{cell::RefCell, fs::File, io::Read, path::Path, sync::Arc};
-
-use crate::{
-    build::{build_assembly, BuildConfig},
-    debugger::Debugger,
-    error::DebuggerError,
-    parser::{parse_rodata, LineMap},
-    repl::Repl,
+    vm::{Config, EbpfVm},
 };
-
-mod adapter;
-mod build;
-mod debugger;
-mod error;
-mod parser;
-mod repl;
-mod syscalls;
-
-/// Parse hex string into bytes
-fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
-    let hex = hex.trim();
-
-    // Remove 0x prefix if present
-    let hex = if hex.starts_with("0x") || hex.starts_with("0X") {
-        &hex[2..]
-    } else {
-        hex
-    };
-
-    // Ensure even length
-    if hex.len() % 2 != 0 {
-        return Err("Hex string must have even length".to_string());
-    }
-
-    let mut bytes = Vec::with_capacity(hex.len() / 2);
-    for i in (0..hex.len()).step_by(2) {
-        let byte_str = &hex[i..i + 2];
-        let byte = u8::from_str_radix(byte_str, 16)
-            .map_err(|_| format!("Invalid hex character in '{}'", byte_str))?;
-        bytes.push(byte);
-    }
-
-    Ok(bytes)
-}
+use std::{path::Path, sync::Arc};
 
 /// Parse input as either a file path or hex string
 fn parse_input(input: &str) -> Result<Vec<u8>, String> {
@@ -66,84 +30,20 @@ fn parse_input(input: &str) -> Result<Vec<u8>, String> {
         return Ok(Vec::new());
     }
 
-    // Check if input looks like a file path (contains path separators or ends with .hex)
-    if input.contains('/') || input.contains('\\') || input.ends_with(".hex") {
-        // Try to read as file
+    // Check if input looks like a file path (contains path separators or ends with .hex or .bin)
+    if input.contains('/')
+        || input.contains('\\')
+        || input.ends_with(".hex")
+        || input.ends_with(".bin")
+    {
         let path = Path::new(input);
         if !path.exists() {
             return Err(format!("File not found: {}", input));
         }
-
-        let mut file =
-            File::open(path).map_err(|e| format!("Failed to open file '{}': {}", input, e))?;
-
-        let mut content = String::new();
-        file.read_to_string(&mut content)
-            .map_err(|e| format!("Failed to read file '{}': {}", input, e))?;
-
-        // Parse the file content as hex
-        parse_hex(&content)
+        input::read_input_file(path)
     } else {
         // Try to parse as hex string directly
-        parse_hex(input)
-    }
-}
-
-/// Simple instruction meter for testing
-#[derive(Debug, Clone, Default)]
-pub struct DebugContextObject {
-    /// Contains the register state at every instruction in order of execution
-    trace_log: Vec<TraceLogEntry>,
-    compute_budget: SVMTransactionExecutionBudget,
-    execution_cost: SVMTransactionExecutionCost,
-    compute_meter: RefCell<u64>,
-}
-
-impl ContextObject for DebugContextObject {
-    fn trace(&mut self, state: [u64; 12]) {
-        self.trace_log.push(state);
-    }
-
-    fn consume(&mut self, amount: u64) {
-        let mut compute_meter = self.compute_meter.borrow_mut();
-        *compute_meter = compute_meter.saturating_sub(amount);
-    }
-
-    fn get_remaining(&self) -> u64 {
-        *self.compute_meter.borrow()
-    }
-}
-
-impl DebugContextObject {
-    /// Initialize with instruction meter
-    pub fn new(
-        compute_budget: SVMTransactionExecutionBudget,
-        execution_cost: SVMTransactionExecutionCost,
-    ) -> Self {
-        Self {
-            trace_log: Vec::new(),
-            compute_budget,
-            execution_cost,
-            compute_meter: RefCell::new(compute_budget.compute_unit_limit),
-        }
-    }
-
-    pub fn consume_checked(&self, amount: u64) -> Result<(), Box<dyn std::error::Error>> {
-        let mut compute_meter = self.compute_meter.borrow_mut();
-        let exceeded = *compute_meter < amount;
-        *compute_meter = compute_meter.saturating_sub(amount);
-        if exceeded {
-            return Err(Box::new(DebuggerError::ComputationalBudgetExceeded));
-        }
-        Ok(())
-    }
-
-    pub fn get_execution_cost(&self) -> SVMTransactionExecutionCost {
-        self.execution_cost
-    }
-
-    pub fn get_compute_budget(&self) -> SVMTransactionExecutionBudget {
-        self.compute_budget
+        input::decode_hex(input)
     }
 }
 
@@ -153,9 +53,10 @@ struct Args {
         short,
         long,
         value_name = "FILE",
-        help = "Path to the assembly file (.s file)"
+        required = true,
+        help = "Path to the assembly file (.s file) or C source file (.c file). Repeat to link several files, the first one being the main file"
     )]
-    file: String,
+    file: Vec<String>,
 
     #[arg(
         short,
@@ -173,29 +74,180 @@ struct Args {
     )]
     input: String,
 
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Program input from a .hex or .bin file generated by sbpf-dbg-input",
+        conflicts_with = "input"
+    )]
+    input_file: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Initialize account state from a dump of the input region taken in a previous run"
+    )]
+    load_accounts_dump: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SLOT",
+        help = "Slot of the Clock sysvar (default: 0)"
+    )]
+    clock_slot: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "EPOCH",
+        help = "Epoch of the Clock sysvar (default: 0)"
+    )]
+    clock_epoch: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        allow_hyphen_values = true,
+        help = "Unix timestamp of the Clock sysvar (default: 0)"
+    )]
+    clock_unix_timestamp: Option<i64>,
+
     #[arg(long, value_name = "BYTES", help = "Heap memory", default_value = "0")]
     heap: String,
 
     #[arg(
         long,
         value_name = "COUNT",
-        help = "Maximal number of instructions to execute",
+        help = "Maximal number of instructions to execute before stopping (0 for no limit)",
         default_value = "10000"
     )]
     max_ixs: String,
 
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Maximal number of instructions kept in the trace log (0 disables it)",
+        default_value_t = DEFAULT_TRACE_LIMIT
+    )]
+    trace_limit: usize,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Run the debugger commands in FILE (one per line) instead of an interactive session"
+    )]
+    script: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "POLICY",
+        help = "What a script does when a command fails (stop or continue)",
+        default_value = "stop"
+    )]
+    on_error: String,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format of regs, reg, compute, rodata, data and profile in the REPL (text or json)",
+        default_value = "text"
+    )]
+    format: String,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Path to the platform-tools directory (defaults to $SBPF_DBG_TOOLCHAIN, then to the active Solana release)"
+    )]
+    toolchain: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Base address of the shared object passed to ld (supports hex with 0x prefix)"
+    )]
+    image_base: Option<String>,
+
+    #[arg(
+        long = "clang-arg",
+        value_name = "ARG",
+        allow_hyphen_values = true,
+        help = "Extra argument for clang (repeatable)"
+    )]
+    clang_args: Vec<String>,
+
+    #[arg(
+        long = "ld-arg",
+        value_name = "ARG",
+        allow_hyphen_values = true,
+        help = "Extra argument for ld (repeatable)"
+    )]
+    ld_args: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Rebuild the program even if a cached build is up to date"
+    )]
+    no_cache: bool,
+
+    #[arg(
+        long,
+        help = "Build without debug information and strip the shared object, like a release build"
+    )]
+    strip: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Copy the built object file and shared object to DIR"
+    )]
+    keep_artifacts: Option<String>,
+
+    #[arg(
+        long,
+        help = "Don't print program logs as they are emitted; they are still shown by `logs`"
+    )]
+    no_echo_logs: bool,
+
     #[arg(long, help = "Run in adapter mode for VS Code extension")]
     adapter: bool,
+
+    #[arg(
+        long,
+        help = "Run as a Debug Adapter Protocol server (Content-Length framed messages on stdio)"
+    )]
+    dap: bool,
 }
 
 fn main() {
     let args = Args::parse();
 
+    let image_base = match &args.image_base {
+        Some(image_base) => match image_base.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => image_base.parse::<u64>(),
+        }
+        .unwrap_or_else(|e| {
+            eprintln!("error:Invalid image base '{}': {}", image_base, e);
+            std::process::exit(1);
+        }),
+        None => DEFAULT_IMAGE_BASE,
+    };
+
+    let format = args.format.parse::<OutputFormat>().unwrap_or_else(|e| {
+        eprintln!("error:{}", e);
+        std::process::exit(1);
+    });
+
     // Build the assembly file
     let build_config = BuildConfig {
-        assembly_file: args.file.clone(),
+        source_files: args.file.clone(),
         linker_file: args.linker.clone(),
-        debug: true, // Always build with debug information for debugging
+        debug: !args.strip,
+        clang_flags: args.clang_args.clone(),
+        ld_flags: args.ld_args.clone(),
+        toolchain: args.toolchain.clone(),
+        image_base,
+        use_cache: !args.no_cache,
     };
 
     let build_result = build_assembly(&build_config).unwrap_or_else(|e| {
@@ -203,56 +255,80 @@ fn main() {
         std::process::exit(1);
     });
 
-    let mut loader = BuiltinProgram::new_loader(Config {
-        enable_symbol_and_section_labels: true,
-        ..Config::default()
-    });
+    // Keeping the artifacts is best effort, the build output is still usable without them.
+    if let Some(dir) = &args.keep_artifacts {
+        match build_result.copy_artifacts(Path::new(dir)) {
+            Ok(paths) => {
+                for path in paths {
+                    eprintln!("Kept build artifact: {}", path.display());
+                }
+            }
+            Err(e) => eprintln!("warning:Failed to keep build artifacts in '{}': {}", dir, e),
+        }
+    }
 
-    // Logging syscalls
-    loader
-        .register_function("sol_log_", syscalls::SyscallLog::vm)
-        .unwrap();
-    loader
-        .register_function("sol_log_64_", syscalls::SyscallLogU64::vm)
-        .unwrap();
+    let loader = SyscallRegistry::with_builtins()
+        .build_loader(Config {
+            enable_symbol_and_section_labels: true,
+            // The trace feeds the instruction profile.
+            enable_instruction_tracing: true,
+            ..Config::default()
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("error:Failed to register syscalls: {}", e);
+            std::process::exit(1);
+        });
     let loader = Arc::new(loader);
 
     // Try to load DWARF line mapping from debug file or executable.
     let file_path = &build_result.shared_object_file;
     let debug_file_path = &build_result.object_file;
-    let line_map = LineMap::from_elf_file(debug_file_path).ok();
-    let rodata = parse_rodata(file_path, debug_file_path).ok();
+    let files = ProgramFiles::open(file_path, debug_file_path).unwrap_or_else(|e| {
+        eprintln!(
+            "error:Failed to read executable file '{}': {}",
+            file_path, e
+        );
+        std::process::exit(1);
+    });
+    let elf = files.elf().unwrap_or_else(|e| {
+        eprintln!("error:Failed to load executable '{}': {}", file_path, e);
+        std::process::exit(1);
+    });
+    let debug_object = files.debug_object().ok();
+    // The object file only describes its own source file. The line programs of a program linked
+    // from several files are read from the shared object instead, where the linker has relocated
+    // all of them.
+    let line_map = if build_result.object_files.len() > 1 {
+        LineMap::from_elf_data(&files.elf_data).ok()
+    } else {
+        debug_object
+            .as_ref()
+            .and_then(|object| LineMap::from_object(object).ok())
+    };
+    let rodata = debug_object
+        .as_ref()
+        .and_then(|object| parse_rodata(&elf, object).ok());
+    let data = debug_object
+        .as_ref()
+        .and_then(|object| parse_data(&elf, object).ok());
+    let bss_range = parse_bss(&elf).unwrap_or_else(|e| {
+        eprintln!("error:Failed to read the .bss section: {}", e);
+        std::process::exit(1);
+    });
 
     #[allow(unused_mut)]
-    let mut executable = {
-        let mut file =
-            File::open(Path::new(&build_result.shared_object_file)).unwrap_or_else(|e| {
-                eprintln!(
-                    "error:Failed to open executable file '{}': {}",
-                    build_result.shared_object_file, e
-                );
-                std::process::exit(1);
-            });
-        let mut elf = Vec::new();
-        file.read_to_end(&mut elf).unwrap_or_else(|e| {
-            eprintln!(
-                "error:Failed to read executable file '{}': {}",
-                build_result.shared_object_file, e
-            );
-            std::process::exit(1);
-        });
-        Executable::<DebugContextObject>::from_elf(&elf, loader).map_err(|err| {
+    let mut executable = Executable::<DebugContextObject>::from_elf(&files.elf_data, loader)
+        .map_err(|err| {
             eprintln!(
                 "error:Failed to load executable '{}': {:?}",
                 build_result.shared_object_file, err
             );
             format!("Executable constructor failed: {err:?}")
         })
-    }
-    .unwrap_or_else(|e| {
-        eprintln!("error:{}", e);
-        std::process::exit(1);
-    });
+        .unwrap_or_else(|e| {
+            eprintln!("error:{}", e);
+            std::process::exit(1);
+        });
 
     executable
         .verify::<RequisiteVerifier>()
@@ -261,7 +337,11 @@ fn main() {
             std::process::exit(1);
         });
 
-    let mut mem: Vec<u8> = match parse_input(&args.input) {
+    let input = match &args.input_file {
+        Some(input_file) => input::read_input_file(Path::new(input_file)),
+        None => parse_input(&args.input),
+    };
+    let mut mem: Vec<u8> = match input {
         Ok(bytes) => bytes,
         Err(e) => {
             eprintln!("error:Failed to parse input: {}", e);
@@ -269,6 +349,22 @@ fn main() {
         }
     };
 
+    if let Some(dump_file) = &args.load_accounts_dump {
+        let dump = std::fs::read(dump_file).unwrap_or_else(|e| {
+            eprintln!("error:Failed to read accounts dump '{}': {}", dump_file, e);
+            std::process::exit(1);
+        });
+        if let Err(e) = input::load_accounts_dump(&mut mem, &dump) {
+            eprintln!("error:Failed to load accounts dump '{}': {}", dump_file, e);
+            std::process::exit(1);
+        }
+    }
+
+    let max_ixs = args.max_ixs.parse::<u64>().unwrap_or_else(|e| {
+        eprintln!("error:Invalid instruction limit '{}': {}", args.max_ixs, e);
+        std::process::exit(1);
+    });
+
     let heap_size = args.heap.parse::<usize>().unwrap_or_else(|e| {
         eprintln!("error:Invalid heap size '{}': {}", args.heap, e);
         std::process::exit(1);
@@ -277,13 +373,39 @@ fn main() {
     let mut context_object = DebugContextObject::new(
         SVMTransactionExecutionBudget::default(),
         SVMTransactionExecutionCost::default(),
+        heap_size as u64,
     );
+    // The serialized input ends with the program id.
+    if let Some(program_id) = mem
+        .len()
+        .checked_sub(32)
+        .and_then(|offset| Pubkey::try_from(&mem[offset..]).ok())
+    {
+        context_object.set_program_id(program_id);
+    }
+    context_object.set_trace_limit(args.trace_limit);
+    let mut sysvars = Sysvars::default();
+    if let Some(slot) = args.clock_slot {
+        sysvars.clock.slot = slot;
+    }
+    if let Some(epoch) = args.clock_epoch {
+        sysvars.clock.epoch = epoch;
+        sysvars.clock.leader_schedule_epoch = epoch;
+    }
+    if let Some(unix_timestamp) = args.clock_unix_timestamp {
+        sysvars.clock.unix_timestamp = unix_timestamp;
+    }
+    context_object.set_sysvars(sysvars);
+    // Keep stdout clean for the adapter protocol; logs are sent as output events instead.
+    if args.adapter || args.dap || args.no_echo_logs {
+        context_object.set_log_sink(LogSink::Buffer);
+    }
     let config = executable.get_config();
     let sbpf_version = executable.get_sbpf_version();
     let mut stack = AlignedMemory::<{ ebpf::HOST_ALIGN }>::zero_filled(config.stack_size());
     let stack_len = stack.len();
     let mut heap = AlignedMemory::<{ ebpf::HOST_ALIGN }>::zero_filled(heap_size);
-    let regions: Vec<MemoryRegion> = vec![
+    let mut regions: Vec<MemoryRegion> = vec![
         executable.get_ro_region(),
         MemoryRegion::new_writable_gapped(
             stack.as_slice_mut(),
@@ -298,10 +420,24 @@ fn main() {
         MemoryRegion::new_writable(&mut mem, ebpf::MM_INPUT_START),
     ];
 
-    let memory_mapping = MemoryMapping::new(regions, config, sbpf_version).unwrap_or_else(|e| {
-        eprintln!("error:Failed to create memory mapping: {:?}", e);
-        std::process::exit(1);
-    });
+    // Zero-initialized globals live right after the program, in the same 4 GiB slot as the
+    // read-only region, which only the unaligned memory mapping supports.
+    let mut bss = AlignedMemory::<{ ebpf::HOST_ALIGN }>::zero_filled(
+        bss_range
+            .as_ref()
+            .map_or(0, |range| (range.end - range.start) as usize),
+    );
+    let mut mapping_config = config.clone();
+    if let Some(range) = &bss_range {
+        regions.push(MemoryRegion::new_writable(bss.as_slice_mut(), range.start));
+        mapping_config.aligned_memory_mapping = false;
+    }
+
+    let memory_mapping =
+        MemoryMapping::new(regions, &mapping_config, sbpf_version).unwrap_or_else(|e| {
+            eprintln!("error:Failed to create memory mapping: {:?}", e);
+            std::process::exit(1);
+        });
 
     let mut vm = EbpfVm::new(
         executable.get_loader().clone(),
@@ -310,15 +446,15 @@ fn main() {
         memory_mapping,
         stack_len,
     );
-    vm.registers[1] = ebpf::MM_INPUT_START;
-    vm.registers[11] = executable.get_entrypoint_instruction_offset() as u64;
-    // let config = executable.get_config();
-    let initial_insn_count = vm.context_object_pointer.get_remaining();
-    vm.previous_instruction_meter = initial_insn_count;
-    vm.due_insn_count = 0;
-    vm.program_result = ProgramResult::Ok(0);
+    init_vm(&mut vm, &executable);
 
     let mut debugger = Debugger::new(&mut vm, &executable);
+    debugger.set_max_instructions(Some(max_ixs).filter(|&max_ixs| max_ixs > 0));
+
+    // Without line information, e.g. with --strip, stepping and breakpoints work on instructions.
+    if !line_map.as_ref().is_some_and(LineMap::has_line_info) {
+        eprintln!("warning:No source line information, debugging at the instruction level");
+    }
 
     // Set the DWARF line mapping if available.
     if let Some(dwarf_map) = line_map {
@@ -329,12 +465,42 @@ fn main() {
         debugger.set_rodata(rodata);
     }
 
-    if args.adapter {
+    if let Some(data) = data {
+        debugger.set_data(data);
+    }
+
+    if args.dap {
+        // Run as a DAP server for any DAP-compatible editor.
+        sbpf_dbg::adapter::run_dap_loop(&mut debugger);
+    } else if args.adapter {
         // Run in adapter mode for VS Code extension.
-        crate::adapter::run_adapter_loop(&mut debugger);
+        sbpf_dbg::adapter::run_adapter_loop(&mut debugger);
+    } else if let Some(script_file) = &args.script {
+        // Run the script non-interactively.
+        let on_error = args.on_error.parse::<OnError>().unwrap_or_else(|e| {
+            eprintln!("error:{}", e);
+            std::process::exit(1);
+        });
+        let script = std::fs::read_to_string(script_file).unwrap_or_else(|e| {
+            eprintln!("error:Failed to read script '{}': {}", script_file, e);
+            std::process::exit(1);
+        });
+        let mut repl = Repl::new(debugger);
+        repl.set_format(format);
+        if let Err(e) = repl.run_script(&script, on_error) {
+            eprintln!("error:Script failed at {}", e);
+            std::process::exit(1);
+        }
     } else {
-        // Run in REPL mode.
+        // Run in REPL mode, with the breakpoints of the previous session.
         let mut repl = Repl::new(debugger);
+        repl.set_format(format);
+        let breakpoints_file = Path::new(BREAKPOINTS_FILE);
+        if breakpoints_file.exists() {
+            if let Err(e) = repl.load_breakpoints(breakpoints_file) {
+                eprintln!("warning:{}", e);
+            }
+        }
         repl.start();
     }
 }
@@ -1,122 +1,322 @@
 use gimli::{EndianSlice, RunTimeEndian, SectionId};
-use object::{Object, ObjectSection, ObjectSymbol};
+use object::{Object, ObjectSection, ObjectSymbol, SymbolKind};
 use solana_sbpf::ebpf::MM_RODATA_START;
 use solana_sbpf::elf_parser::Elf64;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
 
 use crate::error::DebuggerError;
 
+/// A symbol of the `.rodata` or `.data` section with its value.
 #[derive(Debug, Clone)]
-pub struct ROData {
+pub struct Symbol {
     pub name: String,
     pub address: u64,
+    /// Size of the symbol in bytes
+    pub size: u64,
+    /// Kind of the symbol in the object file
+    pub kind: SymbolKind,
     pub content: String,
 }
 
-pub fn parse_rodata(file_path: &str, debug_file_path: &str) -> Result<Vec<ROData>, DebuggerError> {
-    let file = fs::File::open(debug_file_path)?;
-    let mmap = unsafe { memmap2::Mmap::map(&file).unwrap() };
-    let object = object::File::parse(&*mmap).unwrap();
+impl Symbol {
+    /// The demangled name of the symbol, or its raw name if it is not mangled.
+    pub fn display_name(&self) -> String {
+        demangle(&self.name)
+    }
+}
+
+/// Demangle a Rust or C++ symbol name, e.g. `_ZN4core3fmt5write17h0123456789abcdefE` becomes
+/// `core::fmt::write`. Names that are not mangled, and all names when the `demangle` feature is
+/// disabled, are returned unchanged.
+pub fn demangle(name: &str) -> String {
+    #[cfg(feature = "demangle")]
+    {
+        // Legacy Rust symbols also start with `_ZN`, so try Rust first.
+        if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+            // The alternate format omits the hash suffix.
+            return format!("{:#}", demangled);
+        }
+        if name.starts_with("_Z") {
+            if let Ok(demangled) = cpp_demangle::Symbol::new(name)
+                .map_err(|_| std::fmt::Error)
+                .and_then(|symbol| symbol.demangle(&cpp_demangle::DemangleOptions::default()))
+            {
+                return demangled;
+            }
+        }
+    }
+    name.to_string()
+}
+
+/// A symbol of a section: (address, size, name, kind)
+type SectionSymbol = (u64, u64, String, SymbolKind);
+
+/// Get the bytes of each symbol of a section.
+///
+/// Symbols with a size of zero, such as assembly labels without a `.size` directive, extend to the
+/// next symbol or the end of the section.
+fn symbol_contents<'d>(
+    section_data: &'d [u8],
+    section_addr: u64,
+    symbols: &[SectionSymbol],
+) -> Vec<&'d [u8]> {
+    let section_end = section_addr + section_data.len() as u64;
+    symbols
+        .iter()
+        .enumerate()
+        .map(|(i, (addr, size, _, _))| {
+            let offset = addr.saturating_sub(section_addr) as usize;
+            let end = if *size > 0 {
+                addr + size
+            } else {
+                symbols.get(i + 1).map_or(section_end, |next| next.0)
+            };
+            let end = (end.saturating_sub(section_addr) as usize).min(section_data.len());
+            section_data.get(offset..end).unwrap_or(&[])
+        })
+        .collect()
+}
+
+/// Format a symbol's bytes as text if it holds a (possibly NUL-terminated) string, else as hex.
+pub(crate) fn format_symbol_content(content: &[u8], kind: SymbolKind) -> String {
+    let text = content.strip_suffix(&[0]).unwrap_or(content);
+    let is_string = kind != SymbolKind::Text
+        && !text.is_empty()
+        && text.iter().all(|&b| b.is_ascii_graphic() || b == b' ');
+    if is_string {
+        String::from_utf8_lossy(text).to_string()
+    } else {
+        content
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// VM address of a section of the executable, computed like the loader does: section addresses
+/// below `MM_RODATA_START` (the default linker script places the sections right after the headers,
+/// whatever the image base) are relative to the start of the read-only region.
+fn section_vaddr(sh_addr: u64) -> u64 {
+    if sh_addr >= MM_RODATA_START {
+        sh_addr
+    } else {
+        MM_RODATA_START + sh_addr
+    }
+}
+
+/// The files of a program, each read once and shared by the parsers: the executable and the
+/// object file with the debug information.
+pub struct ProgramFiles {
+    /// Bytes of the executable
+    pub elf_data: Vec<u8>,
+    debug_data: memmap2::Mmap,
+}
 
-    // Find the .rodata section.
-    let rodata_section = object.sections().find(|section| {
+impl ProgramFiles {
+    pub fn open(file_path: &str, debug_file_path: &str) -> Result<Self, DebuggerError> {
+        let elf_data = fs::read(file_path)?;
+        let debug_file = fs::File::open(debug_file_path)?;
+        // SAFETY: the build artifacts are not modified while the debugger runs.
+        let debug_data = unsafe { memmap2::Mmap::map(&debug_file)? };
+        Ok(Self {
+            elf_data,
+            debug_data,
+        })
+    }
+
+    /// Parse the executable.
+    pub fn elf(&self) -> Result<Elf64<'_>, DebuggerError> {
+        Ok(Elf64::parse(&self.elf_data)?)
+    }
+
+    /// Parse the object file with the debug information.
+    pub fn debug_object(&self) -> Result<object::File<'_>, DebuggerError> {
+        Ok(object::File::parse(&*self.debug_data)?)
+    }
+}
+
+/// Parse the symbols of `section_name`, or `None` if the section does not exist.
+///
+/// Symbols are read from the debug object, and their addresses from the executable.
+fn parse_section_symbols(
+    elf: &Elf64,
+    debug_object: &object::File,
+    section_name: &str,
+) -> Result<Option<Vec<Symbol>>, DebuggerError> {
+    // Find the section.
+    let section = debug_object.sections().find(|section| {
         section
             .name()
-            .map(|name| name == ".rodata")
+            .map(|name| name == section_name)
             .unwrap_or(false)
     });
 
-    let rodata_section = match rodata_section {
+    let section = match section {
         Some(section) => section,
-        None => {
-            println!("No .rodata section found");
-            return Ok(vec![]);
-        }
+        None => return Ok(None),
     };
 
-    let rodata_addr = rodata_section.address();
-    let rodata_data = rodata_section.uncompressed_data()?;
-    let section_end = rodata_addr + rodata_data.len() as u64;
+    let section_addr = section.address();
+    let section_data = section.uncompressed_data()?;
 
-    // Get all .rodata symbols sorted by address.
-    let mut symbols: Vec<_> = object
+    // Get all symbols of the section sorted by address.
+    let mut symbols: Vec<SectionSymbol> = debug_object
         .symbols()
-        .filter_map(|symbol| {
-            if let Some(index) = symbol.section_index() {
-                if index == rodata_section.index() {
-                    Some((
-                        symbol.address(),
-                        symbol.name().unwrap_or("<unnamed>").to_string(),
-                    ))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
+        .filter(|symbol| symbol.section_index() == Some(section.index()))
+        .filter(|symbol| symbol.kind() != SymbolKind::Section)
+        .map(|symbol| {
+            (
+                symbol.address(),
+                symbol.size(),
+                symbol.name().unwrap_or("<unnamed>").to_string(),
+                symbol.kind(),
+            )
         })
         .collect();
 
-    symbols.sort_by_key(|(addr, _)| *addr);
+    symbols.sort_by_key(|(addr, _, _, _)| *addr);
 
-    // Extract the actual rodata offset from the .so file.
-    let mut rodata_offset = 0;
-    let file_data = std::fs::read(file_path)?;
-    let elf = Elf64::parse(&file_data).unwrap();
-    // Find the .rodata section.
+    // Extract the actual section offset from the .so file.
+    let mut section_offset = 0;
     for section_header in elf.section_header_table() {
-        let section_name = elf.section_name(section_header.sh_name).unwrap();
-        if section_name == b".rodata" {
-            rodata_offset = section_header.sh_addr;
+        let name = elf.section_name(section_header.sh_name)?;
+        if name == section_name.as_bytes() {
+            section_offset = section_header.sh_addr;
         }
     }
 
     // Extract data for each symbol.
-    let mut results = Vec::new();
-    for (i, (addr, name)) in symbols.iter().enumerate() {
-        let offset = if rodata_addr == 0 {
-            *addr as usize
-        } else {
-            (*addr - rodata_addr) as usize
-        };
-        // Determine end of this symbol's data.
-        let next_addr = if i + 1 < symbols.len() {
-            symbols[i + 1].0
-        } else {
-            section_end
-        };
-        let size = (next_addr - *addr) as usize;
-        let content = if offset < rodata_data.len() {
-            let end = std::cmp::min(offset + size, rodata_data.len());
-            &rodata_data[offset..end]
-        } else {
-            &[]
-        };
+    let contents = symbol_contents(&section_data, section_addr, &symbols);
+    let results = symbols
+        .into_iter()
+        .zip(contents)
+        .map(|((addr, _, name, kind), content)| Symbol {
+            name,
+            address: section_vaddr(section_offset) + addr,
+            size: content.len() as u64,
+            kind,
+            content: format_symbol_content(content, kind),
+        })
+        .collect();
 
-        // Format as ASCII if printable else as hex.
-        let msg = if content.iter().all(|&b| b.is_ascii_graphic() || b == b' ') {
-            String::from_utf8_lossy(content).to_string()
-        } else {
-            content
-                .iter()
-                .map(|b| format!("{:02x}", b))
-                .collect::<Vec<_>>()
-                .join(" ")
-        };
+    Ok(Some(results))
+}
 
-        let symbol_offset = *addr;
-        let vm_address = MM_RODATA_START + rodata_offset + symbol_offset;
-        results.push(ROData {
-            name: name.clone(),
-            address: vm_address,
-            content: msg.clone(),
-        });
+pub fn parse_rodata(
+    elf: &Elf64,
+    debug_object: &object::File,
+) -> Result<Vec<Symbol>, DebuggerError> {
+    let symbols = parse_section_symbols(elf, debug_object, ".rodata")?;
+    if symbols.is_none() {
+        println!("No .rodata section found");
+    }
+    Ok(symbols.unwrap_or_default())
+}
+
+/// Parse the initialized mutable globals of the `.data` section.
+pub fn parse_data(elf: &Elf64, debug_object: &object::File) -> Result<Vec<Symbol>, DebuggerError> {
+    Ok(parse_section_symbols(elf, debug_object, ".data")?.unwrap_or_default())
+}
+
+/// Get the VM address range of the `.bss` section of the executable, or `None` if it has no
+/// zero-initialized globals.
+pub fn parse_bss(elf: &Elf64) -> Result<Option<Range<u64>>, DebuggerError> {
+    let bss = elf.section_header_table().iter().find(|section_header| {
+        elf.section_name(section_header.sh_name)
+            .map(|name| name == b".bss")
+            .unwrap_or(false)
+    });
+    Ok(bss
+        .filter(|section_header| section_header.sh_size > 0)
+        .map(|section_header| {
+            let start = section_vaddr(section_header.sh_addr);
+            start..start + section_header.sh_size
+        }))
+}
+
+/// Reader for the DWARF sections of an object file
+type DwarfReader<'a> = EndianSlice<'a, RunTimeEndian>;
+
+/// Full path of a file of a line program
+fn line_program_file_path(
+    unit: &gimli::UnitRef<DwarfReader>,
+    header: &gimli::LineProgramHeader<DwarfReader>,
+    file: &gimli::FileEntry<DwarfReader>,
+    comp_dir: &Path,
+) -> Result<String, DebuggerError> {
+    let mut path = comp_dir.to_path_buf();
+
+    // The directory index 0 is defined to correspond to the compilation unit directory
+    if file.directory_index() != 0 {
+        if let Some(dir) = file.directory(header) {
+            path.push(unit.attr_string(dir)?.to_string_lossy().as_ref());
+        }
+    }
+
+    path.push(
+        unit.attr_string(file.path_name())?
+            .to_string_lossy()
+            .as_ref(),
+    );
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Name of a DIE, taken from the abstract instance for inlined entries
+fn die_name(
+    unit: &gimli::UnitRef<DwarfReader>,
+    entry: &gimli::DebuggingInformationEntry<DwarfReader>,
+) -> Result<Option<String>, DebuggerError> {
+    if let Some(value) = entry.attr_value(gimli::DW_AT_name)? {
+        return Ok(Some(
+            unit.attr_string(value)?.to_string_lossy().into_owned(),
+        ));
     }
+    if let Some(gimli::AttributeValue::UnitRef(offset)) =
+        entry.attr_value(gimli::DW_AT_abstract_origin)?
+    {
+        let origin = unit.entry(offset)?;
+        if let Some(value) = origin.attr_value(gimli::DW_AT_name)? {
+            return Ok(Some(
+                unit.attr_string(value)?.to_string_lossy().into_owned(),
+            ));
+        }
+    }
+    Ok(None)
+}
 
-    Ok(results)
+/// Size in bytes of the type of a DIE, looking through typedefs and qualifiers
+fn die_type_size(
+    unit: &gimli::UnitRef<DwarfReader>,
+    entry: &gimli::DebuggingInformationEntry<DwarfReader>,
+) -> Result<Option<u64>, DebuggerError> {
+    let mut type_attr = entry.attr_value(gimli::DW_AT_type)?;
+    if type_attr.is_none() {
+        if let Some(gimli::AttributeValue::UnitRef(offset)) =
+            entry.attr_value(gimli::DW_AT_abstract_origin)?
+        {
+            type_attr = unit.entry(offset)?.attr_value(gimli::DW_AT_type)?;
+        }
+    }
+    // Bounded in case of malformed, cyclic type references
+    for _ in 0..8 {
+        let Some(gimli::AttributeValue::UnitRef(offset)) = type_attr else {
+            return Ok(None);
+        };
+        let ty = unit.entry(offset)?;
+        if let Some(size) = ty
+            .attr_value(gimli::DW_AT_byte_size)?
+            .and_then(|value| value.udata_value())
+        {
+            return Ok(Some(size));
+        }
+        type_attr = ty.attr_value(gimli::DW_AT_type)?;
+    }
+    Ok(None)
 }
 
 #[derive(Debug, Clone)]
@@ -124,36 +324,94 @@ pub struct SourceLocation {
     pub file: String,
     pub line: u32,
     pub column: u32,
-    _address: u64,
+    address: u64,
+}
+
+/// A function inlined into its caller
+#[derive(Debug, Clone)]
+pub struct InlineFrame {
+    /// Name of the inlined function
+    pub name: String,
+    /// Source file of the call site in the caller
+    pub call_file: String,
+    /// Source line of the call site in the caller
+    pub call_line: usize,
+    /// Depth of the DIE, nested inlined functions are deeper
+    depth: isize,
+    /// Addresses of the inlined code
+    range: Range<u64>,
+}
+
+/// Where a variable is stored, from a DWARF location expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableLocation {
+    /// In a register
+    Register(u16),
+    /// In memory, at an offset from the value of a register
+    RegisterOffset(u16, i64),
+    /// A location the debugger cannot evaluate (location lists, composite expressions, ...)
+    Unsupported,
+}
+
+/// A local variable or parameter
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub location: VariableLocation,
+    /// Size of the variable's type in bytes, if known
+    pub size: Option<u64>,
+    /// Addresses at which the variable is in scope
+    scope: Vec<Range<u64>>,
 }
 
 pub struct LineMap {
     /// Maps instruction addresses to source line numbers
-    address_to_line: HashMap<u64, usize>,
+    address_to_line: BTreeMap<u64, usize>,
     /// Maps line numbers to instruction addresses
     line_to_addresses: HashMap<usize, Vec<u64>>,
     /// Maps DWARF addresses to actual SBPF program counters
-    dwarf_to_pc: HashMap<u64, u64>,
+    dwarf_to_pc: BTreeMap<u64, u64>,
     /// Maps SBPF program counters to DWARF addresses
-    pc_to_dwarf: HashMap<u64, u64>,
+    pc_to_dwarf: BTreeMap<u64, u64>,
+    /// Virtual address of the `.text` section, where PC 0 is
+    text_address: u64,
     /// Complete source location information
-    source_locations: HashMap<u64, SourceLocation>,
-    /// Line to address mapping with file names
-    line_to_address: HashMap<(String, u32), u64>,
+    source_locations: BTreeMap<u64, SourceLocation>,
+    /// Maps (file, line) pairs to instruction addresses
+    file_line_to_addresses: HashMap<(String, usize), Vec<u64>>,
     /// File names
     files: Vec<String>,
+    /// Address ranges of the functions described in the DWARF info
+    subprograms: Vec<Range<u64>>,
+    /// Function symbols from the ELF symbol tables, sorted by address
+    functions: Vec<(u64, String)>,
+    /// Inlined functions described in the DWARF info
+    inline_frames: Vec<InlineFrame>,
+    /// Local variables and parameters described in the DWARF info
+    variables: Vec<Variable>,
+}
+
+impl Default for LineMap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LineMap {
     pub fn new() -> Self {
         Self {
-            address_to_line: HashMap::new(),
+            address_to_line: BTreeMap::new(),
             line_to_addresses: HashMap::new(),
-            dwarf_to_pc: HashMap::new(),
-            pc_to_dwarf: HashMap::new(),
-            source_locations: HashMap::new(),
-            line_to_address: HashMap::new(),
+            dwarf_to_pc: BTreeMap::new(),
+            pc_to_dwarf: BTreeMap::new(),
+            text_address: 0,
+            source_locations: BTreeMap::new(),
+            file_line_to_addresses: HashMap::new(),
             files: Vec::new(),
+            subprograms: Vec::new(),
+            functions: Vec::new(),
+            inline_frames: Vec::new(),
+            variables: Vec::new(),
         }
     }
 
@@ -165,16 +423,25 @@ impl LineMap {
 
     /// Parse DWARF debug information from ELF data
     pub fn from_elf_data(file_data: &[u8]) -> Result<Self, DebuggerError> {
-        let object = object::File::parse(file_data)?;
+        Self::from_object(&object::File::parse(file_data)?)
+    }
 
+    /// Parse DWARF debug information from a parsed object file
+    pub fn from_object(object: &object::File) -> Result<Self, DebuggerError> {
         let mut line_map = Self::new();
+        // Linked programs load `.text` at a nonzero address, relocatable objects at zero.
+        line_map.text_address = object
+            .section_by_name(".text")
+            .map_or(0, |section| section.address());
 
         // Parse DWARF debug information directly from the object
-        line_map.parse_debug_info_from_object(&object)?;
+        line_map.parse_debug_info_from_object(object)?;
 
         // Build the PC mapping after parsing
         line_map.build_pc_mapping();
 
+        line_map.parse_function_symbols(object);
+
         Ok(line_map)
     }
 
@@ -219,14 +486,14 @@ impl LineMap {
                 .map_err(|e| DebuggerError::UnitError(e))?;
             let unit = unit.unit_ref(&dwarf);
 
+            let comp_dir = if let Some(ref dir) = unit.comp_dir {
+                PathBuf::from(dir.to_string_lossy().into_owned())
+            } else {
+                PathBuf::new()
+            };
+
             // Get the line program for the compilation unit
             if let Some(program) = unit.line_program.clone() {
-                let comp_dir = if let Some(ref dir) = unit.comp_dir {
-                    PathBuf::from(dir.to_string_lossy().into_owned())
-                } else {
-                    PathBuf::new()
-                };
-
                 // Iterate over the line program rows
                 let mut rows = program.rows();
                 while let Some((header, row)) =
@@ -236,28 +503,7 @@ impl LineMap {
                         // Determine the file path
                         let mut file_path = String::new();
                         if let Some(file) = row.file(header) {
-                            let mut path = PathBuf::new();
-                            path.clone_from(&comp_dir);
-
-                            // The directory index 0 is defined to correspond to the compilation unit directory
-                            if file.directory_index() != 0 {
-                                if let Some(dir) = file.directory(header) {
-                                    path.push(
-                                        unit.attr_string(dir)
-                                            .map_err(|e| DebuggerError::ReadError(e))?
-                                            .to_string_lossy()
-                                            .as_ref(),
-                                    );
-                                }
-                            }
-
-                            path.push(
-                                unit.attr_string(file.path_name())
-                                    .map_err(|e| DebuggerError::ReadError(e))?
-                                    .to_string_lossy()
-                                    .as_ref(),
-                            );
-                            file_path = path.to_string_lossy().to_string();
+                            file_path = line_program_file_path(&unit, header, file, &comp_dir)?;
                         }
 
                         // Determine line/column
@@ -270,54 +516,227 @@ impl LineMap {
                             gimli::ColumnType::Column(column) => column.get() as u32,
                         };
 
-                        let address = row.address();
-
-                        // Store the mapping
-                        self.address_to_line.insert(address, line as usize);
+                        self.add_row(row.address(), file_path, line, column);
+                    }
+                }
+            }
 
-                        // Add to line_to_addresses
-                        self.line_to_addresses
-                            .entry(line as usize)
-                            .or_insert_with(Vec::new)
-                            .push(address);
+            // Record the address ranges of functions and inlined functions, and the variables in
+            // their scopes, if the producer emitted them
+            let mut depth = 0;
+            // Enclosing scopes as (depth, address ranges, frame base register)
+            let mut scopes: Vec<(isize, Vec<Range<u64>>, Option<u16>)> = Vec::new();
+            let mut entries = unit.entries();
+            while let Some((delta, entry)) = entries
+                .next_dfs()
+                .map_err(|e| DebuggerError::UnitError(e))?
+            {
+                depth += delta;
+                while scopes
+                    .last()
+                    .is_some_and(|(scope_depth, _, _)| *scope_depth >= depth)
+                {
+                    scopes.pop();
+                }
 
-                        // Create source location
-                        let source_loc = SourceLocation {
-                            file: file_path.clone(),
-                            line,
-                            column,
-                            _address: address,
+                let tag = entry.tag();
+                match tag {
+                    gimli::DW_TAG_variable | gimli::DW_TAG_formal_parameter => {
+                        let Some((_, scope, frame_base)) = scopes.last() else {
+                            // Global variable
+                            continue;
                         };
-                        self.source_locations.insert(address, source_loc);
+                        let location = match entry.attr_value(gimli::DW_AT_location)? {
+                            Some(gimli::AttributeValue::Exprloc(expression)) => {
+                                let mut operations = expression.operations(unit.encoding());
+                                match (operations.next()?, operations.next()?) {
+                                    (Some(gimli::Operation::Register { register }), None) => {
+                                        VariableLocation::Register(register.0)
+                                    }
+                                    (
+                                        Some(gimli::Operation::RegisterOffset {
+                                            register,
+                                            offset,
+                                            ..
+                                        }),
+                                        None,
+                                    ) => VariableLocation::RegisterOffset(register.0, offset),
+                                    (Some(gimli::Operation::FrameOffset { offset }), None) => {
+                                        match frame_base {
+                                            Some(register) => {
+                                                VariableLocation::RegisterOffset(*register, offset)
+                                            }
+                                            None => VariableLocation::Unsupported,
+                                        }
+                                    }
+                                    _ => VariableLocation::Unsupported,
+                                }
+                            }
+                            // Location lists
+                            Some(_) => VariableLocation::Unsupported,
+                            // Optimized out
+                            None => continue,
+                        };
+                        self.variables.push(Variable {
+                            name: die_name(&unit, entry)?.unwrap_or_else(|| "?".to_string()),
+                            location,
+                            size: die_type_size(&unit, entry)?,
+                            scope: scope.clone(),
+                        });
+                        continue;
+                    }
+                    gimli::DW_TAG_subprogram
+                    | gimli::DW_TAG_inlined_subroutine
+                    | gimli::DW_TAG_lexical_block => {}
+                    _ => continue,
+                }
 
-                        // Add to line_to_address mapping
-                        self.line_to_address
-                            .insert((file_path.clone(), line), address);
+                let mut ranges = unit
+                    .die_ranges(entry)
+                    .map_err(|e| DebuggerError::UnitError(e))?;
+                let mut entry_ranges = Vec::new();
+                while let Some(range) = ranges.next().map_err(|e| DebuggerError::UnitError(e))? {
+                    entry_ranges.push(range.begin..range.end);
+                }
 
-                        // Add file to files list if not already present
-                        if !file_path.is_empty() && !self.files.contains(&file_path) {
-                            self.files.push(file_path);
+                // Functions using a register as frame base
+                let frame_base = if tag == gimli::DW_TAG_subprogram {
+                    match entry.attr_value(gimli::DW_AT_frame_base)? {
+                        Some(gimli::AttributeValue::Exprloc(expression)) => {
+                            match expression.operations(unit.encoding()).next()? {
+                                Some(gimli::Operation::Register { register }) => Some(register.0),
+                                _ => None,
+                            }
                         }
+                        _ => None,
                     }
+                } else {
+                    scopes.last().and_then(|(_, _, frame_base)| *frame_base)
+                };
+                let scope = match scopes.last() {
+                    // Blocks without addresses cover their parent
+                    Some((_, parent, _))
+                        if entry_ranges.is_empty() && tag == gimli::DW_TAG_lexical_block =>
+                    {
+                        parent.clone()
+                    }
+                    _ => entry_ranges.clone(),
+                };
+                scopes.push((depth, scope, frame_base));
+
+                match tag {
+                    gimli::DW_TAG_subprogram => self.subprograms.extend(entry_ranges),
+                    gimli::DW_TAG_inlined_subroutine => {
+                        let name = die_name(&unit, entry)?.unwrap_or_else(|| "?".to_string());
+                        let call_line = entry
+                            .attr_value(gimli::DW_AT_call_line)?
+                            .and_then(|value| value.udata_value())
+                            .unwrap_or(0) as usize;
+                        let mut call_file = String::new();
+                        if let (Some(index), Some(program)) = (
+                            entry
+                                .attr_value(gimli::DW_AT_call_file)?
+                                .and_then(|value| value.udata_value()),
+                            &unit.line_program,
+                        ) {
+                            let header = program.header();
+                            if let Some(file) = header.file(index) {
+                                call_file = line_program_file_path(&unit, header, file, &comp_dir)?;
+                            }
+                        }
+                        for range in entry_ranges {
+                            self.inline_frames.push(InlineFrame {
+                                name: name.clone(),
+                                call_file: call_file.clone(),
+                                call_line,
+                                depth,
+                                range,
+                            });
+                        }
+                    }
+                    _ => {}
                 }
             }
         }
 
         Ok(())
     }
+
+    /// Collect the `STT_FUNC` symbols of `.symtab` and `.dynsym`
+    fn parse_function_symbols(&mut self, obj_file: &object::File) {
+        for symbol in obj_file.symbols().chain(obj_file.dynamic_symbols()) {
+            if symbol.kind() != SymbolKind::Text {
+                continue;
+            }
+            if let Ok(name) = symbol.name() {
+                if !name.is_empty() {
+                    self.functions.push((symbol.address(), name.to_string()));
+                }
+            }
+        }
+        self.functions.sort();
+        self.functions.dedup();
+    }
+
+    /// Store a row of the line table
+    pub(crate) fn add_row(&mut self, address: u64, file_path: String, line: u32, column: u32) {
+        // Store the mapping
+        self.address_to_line.insert(address, line as usize);
+
+        // Add to line_to_addresses
+        self.line_to_addresses
+            .entry(line as usize)
+            .or_insert_with(Vec::new)
+            .push(address);
+
+        // Create source location
+        let source_loc = SourceLocation {
+            file: file_path.clone(),
+            line,
+            column,
+            address,
+        };
+        self.source_locations.insert(address, source_loc);
+
+        // Add to file_line_to_addresses
+        self.file_line_to_addresses
+            .entry((file_path.clone(), line as usize))
+            .or_insert_with(Vec::new)
+            .push(address);
+
+        // Add file to files list if not already present
+        if !file_path.is_empty() && !self.files.contains(&file_path) {
+            self.files.push(file_path);
+        }
+    }
 }
 
 impl LineMap {
     /// Build mapping between DWARF addresses and SBPF program counters
     fn build_pc_mapping(&mut self) {
-        // For SBPF, the DWARF addresses are typically the actual instruction addresses
-        // We map them directly to program counters
+        // The interpreter counts PCs from the start of `.text`, while DWARF addresses are virtual
+        // addresses. Addresses outside of `.text` have no PC.
         for &dwarf_addr in self.address_to_line.keys() {
-            self.dwarf_to_pc.insert(dwarf_addr, dwarf_addr);
-            self.pc_to_dwarf.insert(dwarf_addr, dwarf_addr);
+            if let Some(pc) = dwarf_addr.checked_sub(self.text_address) {
+                self.dwarf_to_pc.insert(dwarf_addr, pc);
+                self.pc_to_dwarf.insert(pc, dwarf_addr);
+            }
         }
     }
 
+    /// Get the DWARF address of a PC
+    fn dwarf_address(&self, pc: u64) -> u64 {
+        self.pc_to_dwarf
+            .get(&pc)
+            .copied()
+            .unwrap_or_else(|| pc.saturating_add(self.text_address))
+    }
+
+    /// Whether the debug information maps any instruction to a source line
+    pub fn has_line_info(&self) -> bool {
+        !self.address_to_line.is_empty()
+    }
+
     /// Get the source line number for a given instruction address
     pub fn get_line_for_address(&self, address: u64) -> Option<usize> {
         self.address_to_line.get(&address).copied()
@@ -329,14 +748,14 @@ impl LineMap {
     }
 
     /// Get the current line number for a PC (program counter)
+    ///
+    /// PCs between two line table rows belong to the line of the preceding row.
     pub fn get_line_for_pc(&self, pc: u64) -> Option<usize> {
-        // Try to find the DWARF address for this PC
-        if let Some(&dwarf_addr) = self.pc_to_dwarf.get(&pc) {
-            return self.get_line_for_address(dwarf_addr);
-        }
-
-        // If not found, try the PC directly as it might be the same as DWARF address
-        self.get_line_for_address(pc)
+        let dwarf_addr = self.dwarf_address(pc);
+        self.address_to_line
+            .range(..=dwarf_addr)
+            .next_back()
+            .map(|(_, &line)| line)
     }
 
     /// Get all PCs for a given line number
@@ -354,6 +773,114 @@ impl LineMap {
         }
     }
 
+    /// Get all PCs for a given line number of a source file
+    pub fn get_pcs_for_file_line(&self, file: &str, line: usize) -> Vec<u64> {
+        if let Some(dwarf_addresses) = self.file_line_to_addresses.get(&(file.to_string(), line)) {
+            dwarf_addresses
+                .iter()
+                .filter_map(|&dwarf_addr| self.dwarf_to_pc.get(&dwarf_addr).copied())
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Get the source files that have instructions at a given line number
+    pub fn get_files_for_line(&self, line: usize) -> Vec<&str> {
+        let mut files: Vec<&str> = self
+            .file_line_to_addresses
+            .keys()
+            .filter(|(_, l)| *l == line)
+            .map(|(file, _)| file.as_str())
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Find the name of a source file as recorded in the DWARF info, matching either the full
+    /// path or a path suffix (e.g. `src/main.s` for `/home/user/project/src/main.s`)
+    pub fn find_file(&self, file: &str) -> Option<&str> {
+        if let Some(found) = self.files.iter().find(|f| *f == file) {
+            return Some(found);
+        }
+        let path = Path::new(file);
+        self.files
+            .iter()
+            .find(|f| Path::new(f).ends_with(path) || path.ends_with(f))
+            .map(String::as_str)
+    }
+
+    /// Get the source location of the line table row covering a PC
+    pub fn get_source_location_for_pc(&self, pc: u64) -> Option<&SourceLocation> {
+        let dwarf_addr = self.dwarf_address(pc);
+        self.source_locations
+            .range(..=dwarf_addr)
+            .next_back()
+            .map(|(_, location)| location)
+    }
+
+    /// Get the range of source lines of the function containing a PC
+    ///
+    /// Without subprogram information, as is the case for assembly, this is the range of lines of
+    /// the PC's source file that have instructions.
+    pub fn get_function_line_range(&self, pc: u64) -> Option<RangeInclusive<usize>> {
+        let location = self.get_source_location_for_pc(pc)?;
+        let function = self
+            .subprograms
+            .iter()
+            .find(|range| range.contains(&location.address));
+        let mut lines = self
+            .source_locations
+            .values()
+            .filter(|loc| loc.file == location.file && loc.line != 0)
+            .filter(|loc| function.map_or(true, |range| range.contains(&loc.address)))
+            .map(|loc| loc.line as usize);
+        let first = lines.next()?;
+        let (min, max) = lines.fold((first, first), |(min, max), line| {
+            (min.min(line), max.max(line))
+        });
+        Some(min..=max)
+    }
+
+    /// Get the name of the function containing a PC, i.e. the nearest preceding function symbol
+    pub fn function_for_pc(&self, pc: u64) -> Option<&str> {
+        let dwarf_addr = self.dwarf_address(pc);
+        let index = self
+            .functions
+            .partition_point(|(address, _)| *address <= dwarf_addr);
+        index
+            .checked_sub(1)
+            .map(|index| self.functions[index].1.as_str())
+    }
+
+    /// Get the inlined functions containing a PC, innermost first
+    ///
+    /// Each entry is a logical frame of the inline call chain within the physical frame of the PC.
+    pub fn get_inline_frames(&self, pc: u64) -> Vec<&InlineFrame> {
+        let dwarf_addr = self.dwarf_address(pc);
+        let mut frames: Vec<&InlineFrame> = self
+            .inline_frames
+            .iter()
+            .filter(|frame| frame.range.contains(&dwarf_addr))
+            .collect();
+        frames.sort_by_key(|frame| std::cmp::Reverse(frame.depth));
+        frames
+    }
+
+    /// Get the local variables and parameters in scope at a PC
+    pub fn get_variables_for_pc(&self, pc: u64) -> Vec<&Variable> {
+        let dwarf_addr = self.dwarf_address(pc);
+        self.variables
+            .iter()
+            .filter(|variable| {
+                variable
+                    .scope
+                    .iter()
+                    .any(|range| range.contains(&dwarf_addr))
+            })
+            .collect()
+    }
+
     /// Get source location for a given address
     pub fn get_source_location(&self, address: u64) -> Option<&SourceLocation> {
         self.source_locations.get(&address)
@@ -402,3 +929,255 @@ impl LineMap {
         &self.line_to_addresses
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::{BuildResult, DEFAULT_IMAGE_BASE};
+    use crate::debugger::DebugEvent;
+    use crate::test_utils::{build_or_skip, test_config, with_debugger};
+
+    /// Build a program of `test_programs`, or `None` if the Solana platform tools are missing.
+    fn build_test_program(name: &str, image_base: u64) -> Option<BuildResult> {
+        let assembly_file = format!(
+            "{}/../../test_programs/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        let mut config = test_config(&[assembly_file], true);
+        config.image_base = image_base;
+        build_or_skip(&config)
+    }
+
+    fn program_files(build_result: &BuildResult) -> ProgramFiles {
+        ProgramFiles::open(&build_result.shared_object_file, &build_result.object_file).unwrap()
+    }
+
+    #[test]
+    fn test_get_line_for_pc_between_rows() {
+        let mut line_map = LineMap::new();
+        for (address, line) in [(0x0, 3), (0x18, 5), (0x40, 9)] {
+            line_map.add_row(address, "test.s".to_string(), line, 0);
+        }
+        line_map.build_pc_mapping();
+
+        assert_eq!(line_map.get_line_for_pc(0x0), Some(3));
+        assert_eq!(line_map.get_line_for_pc(0x10), Some(3));
+        assert_eq!(line_map.get_line_for_pc(0x18), Some(5));
+        assert_eq!(line_map.get_line_for_pc(0x38), Some(5));
+        assert_eq!(line_map.get_line_for_pc(0x48), Some(9));
+        // Breakpoints still resolve to the exact row addresses.
+        assert_eq!(line_map.get_pcs_for_line(5), vec![0x18]);
+    }
+
+    #[test]
+    fn test_pc_mapping_with_text_address() {
+        let mut line_map = LineMap::new();
+        line_map.text_address = 0x120;
+        for (address, line) in [(0x120, 3), (0x138, 5)] {
+            line_map.add_row(address, "test.s".to_string(), line, 0);
+        }
+        line_map.functions = vec![(0x120, "entrypoint".to_string())];
+        line_map.build_pc_mapping();
+
+        assert_eq!(line_map.get_pcs_for_line(5), vec![0x18]);
+        assert_eq!(line_map.get_pcs_for_file_line("test.s", 3), vec![0x0]);
+        assert_eq!(line_map.get_line_for_pc(0x0), Some(3));
+        assert_eq!(line_map.get_line_for_pc(0x20), Some(5));
+        assert_eq!(line_map.function_for_pc(0x8), Some("entrypoint"));
+    }
+
+    #[test]
+    fn test_line_pcs_of_linked_program() {
+        let Some(build_result) = build_test_program("hello_solana.s", DEFAULT_IMAGE_BASE) else {
+            return;
+        };
+        let object_map = LineMap::from_elf_file(&build_result.object_file).unwrap();
+        let linked_map = LineMap::from_elf_file(&build_result.shared_object_file).unwrap();
+        assert_ne!(linked_map.text_address, 0);
+
+        let mut lines: Vec<_> = object_map.get_line_to_addresses().keys().copied().collect();
+        lines.sort();
+        assert!(!lines.is_empty());
+        for line in lines {
+            assert_eq!(
+                linked_map.get_pcs_for_line(line),
+                object_map.get_pcs_for_line(line)
+            );
+        }
+    }
+
+    #[test]
+    fn test_line_map_of_linked_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let main_file = dir.path().join("main.s");
+        let helper_file = dir.path().join("helper.s");
+        fs::write(
+            &main_file,
+            ".globl entrypoint\nentrypoint:\n  call helper\n  exit\n",
+        )
+        .unwrap();
+        fs::write(
+            &helper_file,
+            ".globl helper\nhelper:\n  mov64 r0, 42\n  exit\n",
+        )
+        .unwrap();
+        let Some(build_result) = build_or_skip(&test_config(&[&main_file, &helper_file], true))
+        else {
+            return;
+        };
+
+        // The first object file knows nothing of the second file, the shared object knows both.
+        let object_map = LineMap::from_elf_file(&build_result.object_file).unwrap();
+        assert!(object_map.find_file("helper.s").is_none());
+        let linked_map = LineMap::from_elf_file(&build_result.shared_object_file).unwrap();
+        let main = linked_map.find_file("main.s").unwrap().to_string();
+        let helper = linked_map.find_file("helper.s").unwrap().to_string();
+        assert_eq!(linked_map.get_pcs_for_file_line(&main, 3), vec![0x0]);
+        // A breakpoint on the first instruction of the helper, linked after the two of main.
+        assert_eq!(linked_map.get_pcs_for_file_line(&helper, 3), vec![0x10]);
+        assert_eq!(linked_map.get_line_for_pc(0x18), Some(4));
+
+        // The same layout as the linked program, jumping over `exit` instead of calling.
+        with_debugger("ja +1\nexit\nmov64 r0, 42\nexit", |mut dbg| {
+            dbg.set_dwarf_line_map(linked_map);
+            dbg.set_breakpoint_at_line("helper.s", 3).unwrap();
+            assert!(matches!(
+                dbg.run().unwrap(),
+                DebugEvent::Breakpoint(0x10, Some(3))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_function_for_pc() {
+        let mut line_map = LineMap::new();
+        line_map.functions = vec![
+            (0x0, "entrypoint".to_string()),
+            (0x30, "helper".to_string()),
+        ];
+
+        assert_eq!(line_map.function_for_pc(0x0), Some("entrypoint"));
+        assert_eq!(line_map.function_for_pc(0x28), Some("entrypoint"));
+        assert_eq!(line_map.function_for_pc(0x30), Some("helper"));
+        assert_eq!(line_map.function_for_pc(0x100), Some("helper"));
+
+        line_map.functions = vec![(0x10, "helper".to_string())];
+        assert_eq!(line_map.function_for_pc(0x8), None);
+    }
+
+    #[test]
+    fn test_inline_frames() {
+        let mut line_map = LineMap::new();
+        for (name, depth, range, call_line) in
+            [("outer", 2, 0x10..0x40, 7), ("inner", 3, 0x18..0x28, 21)]
+        {
+            line_map.inline_frames.push(InlineFrame {
+                name: name.to_string(),
+                call_file: "lib.rs".to_string(),
+                call_line,
+                depth,
+                range,
+            });
+        }
+
+        let names = |pc| -> Vec<String> {
+            line_map
+                .get_inline_frames(pc)
+                .iter()
+                .map(|frame| frame.name.clone())
+                .collect()
+        };
+        assert_eq!(names(0x8), Vec::<String>::new());
+        assert_eq!(names(0x10), vec!["outer"]);
+        assert_eq!(names(0x20), vec!["inner", "outer"]);
+    }
+
+    #[test]
+    fn test_rodata_symbol_sizes() {
+        // "hi\0" followed by alignment padding before an 8-byte value.
+        let mut data = b"hi\0".to_vec();
+        data.resize(8, 0xaa);
+        data.extend_from_slice(&42u64.to_le_bytes());
+        let symbols = vec![
+            (0, 3, "greeting".to_string(), SymbolKind::Data),
+            (8, 8, "answer".to_string(), SymbolKind::Data),
+        ];
+
+        let contents = symbol_contents(&data, 0, &symbols);
+        assert_eq!(contents[0], b"hi\0");
+        assert_eq!(contents[1], &42u64.to_le_bytes());
+        assert_eq!(format_symbol_content(contents[0], SymbolKind::Data), "hi");
+        assert_eq!(
+            format_symbol_content(contents[1], SymbolKind::Data),
+            "2a 00 00 00 00 00 00 00"
+        );
+
+        // Labels without a size extend to the next symbol.
+        let labels = vec![
+            (0, 0, "greeting".to_string(), SymbolKind::Unknown),
+            (8, 0, "answer".to_string(), SymbolKind::Unknown),
+        ];
+        let contents = symbol_contents(&data, 0, &labels);
+        assert_eq!(contents[0].len(), 8);
+        assert_eq!(contents[1].len(), 8);
+    }
+
+    #[test]
+    fn test_parse_bss() {
+        let Some(build_result) = build_test_program("bss_counter.s", DEFAULT_IMAGE_BASE) else {
+            return;
+        };
+
+        let files = program_files(&build_result);
+        let bss = parse_bss(&files.elf().unwrap()).unwrap().unwrap();
+        assert_eq!(bss.end - bss.start, 8);
+        assert!(bss.start > MM_RODATA_START);
+    }
+
+    #[test]
+    fn test_section_vaddr() {
+        assert_eq!(section_vaddr(0x120), MM_RODATA_START + 0x120);
+        assert_eq!(
+            section_vaddr(MM_RODATA_START + 0x120),
+            MM_RODATA_START + 0x120
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "demangle")]
+    fn test_demangle() {
+        assert_eq!(
+            demangle("_ZN4core3fmt5write17h0123456789abcdefE"),
+            "core::fmt::write"
+        );
+        assert_eq!(demangle("_RNvCs1234_7mycrate3foo"), "mycrate::foo");
+        assert_eq!(demangle("_Z3addii"), "add(int, int)");
+        assert_eq!(demangle("entrypoint"), "entrypoint");
+        assert_eq!(demangle("function_foo"), "function_foo");
+    }
+
+    #[test]
+    fn test_rodata_addresses_with_image_base() {
+        let Some(default_build) = build_test_program("hello_solana.s", DEFAULT_IMAGE_BASE) else {
+            return;
+        };
+        let other_build = build_test_program("hello_solana.s", 0x200000000).unwrap();
+
+        let addresses = |build_result: &BuildResult| {
+            let files = program_files(build_result);
+            parse_rodata(&files.elf().unwrap(), &files.debug_object().unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|symbol| (symbol.name, symbol.address))
+                .collect::<Vec<_>>()
+        };
+        let default_addresses = addresses(&default_build);
+        assert!(!default_addresses.is_empty());
+        assert!(default_addresses
+            .iter()
+            .all(|(_, address)| *address > MM_RODATA_START));
+        // The default linker script places the sections itself, so the base does not move them.
+        assert_eq!(addresses(&other_build), default_addresses);
+    }
+}
@@ -1,306 +1,2337 @@
 use crate::{
-    adapter::DebuggerInterface,
-    debugger::{DebugMode, Debugger},
+    adapter::{register_ascii, register_json, symbol_json, DebuggerInterface},
+    debugger::{
+        DebugEvent, DebugMode, Debugger, MemoryMap, RegisterName, SavedBreakpoints, Snapshot,
+        DEFAULT_PROFILE_TOP,
+    },
+    input::{parse_accounts, InputAccount},
+    parser::{demangle, Symbol},
+    DebugContextObject,
 };
-use solana_sbpf::vm::ContextObject;
-use std::io::{self, Write};
+use rustyline::{error::ReadlineError, DefaultEditor};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Width of the symbol column in the `rodata` and `data` tables
+const SYMBOL_NAME_WIDTH: usize = 13;
+/// Width of the value column in the `rodata` and `data` tables
+const SYMBOL_VALUE_WIDTH: usize = 24;
+
+/// Truncate `value` to at most `width` characters, marking truncation with "...".
+fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        value.to_string()
+    } else {
+        let mut truncated: String = value.chars().take(width.saturating_sub(3)).collect();
+        truncated.push_str("...");
+        truncated
+    }
+}
+
+/// Format the `rodata` or `data` overview table, truncating long names and values.
+fn format_symbol_table(symbols: &[Symbol]) -> String {
+    let separator = "+---------------+----------------------+--------------------------+\n";
+    let mut table = String::new();
+    table.push_str(separator);
+    table.push_str("| Symbol        | Address              | Value                    |\n");
+    table.push_str(separator);
+    for symbol in symbols {
+        table.push_str(&format!(
+            "| {:<13} | 0x{:016x}   | {:<24} |\n",
+            truncate(&symbol.display_name(), SYMBOL_NAME_WIDTH),
+            symbol.address,
+            truncate(&symbol.content, SYMBOL_VALUE_WIDTH)
+        ));
+    }
+    table.push_str(separator);
+    table
+}
+
+/// Format the memory map with one region per line.
+fn format_memory_maps(maps: &[MemoryMap]) -> String {
+    let mut output = format!("{:<18} {:<18} {:<4} {}\n", "Start", "End", "Perm", "Name");
+    for map in maps {
+        output.push_str(&format!(
+            "0x{:016x} 0x{:016x} {:<4} {}\n",
+            map.start,
+            map.end,
+            if map.writable { "rw" } else { "r" },
+            map.name
+        ));
+    }
+    output
+}
+
+/// Format bytes as space-separated hex, 16 bytes per line.
+fn format_hex(data: &[u8]) -> String {
+    data.chunks(16)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Largest number of bytes the `x` command reads at once
+const MAX_EXAMINE_BYTES: usize = 64 * 1024;
+
+/// Count, format and unit size of the `x` command, as in `x/8xb`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Examine {
+    count: usize,
+    /// `x` (hex), `d` (signed), `u` (unsigned) or `c` (char)
+    format: char,
+    /// Unit size in bytes: 1 (`b`), 2 (`h`), 4 (`w`) or 8 (`g`)
+    size: usize,
+}
+
+impl Examine {
+    /// Parse the part after the `/`, e.g. `8xb`. Missing fields default to one unit of hex words,
+    /// and to bytes for the `c` format.
+    fn parse(spec: &str) -> Result<Self, String> {
+        let digits = spec.len() - spec.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let count = match &spec[..digits] {
+            "" => 1,
+            count => count
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid count '{}'", count))?,
+        };
+        let mut format = None;
+        let mut size = None;
+        for letter in spec[digits..].chars() {
+            match letter {
+                'x' | 'd' | 'u' | 'c' => format = Some(letter),
+                'b' => size = Some(1),
+                'h' => size = Some(2),
+                'w' => size = Some(4),
+                'g' => size = Some(8),
+                _ => return Err(format!("Unknown format or size '{}'", letter)),
+            }
+        }
+        let format = format.unwrap_or('x');
+        let size = size.unwrap_or(if format == 'c' { 1 } else { 4 });
+        let len = count.checked_mul(size);
+        if len.filter(|&len| len <= MAX_EXAMINE_BYTES).is_none() {
+            return Err(format!(
+                "Range too large, at most {} bytes can be examined at once",
+                MAX_EXAMINE_BYTES
+            ));
+        }
+        Ok(Self {
+            count,
+            format,
+            size,
+        })
+    }
+
+    /// Number of bytes to read, at most [`MAX_EXAMINE_BYTES`]
+    fn len(&self) -> u64 {
+        (self.count * self.size) as u64
+    }
+
+    /// Format a single unit.
+    fn format_unit(&self, unit: &[u8]) -> String {
+        let mut bytes = [0; 8];
+        bytes[..unit.len()].copy_from_slice(unit);
+        let value = u64::from_le_bytes(bytes);
+        let shift = 64 - 8 * self.size as u32;
+        match self.format {
+            'd' => (((value << shift) as i64) >> shift).to_string(),
+            'u' => value.to_string(),
+            'c' => {
+                let byte = unit[0];
+                format!("{} '{}'", byte, std::ascii::escape_default(byte))
+            }
+            _ => format!("0x{:0width$x}", value, width = 2 * self.size),
+        }
+    }
 
-pub struct Repl<'a, 'b, C: ContextObject> {
-    pub dbg: Debugger<'a, 'b, C>,
+    /// Format the units read at `address`, a line of up to 16 bytes at a time.
+    fn format(&self, address: u64, data: &[u8]) -> String {
+        let per_line = (16 / self.size).min(8);
+        data.chunks(self.size * per_line)
+            .enumerate()
+            .map(|(index, line)| {
+                let units: Vec<_> = line
+                    .chunks(self.size)
+                    .map(|unit| self.format_unit(unit))
+                    .collect();
+                format!(
+                    "0x{:016x}: {}\n",
+                    address + (index * self.size * per_line) as u64,
+                    units.join("  ")
+                )
+            })
+            .collect()
+    }
+}
+
+/// Whether `name` is the raw or the demangled name of the symbol.
+fn symbol_matches(symbol: &Symbol, name: &str) -> bool {
+    symbol.name == name || symbol.display_name() == name
+}
+
+/// Format a single symbol with its full, untruncated value.
+fn format_symbol(symbol: &Symbol) -> String {
+    format!(
+        "Symbol:  {}\nRaw:     {}\nAddress: 0x{:016x}\nSize:    {}\nValue:   {}\n",
+        symbol.display_name(),
+        symbol.name,
+        symbol.address,
+        symbol.size,
+        symbol.content
+    )
+}
+
+/// Format a register value as signed (`/s`), binary (`/b`) or ASCII (`/c`).
+fn format_register_as(value: u64, format: &str) -> Result<String, String> {
+    match format {
+        "/s" => Ok(format!("Signed: {}", value as i64)),
+        "/b" => Ok(format!("Binary: 0b{:b}", value)),
+        "/c" => Ok(format!("ASCII:  \"{}\"", register_ascii(value))),
+        _ => Err(format!(
+            "Unknown format '{}', expected /s, /b or /c",
+            format
+        )),
+    }
 }
 
-impl<'a, 'b, C: ContextObject> Repl<'a, 'b, C> {
-    pub fn new(dbg: Debugger<'a, 'b, C>) -> Self {
-        Self { dbg }
+/// Format the registers changed by an instruction as `old -> new`, one per line.
+fn format_register_changes(changed_registers: &[(usize, u64, u64)]) -> String {
+    if changed_registers.is_empty() {
+        return "No registers changed\n".to_string();
+    }
+    changed_registers
+        .iter()
+        .map(|(index, old, new)| format!("  r{:<2} 0x{:016x} -> 0x{:016x}\n", index, old, new))
+        .collect()
+}
+
+/// Print the event the program stopped with and get the resulting outcome.
+fn report_event(event: DebugEvent) -> CommandOutcome {
+    match event {
+        DebugEvent::Step(pc, line) => {
+            if let Some(line_num) = line {
+                println!("Step at PC 0x{:016x} (line {})", pc, line_num);
+            } else {
+                println!("Step at PC 0x{:016x}", pc);
+            }
+        }
+        DebugEvent::Breakpoint(pc, line) => {
+            if let Some(line_num) = line {
+                println!("Breakpoint hit at PC 0x{:016x} (line {})", pc, line_num);
+            } else {
+                println!("Breakpoint hit at PC 0x{:016x}", pc);
+            }
+        }
+        DebugEvent::Exit(code) => {
+            println!("Program exited with code: {}", code);
+            return CommandOutcome::Terminated;
+        }
+        DebugEvent::Error(msg) => {
+            println!("Program error: {}", msg);
+            return CommandOutcome::Terminated;
+        }
+        DebugEvent::Watchpoint(address, old, new) => {
+            println!("Watchpoint at 0x{:016x} changed", address);
+            println!("Old: {}", format_hex(&old));
+            println!("New: {}", format_hex(&new));
+        }
+        DebugEvent::Pause(pc, _) => {
+            println!("Paused at PC 0x{:016x}", pc);
+        }
+        DebugEvent::LimitReached(pc, count) => {
+            // The VM stays stopped so that its state can be inspected.
+            println!(
+                "Instruction limit reached after {} instructions, stopped at PC 0x{:016x}",
+                count, pc
+            );
+        }
+    }
+    CommandOutcome::Continue
+}
+
+/// Format a stack frame of the adapter's `getStackFrames` response.
+fn format_frame(frame: &Value) -> String {
+    let idx = frame.get("index").and_then(|v| v.as_u64()).unwrap_or(0);
+    let name = frame.get("name").and_then(|v| v.as_str()).unwrap_or("?");
+    let file = frame.get("file").and_then(|v| v.as_str()).unwrap_or("?");
+    let line = frame.get("line").and_then(|v| v.as_u64()).unwrap_or(0);
+    let pc = frame
+        .get("instruction")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    format!("#{idx}: {name} at {file}:{line} (PC 0x{pc:016x})")
+}
+
+/// Split a line into its `;`-separated commands, skipping empty ones. A `;` inside single or
+/// double quotes does not split, and the quotes themselves are removed.
+fn split_commands(line: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut command = String::new();
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, ';') => commands.push(std::mem::take(&mut command)),
+            _ => command.push(c),
+        }
+    }
+    commands.push(command);
+    commands
+        .into_iter()
+        .map(|command| command.trim().to_string())
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+/// Parse a `<file>:<line>` breakpoint location.
+fn parse_file_line(arg: &str) -> Option<(&str, usize)> {
+    let (file, line) = arg.rsplit_once(':')?;
+    Some((file, line.parse().ok()?))
+}
+
+/// Outcome of executing a REPL command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandOutcome {
+    /// Keep accepting commands.
+    Continue,
+    /// The program exited or raised an error.
+    Terminated,
+    /// Exit the REPL.
+    Quit,
+}
+
+/// What a script does when one of its commands fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnError {
+    /// Abort the script.
+    Stop,
+    /// Report the error and run the next command.
+    Continue,
+}
+
+impl FromStr for OnError {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stop" => Ok(OnError::Stop),
+            "continue" => Ok(OnError::Continue),
+            _ => Err(format!(
+                "Invalid error policy '{}', expected 'stop' or 'continue'",
+                s
+            )),
+        }
+    }
+}
+
+/// How `regs`, `reg`, `compute`, `rodata`, `data` and `profile` print their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable tables.
+    Text,
+    /// One line of JSON, in the same shape as the adapter's responses.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "Invalid output format '{}', expected 'text' or 'json'",
+                s
+            )),
+        }
+    }
+}
+
+/// Prompt of the interactive REPL
+const PROMPT: &str = "dbg> ";
+
+/// Commands repeated by an empty line, which only move execution forward
+const REPEATABLE_COMMANDS: &[&str] = &[
+    "step",
+    "s",
+    "stepi",
+    "si",
+    "step --diff",
+    "sd",
+    "continue",
+    "c",
+];
+
+/// File the command history is kept in across sessions
+fn history_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home_dir| home_dir.join(".sbpf_dbg_history"))
+}
+
+/// File the aliases are kept in across sessions, one `<name> = <command>` per line
+fn aliases_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home_dir| home_dir.join(".sbpf_dbg_aliases"))
+}
+
+/// Parse an alias definition `<name> = <command>`.
+fn parse_alias(definition: &str) -> Option<(&str, &str)> {
+    let (name, command) = definition.split_once('=')?;
+    let (name, command) = (name.trim(), command.trim());
+    if name.is_empty() || name.contains(char::is_whitespace) || command.is_empty() {
+        return None;
+    }
+    Some((name, command))
+}
+
+/// File `save breakpoints` and `source breakpoints` use when no file is given. The REPL loads
+/// it on startup if it exists.
+pub const BREAKPOINTS_FILE: &str = ".dbg/breakpoints";
+
+/// Number of source lines shown by `list`
+const LIST_LINES: usize = 10;
+
+/// Source lines shown by the last `list` command
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ListWindow {
+    /// PC when the lines were listed
+    pc: u64,
+    first: usize,
+    last: usize,
+    /// Lines paging is restricted to
+    bounds: RangeInclusive<usize>,
+}
+
+pub struct Repl<'a, 'b> {
+    pub dbg: Debugger<'a, 'b, DebugContextObject>,
+    /// Paging state of `list`
+    list_window: Option<ListWindow>,
+    /// Lines of the source files shown by `list`
+    source_cache: HashMap<String, Vec<String>>,
+    /// Output format of the commands that support JSON
+    format: OutputFormat,
+    /// Last command typed at the prompt
+    last_command: Option<String>,
+    /// Whether stepping prints the next instruction
+    show_disasm: bool,
+    /// States saved by `snapshot`, numbered from 0
+    snapshots: Vec<Snapshot>,
+    /// Commands defined with `alias`, by name
+    aliases: HashMap<String, String>,
+}
+
+impl<'a, 'b> Repl<'a, 'b> {
+    pub fn new(dbg: Debugger<'a, 'b, DebugContextObject>) -> Self {
+        Self {
+            dbg,
+            list_window: None,
+            source_cache: HashMap::new(),
+            format: OutputFormat::Text,
+            last_command: None,
+            show_disasm: true,
+            snapshots: Vec::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    pub fn set_format(&mut self, format: OutputFormat) {
+        self.format = format;
+    }
+
+    fn json(&self) -> bool {
+        self.format == OutputFormat::Json
     }
 
     pub fn start(&mut self) {
         println!("\nsBPF Debugger REPL. Type 'help' for commands.");
+        self.load_aliases();
 
+        let mut editor = match DefaultEditor::new() {
+            Ok(editor) => editor,
+            Err(_) => return self.start_without_editor(),
+        };
+        let history_file = history_file();
+        if let Some(history_file) = &history_file {
+            // There is no history on the first run.
+            let _ = editor.load_history(history_file);
+        }
+
+        loop {
+            let input = match editor.readline(PROMPT) {
+                Ok(input) => input,
+                // Ctrl-C discards the current line.
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => {
+                    println!("Error: {}", e);
+                    break;
+                }
+            };
+            let input = input.trim();
+            if !input.is_empty() {
+                let _ = editor.add_history_entry(input);
+            }
+            match self.execute_interactive(input) {
+                Ok(CommandOutcome::Quit) => break,
+                Ok(_) => {}
+                Err(e) => println!("Error: {}", e),
+            }
+        }
+
+        if let Some(history_file) = &history_file {
+            if let Err(e) = editor.save_history(history_file) {
+                println!("Failed to save the command history: {}", e);
+            }
+        }
+        self.save_aliases();
+    }
+
+    /// Load the aliases saved by previous sessions.
+    fn load_aliases(&mut self) {
+        let Some(contents) = aliases_file().and_then(|file| std::fs::read_to_string(file).ok())
+        else {
+            return;
+        };
+        for (name, command) in contents.lines().filter_map(parse_alias) {
+            self.aliases.insert(name.to_string(), command.to_string());
+        }
+    }
+
+    /// Save the aliases for the next sessions.
+    fn save_aliases(&self) {
+        let Some(file) = aliases_file() else {
+            return;
+        };
+        let mut aliases: Vec<_> = self.aliases.iter().collect();
+        aliases.sort();
+        let contents: String = aliases
+            .into_iter()
+            .map(|(name, command)| format!("{} = {}\n", name, command))
+            .collect();
+        if let Err(e) = std::fs::write(file, contents) {
+            println!("Failed to save the aliases: {}", e);
+        }
+    }
+
+    /// Replace a leading alias by its commands, recursively since aliases may refer to other
+    /// aliases. An alias may stand for several `;`-separated commands, the arguments after it
+    /// going to the last one.
+    fn expand_alias(&self, cmd: &str) -> Result<Vec<String>, String> {
+        self.expand_alias_from(cmd, &mut HashSet::new())
+    }
+
+    /// Expand `cmd` within the expansion of the aliases in `expanding`.
+    fn expand_alias_from(
+        &self,
+        cmd: &str,
+        expanding: &mut HashSet<String>,
+    ) -> Result<Vec<String>, String> {
+        let (name, args) = cmd.split_once(' ').unwrap_or((cmd, ""));
+        let Some(alias) = self.aliases.get(name) else {
+            return Ok(vec![cmd.to_string()]);
+        };
+        if !expanding.insert(name.to_string()) {
+            return Err(format!("Alias '{}' refers to itself", name));
+        }
+        let mut commands = Vec::new();
+        for command in split_commands(format!("{} {}", alias, args).trim_end()) {
+            commands.extend(self.expand_alias_from(&command, expanding)?);
+        }
+        expanding.remove(name);
+        Ok(commands)
+    }
+
+    /// Read commands from stdin without line editing, for terminals the line editor does not
+    /// support.
+    fn start_without_editor(&mut self) {
         let stdin = io::stdin();
         loop {
-            print!("dbg> ");
+            print!("{}", PROMPT);
             io::stdout().flush().unwrap();
             let mut input = String::new();
             if stdin.read_line(&mut input).is_err() {
                 break;
             }
-            let cmd = input.trim();
-            match cmd {
-                "step" | "s" => {
-                    self.dbg.set_debug_mode(DebugMode::Step);
-                    match self.dbg.run() {
-                        Ok(event) => match event {
-                            crate::debugger::DebugEvent::Step(pc, line) => {
-                                if let Some(line_num) = line {
-                                    println!("Step at PC 0x{:016x} (line {})", pc, line_num);
-                                } else {
-                                    println!("Step at PC 0x{:016x}", pc);
-                                }
-                            }
-                            crate::debugger::DebugEvent::Breakpoint(pc, line) => {
-                                if let Some(line_num) = line {
-                                    println!(
-                                        "Breakpoint hit at PC 0x{:016x} (line {})",
-                                        pc, line_num
-                                    );
-                                } else {
-                                    println!("Breakpoint hit at PC 0x{:016x}", pc);
-                                }
-                            }
-                            crate::debugger::DebugEvent::Exit(code) => {
-                                println!("Program exited with code: {}", code);
-                            }
-                            crate::debugger::DebugEvent::Error(msg) => {
-                                println!("Program error: {}", msg);
-                            }
-                        },
-                        Err(e) => println!("Debugger error: {:?}", e),
+            match self.execute_interactive(input.trim()) {
+                Ok(CommandOutcome::Quit) => break,
+                Ok(_) => {}
+                Err(e) => println!("Error: {}", e),
+            }
+        }
+        self.save_aliases();
+    }
+
+    /// Execute a line typed at the prompt. Like in gdb, an empty line repeats the previous
+    /// command if it is one of [`REPEATABLE_COMMANDS`].
+    fn execute_interactive(&mut self, input: &str) -> Result<CommandOutcome, String> {
+        if !input.is_empty() {
+            self.last_command = Some(input.to_string());
+            return self.execute_line(input);
+        }
+        match self
+            .last_command
+            .clone()
+            .filter(|command| REPEATABLE_COMMANDS.contains(&command.as_str()))
+        {
+            Some(command) => self.execute_command(&command),
+            None => Ok(CommandOutcome::Continue),
+        }
+    }
+
+    /// Execute the `;`-separated commands of a line in order, e.g. `break 10; continue; regs`.
+    /// A failing command is reported and the next one still runs, only `quit` stops the line.
+    /// Returns the outcome of the last command.
+    fn execute_line(&mut self, line: &str) -> Result<CommandOutcome, String> {
+        let commands = split_commands(line);
+        let Some((last, commands)) = commands.split_last() else {
+            return Ok(CommandOutcome::Continue);
+        };
+        for command in commands {
+            match self.execute_command(command) {
+                Ok(CommandOutcome::Quit) => return Ok(CommandOutcome::Quit),
+                Ok(_) => {}
+                Err(e) => println!("Error: {}", e),
+            }
+        }
+        self.execute_command(last)
+    }
+
+    /// Run the commands of a script, one per line. Empty lines and lines starting with `#` are
+    /// skipped.
+    ///
+    /// Returns the outcome of the last command, or the first error if `on_error` is
+    /// [`OnError::Stop`].
+    pub fn run_script(
+        &mut self,
+        script: &str,
+        on_error: OnError,
+    ) -> Result<CommandOutcome, String> {
+        let mut outcome = CommandOutcome::Continue;
+        for (i, line) in script.lines().enumerate() {
+            let command = line.trim();
+            if command.is_empty() || command.starts_with('#') {
+                continue;
+            }
+            println!("dbg> {}", command);
+            match self.execute_command(command) {
+                Ok(CommandOutcome::Quit) => return Ok(CommandOutcome::Quit),
+                Ok(result) => outcome = result,
+                Err(e) => {
+                    let message = format!("line {}: {}", i + 1, e);
+                    if on_error == OnError::Stop {
+                        return Err(message);
                     }
+                    println!("Error: {}", message);
                 }
-                "continue" | "c" => {
-                    self.dbg.set_debug_mode(DebugMode::Continue);
-                    match self.dbg.run() {
-                        Ok(event) => match event {
-                            crate::debugger::DebugEvent::Step(pc, line) => {
-                                if let Some(line_num) = line {
-                                    println!("Step at PC 0x{:016x} (line {})", pc, line_num);
-                                } else {
-                                    println!("Step at PC 0x{:016x}", pc);
-                                }
-                            }
-                            crate::debugger::DebugEvent::Breakpoint(pc, line) => {
-                                if let Some(line_num) = line {
-                                    println!(
-                                        "Breakpoint hit at PC 0x{:016x} (line {})",
-                                        pc, line_num
-                                    );
-                                } else {
-                                    println!("Breakpoint hit at PC 0x{:016x}", pc);
-                                }
-                            }
-                            crate::debugger::DebugEvent::Exit(code) => {
-                                println!("Program exited with code: {}", code);
-                            }
-                            crate::debugger::DebugEvent::Error(msg) => {
-                                println!("Program error: {}", msg);
-                            }
-                        },
-                        Err(e) => println!("Debugger error: {:?}", e),
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Returns the lines of a source file, reading it from disk on first use.
+    fn source_lines(&mut self, file: &str) -> Result<&[String], String> {
+        if !self.source_cache.contains_key(file) {
+            let source = match std::fs::read_to_string(file) {
+                Ok(source) => source,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    return Err(format!("Source file '{}' no longer exists", file));
+                }
+                Err(e) => return Err(format!("Failed to read source file '{}': {}", file, e)),
+            };
+            let lines = source.lines().map(str::to_string).collect();
+            self.source_cache.insert(file.to_string(), lines);
+        }
+        Ok(&self.source_cache[file])
+    }
+
+    /// Show the source around the current line, or around `line` if given. Successive calls page
+    /// forward (or backward) through the current function until the PC changes.
+    fn list(&mut self, backward: bool, line: Option<usize>) -> Result<(), String> {
+        let pc = self.dbg.get_selected_pc();
+        let line_map = self
+            .dbg
+            .dwarf_line_map
+            .as_ref()
+            .ok_or("No DWARF line mapping available.")?;
+        let location = line_map
+            .get_source_location_for_pc(pc)
+            .ok_or("No line information available for current PC")?;
+        let current_line = location.line as usize;
+        if current_line == 0 {
+            return Err("No line information available for current PC".to_string());
+        }
+        let file = location.file.clone();
+        let function_lines = line_map
+            .get_function_line_range(pc)
+            .unwrap_or(current_line..=current_line);
+        let line_count = self.source_lines(&file)?.len();
+
+        let (first, last, bounds) = match (&self.list_window, line) {
+            (_, Some(line)) => {
+                if line == 0 || line > line_count {
+                    return Err(format!(
+                        "Line {} out of range, '{}' has {} lines",
+                        line, file, line_count
+                    ));
+                }
+                let first = line.saturating_sub(LIST_LINES / 2).max(1);
+                (
+                    first,
+                    (first + LIST_LINES - 1).min(line_count),
+                    1..=line_count,
+                )
+            }
+            (Some(window), None) if window.pc == pc && backward => {
+                if window.first <= *window.bounds.start() {
+                    return Err("Already at the start of the function".to_string());
+                }
+                let last = window.first - 1;
+                let first = (last + 1)
+                    .saturating_sub(LIST_LINES)
+                    .max(*window.bounds.start());
+                (first, last, window.bounds.clone())
+            }
+            (Some(window), None) if window.pc == pc => {
+                if window.last >= *window.bounds.end() {
+                    return Err("Already at the end of the function".to_string());
+                }
+                let first = window.last + 1;
+                let last = (first + LIST_LINES - 1).min(*window.bounds.end());
+                (first, last, window.bounds.clone())
+            }
+            _ => {
+                let first = current_line
+                    .saturating_sub(LIST_LINES / 2)
+                    .max(*function_lines.start());
+                let last = (first + LIST_LINES - 1).min(*function_lines.end());
+                (first, last, function_lines)
+            }
+        };
+
+        let lines = self.source_lines(&file)?;
+        for (number, text) in lines
+            .iter()
+            .enumerate()
+            .map(|(i, text)| (i + 1, text))
+            .filter(|(number, _)| (first..=last).contains(number))
+        {
+            let marker = if number == current_line { "=>" } else { "  " };
+            println!("{} {:>4}  {}", marker, number, text);
+        }
+        self.list_window = Some(ListWindow {
+            pc,
+            first,
+            last,
+            bounds,
+        });
+        Ok(())
+    }
+
+    /// Format the instructions of the current function, each source line followed by the
+    /// instructions generated for it. Without line information, only the instructions are shown.
+    fn format_source_and_assembly(&mut self) -> Result<String, String> {
+        let pc = self.dbg.get_selected_pc();
+        let instructions = self.dbg.disassemble_function(pc);
+        if instructions.is_empty() {
+            return Err("No instructions available for current PC".to_string());
+        }
+        let mut output = String::new();
+        let mut current_location = None;
+        for (insn_pc, disassembly) in instructions {
+            let location = self
+                .dbg
+                .dwarf_line_map
+                .as_ref()
+                .and_then(|line_map| line_map.get_source_location_for_pc(insn_pc))
+                .filter(|location| location.line != 0)
+                .map(|location| (location.file.clone(), location.line as usize));
+            if location.is_some() && location != current_location {
+                let (file, line) = location.clone().unwrap();
+                let text = self
+                    .source_lines(&file)
+                    .ok()
+                    .and_then(|lines| lines.get(line - 1))
+                    .map_or("", |text| text.trim());
+                output.push_str(&format!("{}:{}  {}\n", file, line, text));
+                current_location = location;
+            }
+            let marker = if insn_pc == pc { "=>" } else { "  " };
+            output.push_str(&format!(
+                "{}   0x{:016x}: {}\n",
+                marker, insn_pc, disassembly
+            ));
+        }
+        Ok(output)
+    }
+
+    /// Run the program in the given mode and report the resulting event.
+    fn run(&mut self, mode: DebugMode) -> Result<CommandOutcome, String> {
+        self.dbg.set_debug_mode(mode);
+        match self.dbg.run() {
+            Ok(event) => Ok(self.report_stop(event)),
+            Err(e) => Err(format!("Debugger error: {:?}", e)),
+        }
+    }
+
+    /// Select a stack frame and show it.
+    fn select_frame(&mut self, index: usize) -> Result<(), String> {
+        self.dbg.select_frame(index)?;
+        if let Some(frame) = self.dbg.get_frames().get(index) {
+            println!("{}", format_frame(frame));
+        }
+        Ok(())
+    }
+
+    /// Step to the next source line and report where execution stopped.
+    fn step_line(&mut self) -> Result<CommandOutcome, String> {
+        match self.dbg.step_line() {
+            Ok(event) => Ok(self.report_stop(event)),
+            Err(e) => Err(format!("Debugger error: {:?}", e)),
+        }
+    }
+
+    /// Report the event the program stopped with, followed by the instruction at the new
+    /// PC after a step.
+    fn report_stop(&self, event: DebugEvent) -> CommandOutcome {
+        let stepped = matches!(event, DebugEvent::Step(..) | DebugEvent::Watchpoint(..));
+        let stopped = stepped || matches!(event, DebugEvent::Breakpoint(..));
+        let outcome = report_event(event);
+        if stepped && self.show_disasm {
+            let pc = self.dbg.get_pc();
+            if let Some(disassembly) = self.dbg.disassemble_instruction(pc) {
+                println!("=> 0x{:016x}: {}", pc, disassembly);
+            }
+        }
+        if stopped {
+            print!("{}", self.format_watches());
+        }
+        outcome
+    }
+
+    /// Format the current bytes of each memory watch.
+    fn format_watches(&self) -> String {
+        let mut output = String::new();
+        for (id, watch) in &self.dbg.watches {
+            output.push_str(&format!(
+                "Watch {} (0x{:016x}, {} bytes):",
+                id, watch.address, watch.len
+            ));
+            match self.dbg.read_memory(watch.address, watch.len) {
+                Ok(bytes) => output.push_str(&format!("\n{}\n", format_hex(bytes))),
+                Err(err) => output.push_str(&format!(" {}\n", err)),
+            }
+        }
+        output
+    }
+
+    /// Enable or disable the breakpoints at `[file:]line` or `0x<pc>` and return their PCs.
+    fn set_breakpoints_enabled(&mut self, arg: &str, enabled: bool) -> Result<Vec<u64>, String> {
+        if let Some((file, line)) = parse_file_line(arg) {
+            self.dbg.set_line_breakpoint_enabled(file, line, enabled)
+        } else if let Ok(line) = arg.parse::<usize>() {
+            self.dbg.set_line_breakpoint_enabled("", line, enabled)
+        } else if let Some(pc) = arg
+            .strip_prefix("0x")
+            .and_then(|pc| u64::from_str_radix(pc, 16).ok())
+        {
+            self.dbg.set_breakpoint_enabled(pc, enabled)?;
+            Ok(vec![pc])
+        } else {
+            Err("Invalid breakpoint. Use [file:]line or 0x<pc>.".to_string())
+        }
+    }
+
+    /// Write the current breakpoints to `path` as JSON.
+    fn save_breakpoints(&self, path: &Path) -> Result<(), String> {
+        let saved = self.dbg.saved_breakpoints();
+        let json = serde_json::to_string_pretty(&saved).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+        }
+        std::fs::write(path, json)
+            .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+        println!("Saved breakpoints to {}", path.display());
+        Ok(())
+    }
+
+    /// Set the breakpoints saved in `path`. Line and function breakpoints are resolved against
+    /// the current program, and those that no longer exist are reported and skipped.
+    pub fn load_breakpoints(&mut self, path: &Path) -> Result<(), String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+        let saved: SavedBreakpoints = serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid breakpoints file '{}': {}", path.display(), e))?;
+        for error in self.dbg.restore_breakpoints(&saved) {
+            println!("Skipped breakpoint: {}", error);
+        }
+        println!("Loaded breakpoints from {}", path.display());
+        Ok(())
+    }
+
+    /// Execute one instruction and report the registers it changed.
+    fn step_diff(&mut self) -> Result<CommandOutcome, String> {
+        let info = self
+            .dbg
+            .step_detailed()
+            .map_err(|e| format!("Debugger error: {:?}", e))?;
+        let outcome = self.report_stop(info.event);
+        print!("{}", format_register_changes(&info.changed_registers));
+        Ok(outcome)
+    }
+
+    /// Execute a single REPL command, after expanding aliases.
+    ///
+    /// Returns an error if the command is unknown, malformed or cannot be carried out.
+    pub fn execute_command(&mut self, cmd: &str) -> Result<CommandOutcome, String> {
+        let commands = self.expand_alias(cmd)?;
+        // The commands of an alias run like those of a line, see `execute_line`.
+        let Some((last, commands)) = commands.split_last() else {
+            return Ok(CommandOutcome::Continue);
+        };
+        for command in commands {
+            match self.dispatch_command(command) {
+                Ok(CommandOutcome::Quit) => return Ok(CommandOutcome::Quit),
+                Ok(_) => {}
+                Err(e) => println!("Error: {}", e),
+            }
+        }
+        self.dispatch_command(last)
+    }
+
+    fn dispatch_command(&mut self, cmd: &str) -> Result<CommandOutcome, String> {
+        // A trailing `--json` switches the output format for this command only.
+        if let Some(cmd) = cmd.strip_suffix("--json") {
+            let format = std::mem::replace(&mut self.format, OutputFormat::Json);
+            let result = self.dispatch_command(cmd.trim_end());
+            self.format = format;
+            return result;
+        }
+
+        match cmd {
+            "step" | "s" => return self.step_line(),
+            "stepi" | "si" => return self.run(DebugMode::Step),
+            "step --diff" | "sd" => return self.step_diff(),
+            "continue" | "c" => return self.run(DebugMode::Continue),
+            cmd if cmd.starts_with("break ") => {
+                // An optional `if <condition>` makes the breakpoint conditional.
+                let (location, condition) = match cmd.split_once(" if ") {
+                    Some((location, condition)) => (location, Some(condition.parse()?)),
+                    None => (cmd, None),
+                };
+                let arg = location["break".len()..].trim();
+                if !arg.is_empty() {
+                    // Try to parse as <file>:<line> or line number first
+                    if let Some((file, line)) = parse_file_line(arg) {
+                        self.dbg.set_breakpoint_at_line(file, line)?;
+                        self.dbg
+                            .set_line_breakpoint_condition(file, line, condition)?;
+                        println!("Breakpoint set at {}:{}", file, line);
+                    } else if let Ok(line) = arg.parse::<usize>() {
+                        self.dbg.set_breakpoint_at_line("", line)?;
+                        self.dbg
+                            .set_line_breakpoint_condition("", line, condition)?;
+                        println!("Breakpoint set at line: {}", line);
+                    } else if let Ok(pc) = self.dbg.evaluate(arg) {
+                        // Fall back to PC-based breakpoint, such as `0x28` or `pc + 16`
+                        self.dbg.set_breakpoint(pc);
+                        self.dbg.set_breakpoint_condition(pc, condition)?;
+                        println!("Breakpoint set at instruction: 0x{:x}", pc);
+                    } else {
+                        let (symbol, pc) = self.dbg.set_breakpoint_at_function(arg)?;
+                        self.dbg.set_breakpoint_condition(pc, condition)?;
+                        println!(
+                            "Breakpoint set at function {} (PC 0x{:x})",
+                            demangle(&symbol),
+                            pc
+                        );
+                    }
+                    if let Some(condition) = condition {
+                        println!("Stopping only if {}", condition);
                     }
                 }
-                cmd if cmd.starts_with("break ") => {
-                    if let Some(arg) = cmd.split_whitespace().nth(1) {
-                        // Try to parse as line number first
-                        if let Ok(line) = arg.parse::<usize>() {
-                            match self.dbg.set_breakpoint_at_line(line) {
-                                Ok(()) => println!("Breakpoint set at line: {}", line),
-                                Err(e) => println!("Error: {}", e),
-                            }
-                        } else if let Ok(pc) = arg.parse::<u64>() {
-                            // Fall back to PC-based breakpoint
-                            self.dbg.set_breakpoint(pc);
-                            println!("Breakpoint set at instruction: {pc}");
-                        } else {
-                            println!(
-                                "Error: Invalid breakpoint argument. Use line number or PC address."
-                            );
-                        }
+            }
+            cmd if cmd.starts_with("tbreak ") => {
+                let arg = cmd.split_whitespace().nth(1).unwrap_or_default();
+                let pc = if let Some((file, line)) = parse_file_line(arg) {
+                    self.dbg.set_temporary_breakpoint_at_line(file, line)?
+                } else if let Ok(line) = arg.parse::<usize>() {
+                    self.dbg.set_temporary_breakpoint_at_line("", line)?
+                } else if let Some(pc) = arg
+                    .strip_prefix("0x")
+                    .and_then(|pc| u64::from_str_radix(pc, 16).ok())
+                {
+                    self.dbg.set_temporary_breakpoint(pc);
+                    pc
+                } else {
+                    return Err("Usage: tbreak <[file:]line|0x<pc>>".to_string());
+                };
+                println!("Temporary breakpoint set at PC 0x{:x}", pc);
+            }
+            cmd if cmd.starts_with("jump ") => {
+                let arg = cmd["jump".len()..].trim();
+                let pc = if let Some((file, line)) = parse_file_line(arg) {
+                    self.dbg.jump_targets(file, line)?[0]
+                } else if let Ok(line) = arg.parse::<usize>() {
+                    self.dbg.jump_targets("", line)?[0]
+                } else {
+                    self.dbg.evaluate(arg)?
+                };
+                let warning = self.dbg.jump(pc)?;
+                match self.dbg.get_line_for_pc(pc) {
+                    Some(line) => println!("Jumped to PC 0x{:016x} (line {})", pc, line),
+                    None => println!("Jumped to PC 0x{:016x}", pc),
+                }
+                if let Some(warning) = warning {
+                    println!("Warning: {}", warning);
+                }
+            }
+            cmd if cmd.starts_with("delete ") => {
+                if let Some(arg) = cmd.split_whitespace().nth(1) {
+                    if let Some((file, line)) = parse_file_line(arg) {
+                        self.dbg.remove_breakpoint_at_line(file, line)?;
+                        println!("Breakpoint removed from {}:{}", file, line);
+                    } else if let Ok(line) = arg.parse::<usize>() {
+                        self.dbg.remove_breakpoint_at_line("", line)?;
+                        println!("Breakpoint removed from line: {}", line);
+                    } else {
+                        return Err("Invalid line number for delete command.".to_string());
                     }
                 }
-                cmd if cmd.starts_with("delete ") => {
-                    if let Some(arg) = cmd.split_whitespace().nth(1) {
-                        if let Ok(line) = arg.parse::<usize>() {
-                            match self.dbg.remove_breakpoint_at_line(line) {
-                                Ok(()) => println!("Breakpoint removed from line: {}", line),
-                                Err(e) => println!("Error: {}", e),
+            }
+            cmd if cmd.starts_with("disable ") || cmd.starts_with("enable ") => {
+                let mut parts = cmd.split_whitespace();
+                let enabled = parts.next() == Some("enable");
+                let arg = parts.next().unwrap_or_default();
+                let pcs = self.set_breakpoints_enabled(arg, enabled)?;
+                let pcs: Vec<String> = pcs.iter().map(|pc| format!("0x{:x}", pc)).collect();
+                println!(
+                    "Breakpoint {} at PC {}",
+                    if enabled { "enabled" } else { "disabled" },
+                    pcs.join(", ")
+                );
+            }
+            cmd if cmd.starts_with("save breakpoints") => {
+                let file = cmd
+                    .strip_prefix("save breakpoints")
+                    .unwrap_or_default()
+                    .trim();
+                let file = if file.is_empty() {
+                    BREAKPOINTS_FILE
+                } else {
+                    file
+                };
+                self.save_breakpoints(Path::new(file))?;
+            }
+            cmd if cmd.starts_with("source breakpoints") => {
+                let file = cmd
+                    .strip_prefix("source breakpoints")
+                    .unwrap_or_default()
+                    .trim();
+                let file = if file.is_empty() {
+                    BREAKPOINTS_FILE
+                } else {
+                    file
+                };
+                self.load_breakpoints(Path::new(file))?;
+            }
+            "snapshot" => {
+                self.snapshots.push(self.dbg.snapshot());
+                println!(
+                    "Snapshot {} saved at PC 0x{:016x}",
+                    self.snapshots.len() - 1,
+                    self.dbg.get_pc()
+                );
+            }
+            cmd if cmd.starts_with("dump memory ") => {
+                let usage = "Usage: dump memory <file> <start> <end>";
+                let [file, start, end] = cmd
+                    .split_whitespace()
+                    .skip(2)
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .map_err(|_| usage)?;
+                let start = self.dbg.evaluate(start)?;
+                let end = self.dbg.evaluate(end)?;
+                if end <= start {
+                    return Err(format!(
+                        "End 0x{:016x} is not past start 0x{:016x}",
+                        end, start
+                    ));
+                }
+                let data = self.dbg.read_memory(start, end - start)?;
+                std::fs::write(file, data).map_err(|e| format!("Cannot write {}: {}", file, e))?;
+                println!(
+                    "Dumped {} bytes at 0x{:016x} to {}",
+                    data.len(),
+                    start,
+                    file
+                );
+            }
+            cmd if cmd == "restore memory" || cmd.starts_with("restore memory ") => {
+                let (file, address) = cmd["restore memory".len()..]
+                    .trim()
+                    .split_once(' ')
+                    .ok_or("Usage: restore memory <file> <address>")?;
+                let address = self.dbg.evaluate(address.trim())?;
+                let data =
+                    std::fs::read(file).map_err(|e| format!("Cannot read {}: {}", file, e))?;
+                self.dbg.write_memory(address, &data)?;
+                println!(
+                    "Restored {} bytes from {} at 0x{:016x}",
+                    data.len(),
+                    file,
+                    address
+                );
+            }
+            cmd if cmd == "restore" || cmd.starts_with("restore ") => {
+                let index = match cmd.strip_prefix("restore").unwrap_or_default().trim() {
+                    "" => self
+                        .snapshots
+                        .len()
+                        .checked_sub(1)
+                        .ok_or("No snapshot taken")?,
+                    index => index
+                        .parse::<usize>()
+                        .map_err(|_| "Usage: restore [<n>]".to_string())?,
+                };
+                let snapshot = self
+                    .snapshots
+                    .get(index)
+                    .ok_or_else(|| format!("No snapshot {}", index))?;
+                self.dbg.restore(snapshot)?;
+                println!("Restored snapshot {} at PC 0x{:016x}", index, snapshot.pc());
+            }
+            "info breakpoints" | "info b" => {
+                println!("{}", self.dbg.get_breakpoints_info());
+            }
+            "info line" => {
+                let pc = self.dbg.get_selected_pc();
+                if let Some(line) = self.dbg.get_line_for_pc(pc) {
+                    println!("Current line: {}", line);
+                    let file = self
+                        .dbg
+                        .dwarf_line_map
+                        .as_ref()
+                        .and_then(|map| map.get_source_location_for_pc(pc))
+                        .map(|location| location.file.clone())
+                        .unwrap_or_default();
+                    let pcs = self.dbg.get_pcs_for_file_line(&file, line);
+                    if !pcs.is_empty() {
+                        println!("Line {} maps to PCs: {:?}", line, pcs);
+                    }
+                } else {
+                    println!("No line information available for current PC");
+                }
+            }
+            cmd if cmd.starts_with("repeat ") => {
+                let args = cmd.strip_prefix("repeat ").unwrap_or_default().trim();
+                match args
+                    .split_once(char::is_whitespace)
+                    .map(|(count, command)| (count.parse::<usize>(), command.trim()))
+                {
+                    Some((Ok(count), command)) if !command.is_empty() => {
+                        for _ in 0..count {
+                            let outcome = self.execute_command(command)?;
+                            if outcome != CommandOutcome::Continue {
+                                return Ok(outcome);
                             }
-                        } else {
-                            println!("Error: Invalid line number for delete command.");
                         }
                     }
+                    _ => return Err("Usage: repeat <n> <command>".to_string()),
                 }
-                "info breakpoints" | "info b" => {
-                    println!("{}", self.dbg.get_breakpoints_info());
+            }
+            cmd if cmd.starts_with("set disasm") => match cmd.split_whitespace().nth(2) {
+                Some("on") => self.show_disasm = true,
+                Some("off") => self.show_disasm = false,
+                _ => return Err("Usage: set disasm <on|off>".to_string()),
+            },
+            "alias" if self.aliases.is_empty() => println!("No aliases"),
+            "alias" => {
+                let mut aliases: Vec<_> = self.aliases.iter().collect();
+                aliases.sort();
+                for (name, command) in aliases {
+                    println!("{} = {}", name, command);
                 }
-                "info line" => {
-                    if let Some(line) = self.dbg.get_current_line() {
-                        println!("Current line: {}", line);
-                        let pcs = self.dbg.get_pcs_for_line(line);
-                        if !pcs.is_empty() {
-                            println!("Line {} maps to PCs: {:?}", line, pcs);
-                        }
-                    } else {
-                        println!("No line information available for current PC");
-                    }
+            }
+            cmd if cmd.starts_with("alias ") => {
+                let (name, command) =
+                    parse_alias(&cmd["alias ".len()..]).ok_or("Usage: alias <name> = <command>")?;
+                if matches!(name, "alias" | "unalias") {
+                    return Err(format!("Cannot redefine '{}'", name));
                 }
-                "quit" => break,
-                "help" => {
-                    println!("Commands:");
-                    println!("  step (s)                    - Execute one instruction");
-                    println!("  continue (c)                 - Continue execution");
+                self.aliases.insert(name.to_string(), command.to_string());
+                println!("Alias {} = {}", name, command);
+            }
+            cmd if cmd.starts_with("unalias ") => {
+                let name = cmd["unalias ".len()..].trim();
+                self.aliases
+                    .remove(name)
+                    .ok_or_else(|| format!("No alias named '{}'", name))?;
+                println!("Alias {} removed", name);
+            }
+            "quit" => return Ok(CommandOutcome::Quit),
+            "help" => {
+                println!("Commands:");
+                println!("  step (s)                     - Run to the next source line");
+                println!("  stepi (si)                   - Execute one instruction");
+                println!("  step --diff (sd)             - Step and show the changed registers");
+                println!("  continue (c)                 - Continue execution");
+                println!("  break <[file:]line|pc|func>  - Set breakpoint at line number, PC or function");
+                println!("  break <location> if <cond>   - Stop only if e.g. r2 == 0x10 holds");
+                println!("  tbreak <[file:]line|0x<pc>>  - Set breakpoint removed once it is hit");
+                println!(
+                    "  jump <[file:]line|pc>        - Resume from another line or PC next time"
+                );
+                println!("  delete <[file:]line>         - Remove breakpoint at line");
+                println!(
+                    "  disable <[file:]line|0x<pc>> - Disable a breakpoint without deleting it"
+                );
+                println!("  enable <[file:]line|0x<pc>>  - Enable a disabled breakpoint");
+                println!("  info breakpoints (info b)    - Show all breakpoints");
+                println!(
+                    "  save breakpoints [<file>]    - Save breakpoints (default .dbg/breakpoints)"
+                );
+                println!("  source breakpoints [<file>]  - Load saved breakpoints");
+                println!("  info line                    - Show current line info");
+                println!(
+                    "  list (l)                     - Show source, then page through the function"
+                );
+                println!("  list -                       - Page backward through the function");
+                println!("  list <line>                  - Show the source around a line");
+                println!(
+                    "  list-asm                     - Show the function's source and instructions"
+                );
+                println!("  info dwarf                   - Show DWARF debug info");
+                println!("  info dwarf-details           - Show detailed DWARF mapping info");
+                println!("  stack (bt)                   - Show call stack");
+                println!(
+                    "  up / down / frame <n>        - Select the frame shown by list and info line"
+                );
+                println!("  locals                       - Show local variables in scope");
+                println!("  eval <expr>                  - Evaluate e.g. r1 + 0x10 or *[r10 - 8]");
+                println!("  x/<n><fmt><size> <address>   - Examine memory, e.g. x/8xb r10 - 8");
+                println!("  watch <address> <len>        - Show memory whenever execution stops");
+                println!("  watch -w <address> <len>     - Stop when the memory changes");
+                println!("  watch                        - Show the watched memory");
+                println!("  unwatch <id>                 - Remove a watch or watchpoint");
+                println!("  compute                      - Show compute unit information");
+                println!("  reset-meter                  - Reset the compute meter");
+                println!("  restart                      - Run the program again from the start");
+                println!("  snapshot                     - Save the registers and writable memory");
+                println!(
+                    "  restore [<n>]                - Go back to snapshot n (default: the last)"
+                );
+                println!("  dump memory <file> <start> <end> - Write memory to a file");
+                println!("  restore memory <file> <address> - Load a file into memory");
+                println!("  repeat <n> <command>         - Run a command n times");
+                println!("  alias <name> = <command>     - Define a shortcut for a command");
+                println!("  unalias <name>               - Remove an alias");
+                println!("  rodata                       - Show .rodata symbols");
+                println!(
+                    "  rodata <name>                - Show the full value of a .rodata symbol"
+                );
+                println!("  data                         - Show .data globals");
+                println!("  data <name>                  - Show the full value of a .data global");
+                println!("  returndata                   - Show the program's return data");
+                println!("  invocations                  - Show cross-program invocations");
+                println!("  accounts                     - Show the accounts in the program input");
+                println!("  maps                         - Show the mapped memory regions");
+                println!("  syscalls (strace)            - Show the most recent syscalls");
+                println!("  logs                         - Show the program logs of the run");
+                println!("  profile [<n>]                - Show the n most executed instructions");
+                println!("  <command> --json             - Print regs, reg, compute, rodata, data or profile as JSON");
+                println!("  set disasm <on|off>          - Show the next instruction after a step");
+                println!("  help                         - Show this help");
+                println!("  quit                         - Exit debugger");
+            }
+            "regs" if self.json() => println!("{}", DebuggerInterface::get_registers(&self.dbg)),
+            "regs" => {
+                let regs = self.dbg.get_registers();
+                // ASCII table header
+                println!("+------------+--------------------+--------------------+");
+                println!("| Register   | Hex Value          | Decimal Value      |");
+                println!("+------------+--------------------+--------------------+");
+                for (i, val) in regs.iter().enumerate() {
                     println!(
-                        "  break <line|pc>              - Set breakpoint at line number or PC"
+                        "| {:<10} | {:<18} | {:>18} |",
+                        format!("r{}", i),
+                        format!("0x{:016x}", val),
+                        val
                     );
-                    println!("  delete <line>                - Remove breakpoint at line");
-                    println!("  info breakpoints (info b)    - Show all breakpoints");
-                    println!("  info line                    - Show current line info");
-                    println!("  info dwarf                   - Show DWARF debug info");
-                    println!("  info dwarf-details           - Show detailed DWARF mapping info");
-                    println!("  stack (bt)                   - Show call stack");
-                    println!("  compute                      - Show compute unit information");
-                    println!("  help                         - Show this help");
-                    println!("  quit                         - Exit debugger");
-                }
-                "regs" => {
-                    let regs = self.dbg.get_registers();
-                    // ASCII table header
-                    println!("+------------+--------------------+--------------------+");
-                    println!("| Register   | Hex Value          | Decimal Value      |");
-                    println!("+------------+--------------------+--------------------+");
-                    for (i, val) in regs.iter().enumerate() {
-                        println!(
-                            "| {:<10} | {:<18} | {:>18} |",
-                            format!("r{}", i),
-                            format!("0x{:016x}", val),
-                            val
-                        );
-                    }
-                    println!("+------------+--------------------+--------------------+");
-                }
-                cmd if cmd.starts_with("reg ") => {
-                    if let Some(arg) = cmd.split_whitespace().nth(1) {
-                        if let Ok(idx) = arg.parse::<usize>() {
-                            if let Some(val) = self.dbg.get_register(idx) {
-                                println!(
-                                    "+------------+--------------------+--------------------+"
-                                );
-                                println!(
-                                    "| Register   | Hex Value          | Decimal Value      |"
-                                );
-                                println!(
-                                    "+------------+--------------------+--------------------+"
-                                );
-                                println!(
-                                    "| {:<10} | {:<18} | {:>18} |",
-                                    format!("r{}", idx),
-                                    format!("0x{:016x}", val),
-                                    val
-                                );
-                                println!(
-                                    "+------------+--------------------+--------------------+"
-                                );
-                            } else {
-                                println!("Register index out of range");
+                }
+                println!("+------------+--------------------+--------------------+");
+            }
+            cmd if cmd.starts_with("reg ") => {
+                let formats: Vec<_> = cmd.split_whitespace().skip(2).collect();
+                if let Some(arg) = cmd.split_whitespace().nth(1) {
+                    if let Some(register) = RegisterName::parse(arg) {
+                        if let Some(val) = self.dbg.read_register(register) {
+                            if self.json() {
+                                println!("{}", register_json(register, val));
+                                return Ok(CommandOutcome::Continue);
+                            }
+                            println!("+------------+--------------------+--------------------+");
+                            println!("| Register   | Hex Value          | Decimal Value      |");
+                            println!("+------------+--------------------+--------------------+");
+                            println!(
+                                "| {:<10} | {:<18} | {:>18} |",
+                                register.to_string(),
+                                format!("0x{:016x}", val),
+                                val
+                            );
+                            println!("+------------+--------------------+--------------------+");
+                            for format in formats {
+                                println!("{}", format_register_as(val, format)?);
                             }
                         } else {
-                            println!("Invalid register index");
+                            return Err("Register index out of range".to_string());
                         }
                     } else {
-                        println!("Usage: reg <idx>");
+                        return Err("Invalid register index".to_string());
                     }
+                } else {
+                    return Err("Usage: reg <idx|pc|sp|fp> [/s] [/b] [/c]".to_string());
                 }
-                cmd if cmd.starts_with("setreg ") => {
-                    let mut parts = cmd.split_whitespace();
-                    parts.next(); // skip 'setreg'
-                    let idx_str = parts.next();
-                    let val_str = parts.next();
-                    if let (Some(idx_str), Some(val_str)) = (idx_str, val_str) {
-                        if let Ok(idx) = idx_str.parse::<usize>() {
-                            let value = if let Some(stripped) = val_str.strip_prefix("0x") {
-                                u64::from_str_radix(stripped, 16)
-                            } else {
-                                val_str.parse::<u64>()
-                            };
-                            match value {
-                                Ok(val) => match self.dbg.set_register(idx, val) {
-                                    Ok(()) => println!("Set r{} = 0x{:016x} ({})", idx, val, val),
-                                    Err(e) => println!("{}", e),
-                                },
-                                Err(_) => println!(
-                                    "Invalid value: must be a number (decimal or 0x... hex)"
-                                ),
-                            }
-                        } else {
-                            println!("Invalid register index");
-                        }
+            }
+            cmd if cmd.starts_with("setreg ") => {
+                let args = cmd["setreg ".len()..].trim();
+                if let Some((idx_str, val_str)) = args.split_once(char::is_whitespace) {
+                    if let Some(register) = RegisterName::parse(idx_str) {
+                        let val = self
+                            .dbg
+                            .evaluate(val_str)
+                            .map_err(|e| format!("Invalid value: {}", e))?;
+                        self.dbg.write_register(register, val)?;
+                        println!("Set {} = 0x{:016x} ({})", register, val, val);
                     } else {
-                        println!("Usage: setreg <idx> <value>");
+                        return Err("Invalid register index".to_string());
                     }
+                } else {
+                    return Err("Usage: setreg <idx|pc|sp|fp> <value>".to_string());
                 }
-                "rodata" => {
-                    if let Some(rodata_symbols) = self.dbg.get_rodata() {
-                        println!(
-                            "+---------------+----------------------+--------------------------+"
-                        );
-                        println!(
-                            "| Symbol        | Address              | Value                    |"
-                        );
-                        println!(
-                            "+---------------+----------------------+--------------------------+"
-                        );
-                        for symbol in rodata_symbols {
-                            println!(
-                                "| {:<13} | 0x{:016x}   | {:<24} |",
-                                symbol.name, symbol.address, symbol.content
-                            );
-                        }
+            }
+            "rodata" if self.json() => println!("{}", DebuggerInterface::get_rodata(&self.dbg)),
+            "rodata" => {
+                if let Some(rodata_symbols) = self.dbg.get_rodata() {
+                    print!("{}", format_symbol_table(rodata_symbols));
+                } else {
+                    println!("No .rodata information available");
+                }
+            }
+            cmd if cmd.starts_with("rodata ") => {
+                if let Some(name) = cmd.split_whitespace().nth(1) {
+                    match self
+                        .dbg
+                        .get_rodata()
+                        .and_then(|symbols| symbols.iter().find(|sym| symbol_matches(sym, name)))
+                    {
+                        Some(symbol) if self.json() => println!("{}", symbol_json(symbol)),
+                        Some(symbol) => print!("{}", format_symbol(symbol)),
+                        None => return Err(format!("No .rodata symbol named '{}'", name)),
+                    }
+                } else {
+                    return Err("Usage: rodata <name>".to_string());
+                }
+            }
+            "maps" => print!("{}", format_memory_maps(&self.dbg.get_memory_maps())),
+            "data" if self.json() => println!("{}", DebuggerInterface::get_data(&self.dbg)),
+            "data" => match self.dbg.get_data() {
+                Some(symbols) if !symbols.is_empty() => print!("{}", format_symbol_table(&symbols)),
+                _ => println!("No .data information available"),
+            },
+            cmd if cmd.starts_with("data ") => {
+                if let Some(name) = cmd.split_whitespace().nth(1) {
+                    match self.dbg.get_data().and_then(|symbols| {
+                        symbols.into_iter().find(|sym| symbol_matches(sym, name))
+                    }) {
+                        Some(symbol) if self.json() => println!("{}", symbol_json(&symbol)),
+                        Some(symbol) => print!("{}", format_symbol(&symbol)),
+                        None => return Err(format!("No .data symbol named '{}'", name)),
+                    }
+                } else {
+                    return Err("Usage: data <name>".to_string());
+                }
+            }
+            "returndata" => {
+                let (program_id, data) = self.dbg.get_context_object().get_return_data();
+                if data.is_empty() {
+                    println!("No return data");
+                } else {
+                    println!("Return data set by {} ({} bytes):", program_id, data.len());
+                    println!("{}", format_hex(data));
+                }
+            }
+            "restart" => {
+                self.dbg.restart();
+                println!("Restarted at PC 0x{:016x}", self.dbg.get_pc());
+            }
+            "reset-meter" => {
+                self.dbg.reset_compute_meter();
+                println!("Compute meter reset");
+            }
+            "invocations" => {
+                let invocations = self.dbg.get_context_object().get_invocations();
+                if invocations.is_empty() {
+                    println!("No cross-program invocations");
+                }
+                for (i, invocation) in invocations.iter().enumerate() {
+                    println!(
+                        "#{}: {} ({} bytes of data)",
+                        i,
+                        invocation.program_id,
+                        invocation.data.len()
+                    );
+                    for account in &invocation.accounts {
                         println!(
-                            "+---------------+----------------------+--------------------------+"
+                            "    {} signer={} writable={}",
+                            account.pubkey, account.is_signer, account.is_writable
                         );
-                    } else {
-                        println!("No .rodata information available");
                     }
                 }
-                "lines" => {
-                    if let Some(ref dwarf_map) = self.dbg.dwarf_line_map {
-                        println!("+----------+--------------------------+");
-                        println!("| Line     | Instruction Addresses    |");
-                        println!("+----------+--------------------------+");
-                        let mut lines: Vec<_> = dwarf_map.get_line_to_addresses().iter().collect();
-                        lines.sort_by_key(|(line, _)| *line);
-                        for (line, pcs) in lines {
-                            let pcs_str = pcs
-                                .iter()
-                                .map(|pc| format!("0x{:016x}", pc))
-                                .collect::<Vec<_>>()
-                                .join(", ");
-                            println!("| {:<8} | {:<24} |", line, pcs_str);
-                        }
-                        println!("+----------+--------------------------+");
-                    } else {
-                        println!("No DWARF line mapping available.");
+            }
+            "watch" if self.dbg.watches.is_empty() && self.dbg.watchpoints.is_empty() => {
+                println!("No watches")
+            }
+            "watch" => {
+                print!("{}", self.format_watches());
+                for (id, (watch, _)) in &self.dbg.watchpoints {
+                    println!(
+                        "Watchpoint {} (0x{:016x}, {} bytes)",
+                        id, watch.address, watch.len
+                    );
+                }
+            }
+            cmd if cmd.starts_with("watch ") => {
+                let usage = "Usage: watch [-w] <address> <len>";
+                let arg = cmd["watch ".len()..].trim();
+                let (write, arg) = match arg.strip_prefix("-w ") {
+                    Some(arg) => (true, arg.trim()),
+                    None => (false, arg),
+                };
+                let (address, len) = arg.rsplit_once(' ').ok_or(usage)?;
+                let len = len.parse::<u64>().map_err(|_| usage)?;
+                let address = self.dbg.evaluate(address)?;
+                if write {
+                    let id = self.dbg.add_watchpoint(address, len)?;
+                    println!(
+                        "Watchpoint {} set on {} bytes at 0x{:016x}",
+                        id, len, address
+                    );
+                } else {
+                    let id = self.dbg.add_watch(address, len)?;
+                    println!("Watch {} set on {} bytes at 0x{:016x}", id, len, address);
+                }
+            }
+            cmd if cmd.starts_with("unwatch ") => {
+                let id = cmd["unwatch ".len()..]
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| "Usage: unwatch <id>")?;
+                self.dbg.remove_watch(id)?;
+                println!("Watch {} removed", id);
+            }
+            cmd if cmd.starts_with("x/") || cmd.starts_with("x ") => {
+                let (spec, address) = match cmd[1..].strip_prefix('/') {
+                    Some(rest) => rest.split_once(' ').unwrap_or((rest, "")),
+                    None => ("", &cmd[1..]),
+                };
+                let address = address.trim();
+                if address.is_empty() {
+                    return Err("Usage: x/<count><format><size> <address>".to_string());
+                }
+                let examine = Examine::parse(spec)?;
+                let address = self.dbg.evaluate(address)?;
+                let data = self.dbg.read_memory(address, examine.len())?;
+                print!("{}", examine.format(address, data));
+            }
+            cmd if cmd.starts_with("eval ") => {
+                let expr = cmd["eval ".len()..].trim();
+                let value = self.dbg.evaluate(expr)?;
+                println!("{} = 0x{:x} ({})", expr, value, value);
+            }
+            cmd if cmd == "profile" || cmd.starts_with("profile ") => {
+                let top = match cmd.split_whitespace().nth(1) {
+                    Some(top) => top
+                        .parse::<usize>()
+                        .map_err(|_| "Usage: profile [<count>]".to_string())?,
+                    None => DEFAULT_PROFILE_TOP,
+                };
+                if self.json() {
+                    println!("{}", DebuggerInterface::get_profile(&self.dbg, top));
+                    return Ok(CommandOutcome::Continue);
+                }
+                let (entries, total) = self.dbg.get_profile(top);
+                if entries.is_empty() {
+                    println!("No instructions executed");
+                } else {
+                    println!("{} instructions executed", total);
+                    println!("{:<18} {:>10} {:>7}  Line", "PC", "Count", "%");
+                }
+                for entry in entries {
+                    println!(
+                        "0x{:016x} {:>10} {:>6.2}%  {}",
+                        entry.pc,
+                        entry.count,
+                        entry.percent,
+                        entry
+                            .line
+                            .map_or_else(|| "?".to_string(), |line| line.to_string())
+                    );
+                }
+            }
+            "locals" => {
+                let locals = self.dbg.get_locals();
+                if locals.is_empty() {
+                    println!("No local variables in scope");
+                }
+                for (name, value) in locals {
+                    match value {
+                        Ok(value) => println!("{} = 0x{:x} ({})", name, value, value),
+                        Err(e) => println!("{} = <{}>", name, e),
                     }
                 }
-                "stack" | "bt" => {
-                    let stack = self.dbg.get_stack_frames();
-                    if let Some(frames) = stack.get("frames").and_then(|f| f.as_array()) {
-                        println!("Call stack:");
-                        for frame in frames {
-                            let idx = frame.get("index").and_then(|v| v.as_u64()).unwrap_or(0);
-                            let name = frame.get("name").and_then(|v| v.as_str()).unwrap_or("?");
-                            let file = frame.get("file").and_then(|v| v.as_str()).unwrap_or("?");
-                            let line = frame.get("line").and_then(|v| v.as_u64()).unwrap_or(0);
-                            let pc = frame
-                                .get("instruction")
-                                .and_then(|v| v.as_u64())
-                                .unwrap_or(0);
-                            println!("  #{idx}: {name} at {file}:{line} (PC 0x{pc:016x})");
+            }
+            "logs" => {
+                let context = self.dbg.get_context_object();
+                let logs = context.get_logs();
+                if logs.is_empty() {
+                    println!("No logs");
+                }
+                if context.get_logs_dropped() > 0 {
+                    println!("({} earlier lines dropped)", context.get_logs_dropped());
+                }
+                for line in logs {
+                    println!("{}", line);
+                }
+            }
+            "syscalls" | "strace" => {
+                let trace = self.dbg.get_context_object().get_syscall_trace();
+                if trace.is_empty() {
+                    println!("No syscalls");
+                }
+                for entry in trace {
+                    let args: Vec<String> = entry
+                        .args
+                        .iter()
+                        .map(|arg| format!("0x{:x}", arg))
+                        .collect();
+                    let result = match entry.return_value {
+                        Some(value) => format!("0x{:x}", value),
+                        None => "error".to_string(),
+                    };
+                    println!(
+                        "0x{:016x}: {}({}) = {}",
+                        entry.pc,
+                        entry.name,
+                        args.join(", "),
+                        result
+                    );
+                }
+            }
+            "accounts" => match self.dbg.get_input() {
+                None => println!("No program input"),
+                Some(input) => match parse_accounts(input) {
+                    Ok(accounts) if accounts.is_empty() => println!("No accounts"),
+                    Ok(accounts) => {
+                        for account in accounts {
+                            match account {
+                                InputAccount::Account {
+                                    index,
+                                    is_signer,
+                                    is_writable,
+                                    executable,
+                                    key,
+                                    owner,
+                                    lamports,
+                                    data,
+                                    ..
+                                } => {
+                                    println!(
+                                        "#{}: {} signer={} writable={} executable={}",
+                                        index, key, is_signer, is_writable, executable
+                                    );
+                                    println!("    owner:    {}", owner);
+                                    println!("    lamports: {}", lamports);
+                                    println!("    data:     {} bytes", data.len());
+                                    if !data.is_empty() {
+                                        println!("{}", format_hex(&data));
+                                    }
+                                }
+                                InputAccount::Duplicate { index, original } => {
+                                    println!("#{}: duplicate of #{}", index, original);
+                                }
+                            }
                         }
-                    } else {
-                        println!("No stack frames available");
                     }
+                    Err(e) => return Err(format!("Failed to parse accounts: {}", e)),
+                },
+            },
+            "list-asm" => print!("{}", self.format_source_and_assembly()?),
+            "list" | "l" => self.list(false, None)?,
+            "list -" | "l -" => self.list(true, None)?,
+            cmd if cmd.starts_with("list ") || cmd.starts_with("l ") => {
+                match cmd.split_whitespace().nth(1).map(str::parse::<usize>) {
+                    Some(Ok(line)) => self.list(false, Some(line))?,
+                    _ => return Err("Usage: list [-|<line>]".to_string()),
                 }
-                "compute" => {
-                    let compute_data = self.dbg.get_compute_units();
-                    if let Some(total) = compute_data.get("total").and_then(|v| v.as_u64()) {
-                        if let Some(used) = compute_data.get("used").and_then(|v| v.as_u64()) {
-                            println!("Program consumed {} of {} compute units", used, total);
-                        }
+            }
+            "lines" => {
+                if let Some(ref dwarf_map) = self.dbg.dwarf_line_map {
+                    println!("+----------+--------------------------+");
+                    println!("| Line     | Instruction Addresses    |");
+                    println!("+----------+--------------------------+");
+                    let mut lines: Vec<_> = dwarf_map.get_line_to_addresses().iter().collect();
+                    lines.sort_by_key(|(line, _)| *line);
+                    for (line, _) in lines {
+                        let pcs_str = dwarf_map
+                            .get_pcs_for_line(*line)
+                            .iter()
+                            .map(|pc| format!("0x{:016x}", pc))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        println!("| {:<8} | {:<24} |", line, pcs_str);
+                    }
+                    println!("+----------+--------------------------+");
+                } else {
+                    println!("No DWARF line mapping available.");
+                }
+            }
+            "stack" | "bt" => {
+                let frames = self.dbg.get_frames();
+                if frames.is_empty() {
+                    println!("No stack frames available");
+                } else {
+                    println!("Call stack:");
+                    for (idx, frame) in frames.iter().enumerate() {
+                        let marker = if idx == self.dbg.selected_frame {
+                            "=>"
+                        } else {
+                            "  "
+                        };
+                        println!("{}{}", marker, format_frame(frame));
                     }
                 }
-                _ => println!("Unknown command. Type 'help'."),
             }
+            "up" | "down" | "frame" => {
+                let index = match cmd {
+                    "up" => self.dbg.selected_frame + 1,
+                    "down" => self
+                        .dbg
+                        .selected_frame
+                        .checked_sub(1)
+                        .ok_or("Already at the innermost frame")?,
+                    _ => self.dbg.selected_frame,
+                };
+                self.select_frame(index)?;
+            }
+            cmd if cmd.starts_with("frame ") => {
+                let index = cmd
+                    .strip_prefix("frame ")
+                    .unwrap_or_default()
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| "Usage: frame <n>".to_string())?;
+                self.select_frame(index)?;
+            }
+            "compute" if self.json() => println!("{}", self.dbg.get_compute_units()),
+            "compute" => {
+                let compute_data = self.dbg.get_compute_units();
+                if let Some(total) = compute_data.get("total").and_then(|v| v.as_u64()) {
+                    if let Some(used) = compute_data.get("used").and_then(|v| v.as_u64()) {
+                        println!("Program consumed {} of {} compute units", used, total);
+                    }
+                }
+            }
+            _ => return Err("Unknown command. Type 'help'.".to_string()),
+        }
+        Ok(CommandOutcome::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{debugger::Watch, parser::LineMap, test_utils::with_debugger};
+
+    #[test]
+    fn test_long_rodata_value() {
+        let symbol = Symbol {
+            name: "a_very_long_symbol_name".to_string(),
+            address: 0x100000000,
+            size: 57,
+            kind: object::SymbolKind::Data,
+            content: "Hello from a rodata string that does not fit in the table".to_string(),
+        };
+
+        let table = format_symbol_table(std::slice::from_ref(&symbol));
+        assert!(!table.contains(&symbol.content));
+        assert!(table.contains("Hello from a rodata s..."));
+        assert!(table.contains("a_very_lon..."));
+        // Every row of the table has the same width.
+        let widths: Vec<usize> = table.lines().map(|line| line.chars().count()).collect();
+        assert!(widths.iter().all(|&width| width == widths[0]));
+
+        let full = format_symbol(&symbol);
+        assert!(full.contains(&symbol.content));
+        assert!(full.contains(&symbol.name));
+    }
+
+    #[test]
+    fn test_repeat_step() {
+        let program = "
+            mov64 r0, 0
+            mov64 r1, 1
+            mov64 r2, 2
+            mov64 r3, 3
+            mov64 r4, 4
+            mov64 r5, 5
+            exit";
+        with_debugger(program, |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert_eq!(
+                repl.execute_command("repeat 5 step"),
+                Ok(CommandOutcome::Continue)
+            );
+            assert_eq!(repl.dbg.get_pc(), 5 * 8);
+            assert_eq!(repl.dbg.get_register(4), Some(4));
+        });
+    }
+
+    #[test]
+    fn test_restart() {
+        with_debugger("mov64 r0, 7\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("break 0x8").unwrap();
+            repl.execute_command("continue").unwrap();
+            assert_eq!(
+                repl.execute_command("continue"),
+                Ok(CommandOutcome::Terminated)
+            );
+            repl.execute_command("restart").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 0);
+            // Breakpoints are kept.
+            repl.execute_command("continue").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 8);
+        });
+    }
+
+    #[test]
+    fn test_repeat_stops_on_exit() {
+        with_debugger("mov64 r0, 0\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert_eq!(
+                repl.execute_command("repeat 5 step"),
+                Ok(CommandOutcome::Terminated)
+            );
+        });
+    }
+
+    #[test]
+    fn test_script_on_error() {
+        let program = "
+            mov64 r1, 1
+            mov64 r2, 2
+            exit";
+        let script = "step\nreg 42\nstep\n";
+
+        with_debugger(program, |dbg| {
+            let mut repl = Repl::new(dbg);
+            let err = repl.run_script(script, OnError::Stop).unwrap_err();
+            assert!(err.contains("line 2"));
+            assert_eq!(repl.dbg.get_pc(), 8);
+        });
+
+        with_debugger(program, |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert_eq!(
+                repl.run_script(script, OnError::Continue),
+                Ok(CommandOutcome::Continue)
+            );
+            assert_eq!(repl.dbg.get_pc(), 2 * 8);
+        });
+    }
+
+    #[test]
+    fn test_empty_line_repeats_command() {
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nmov64 r3, 3\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            // Nothing to repeat yet.
+            assert_eq!(repl.execute_interactive(""), Ok(CommandOutcome::Continue));
+            assert_eq!(repl.dbg.get_pc(), 0);
+
+            repl.execute_interactive("step").unwrap();
+            repl.execute_interactive("").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 2 * 8);
+
+            // Other commands are not repeated.
+            repl.execute_interactive("setreg 1 7").unwrap();
+            repl.execute_interactive("setreg 1 8").unwrap();
+            repl.execute_interactive("").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 2 * 8);
+            assert_eq!(repl.dbg.get_register(1), Some(8));
+        });
+    }
+
+    #[test]
+    fn test_split_commands() {
+        assert_eq!(
+            split_commands("break 10 ; continue;regs"),
+            vec!["break 10", "continue", "regs"]
+        );
+        assert_eq!(split_commands(" ; step ;; "), vec!["step"]);
+        assert_eq!(
+            split_commands("save breakpoints 'a;b' ; quit"),
+            vec!["save breakpoints a;b", "quit"]
+        );
+        assert_eq!(split_commands("eval \"r1; r2\""), vec!["eval r1; r2"]);
+    }
+
+    #[test]
+    fn test_execute_line() {
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nmov64 r3, 3\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            // A failing command does not stop the next ones.
+            assert_eq!(
+                repl.execute_interactive("step; reg 42; step"),
+                Ok(CommandOutcome::Continue)
+            );
+            assert_eq!(repl.dbg.get_pc(), 2 * 8);
+            assert!(repl.execute_interactive("step; reg 42").is_err());
+            assert_eq!(
+                repl.execute_interactive("quit; step"),
+                Ok(CommandOutcome::Quit)
+            );
+            assert_eq!(repl.dbg.get_pc(), 3 * 8);
+        });
+    }
+
+    #[test]
+    fn test_command_aliases() {
+        assert_eq!(parse_alias("sr = step --diff"), Some(("sr", "step --diff")));
+        assert_eq!(parse_alias("s r = step"), None);
+        assert_eq!(parse_alias("sr ="), None);
+
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nmov64 r3, 3\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("alias sr = step --diff").unwrap();
+            repl.execute_command("sr").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 8);
+
+            // Aliases can take arguments and refer to other aliases.
+            repl.execute_command("alias b = break").unwrap();
+            repl.execute_command("alias bb = b").unwrap();
+            repl.execute_command("bb 0x18").unwrap();
+            assert!(repl.dbg.breakpoints.contains(&0x18));
+
+            // An alias may stand for several commands, quoted to define it on one line.
+            repl.execute_interactive("alias s2 = 'step; step'").unwrap();
+            repl.execute_command("s2").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 3 * 8);
+            repl.execute_interactive("alias again = 's2; again'")
+                .unwrap();
+            assert!(repl
+                .execute_command("again")
+                .unwrap_err()
+                .contains("itself"));
+            assert_eq!(repl.dbg.get_pc(), 3 * 8);
+
+            repl.execute_command("alias loop1 = loop2").unwrap();
+            repl.execute_command("alias loop2 = loop1").unwrap();
+            assert!(repl
+                .execute_command("loop1")
+                .unwrap_err()
+                .contains("itself"));
+            assert!(repl.execute_command("alias alias = quit").is_err());
+            assert!(repl.execute_command("alias sr").is_err());
+
+            repl.execute_command("unalias sr").unwrap();
+            assert!(repl.execute_command("sr").is_err());
+            assert!(repl.execute_command("unalias sr").is_err());
+        });
+    }
+
+    #[test]
+    fn test_register_aliases() {
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("step").unwrap();
+            assert_eq!(repl.dbg.read_register(RegisterName::Pc), Some(8));
+            assert_eq!(repl.execute_command("reg pc"), Ok(CommandOutcome::Continue));
+            assert_eq!(repl.execute_command("reg sp"), Ok(CommandOutcome::Continue));
+
+            repl.execute_command("setreg fp 0x20").unwrap();
+            assert_eq!(repl.dbg.get_register(10), Some(0x20));
+            repl.execute_command("setreg pc 0x10").unwrap();
+            assert_eq!(repl.dbg.get_register(11), Some(2));
+            assert_eq!(repl.dbg.get_pc(), 0x10);
+            assert!(repl.execute_command("setreg pc 0x11").is_err());
+            assert!(repl.execute_command("reg ip").is_err());
+        });
+    }
+
+    #[test]
+    fn test_expression_arguments() {
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nmov64 r3, 3\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("setreg 1 r10 - 16").unwrap();
+            let fp = repl.dbg.get_register(10).unwrap();
+            assert_eq!(repl.dbg.get_register(1), Some(fp - 16));
+            repl.execute_command("setreg r2 2 * 3").unwrap();
+            assert_eq!(repl.dbg.get_register(2), Some(6));
+            assert!(repl.execute_command("setreg r2 r1 +").is_err());
+            assert!(repl.execute_command("setreg r2").is_err());
+
+            repl.execute_command("break r11*8 + 16").unwrap();
+            assert!(repl.dbg.breakpoints.contains(&16));
+            repl.execute_command("break 0x18").unwrap();
+            assert!(repl.dbg.breakpoints.contains(&24));
+        });
+    }
+
+    #[test]
+    fn test_jump() {
+        let mut line_map = LineMap::new();
+        for (address, line) in [(0, 1), (8, 2), (16, 3)] {
+            line_map.add_row(address, "program.s".to_string(), line, 0);
         }
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nexit", |mut dbg| {
+            dbg.set_dwarf_line_map(line_map);
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("jump 3").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 16);
+            repl.execute_command("jump program.s:2").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 8);
+            repl.execute_command("jump pc - 8").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 0);
+            assert!(repl.execute_command("jump 7").is_err());
+            assert!(repl.execute_command("jump 0x18").is_err());
+            // Skip the first instruction.
+            let r1 = repl.dbg.get_register(1);
+            repl.execute_command("jump 0x8").unwrap();
+            repl.execute_command("continue").unwrap();
+            assert_eq!(repl.dbg.get_register(1), r1);
+            assert_eq!(repl.dbg.get_register(2), Some(2));
+        });
+    }
+
+    #[test]
+    fn test_tbreak() {
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("tbreak 0x8").unwrap();
+            assert!(repl.dbg.temporary_breakpoints.contains(&8));
+            assert!(repl.execute_command("tbreak").is_err());
+            assert!(repl.execute_command("tbreak main").is_err());
+            repl.execute_command("continue").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 8);
+            assert!(repl.dbg.breakpoints.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_enable_disable() {
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.dbg.set_breakpoint(8);
+            repl.execute_command("disable 0x8").unwrap();
+            assert!(repl.dbg.disabled.contains(&8));
+            assert!(repl.execute_command("disable 0x10").is_err());
+            assert!(repl.execute_command("enable main").is_err());
+            repl.execute_command("enable 0x8").unwrap();
+            assert!(repl.dbg.disabled.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_conditional_break() {
+        let source = "mov64 r1, 1\ncall function_foo\nexit\nfunction_foo:\nmov64 r0, 0\nexit";
+        with_debugger(source, |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert!(repl.execute_command("break 42 if r2 = 1").is_err());
+            repl.execute_command("break function_foo if r1 == 2")
+                .unwrap();
+            assert_eq!(repl.dbg.conditions[&24].to_string(), "r1 == 0x2");
+            // The condition does not hold, so the program runs to its end.
+            assert_eq!(
+                repl.execute_command("continue"),
+                Ok(CommandOutcome::Terminated)
+            );
+        });
+    }
+
+    #[test]
+    fn test_save_and_source_breakpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("breakpoints");
+        let command = format!("save breakpoints {}", path.display());
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.dbg.set_breakpoint(8);
+            repl.execute_command(&command).unwrap();
+        });
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.execute_command(&format!("source breakpoints {}", path.display()))
+                .unwrap();
+            assert!(repl.dbg.breakpoints.contains(&8));
+            assert!(repl
+                .execute_command("source breakpoints /nonexistent/breakpoints")
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn test_frame_selection() {
+        let source = "call function_foo\nexit\nfunction_foo:\nmov64 r0, 0\nexit";
+        with_debugger(source, |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("stepi").unwrap();
+            assert_eq!(repl.dbg.get_frames().len(), 2);
+            assert!(repl.execute_command("down").is_err());
+            repl.execute_command("up").unwrap();
+            assert_eq!(repl.dbg.selected_frame, 1);
+            // The caller continues after the call.
+            assert_eq!(repl.dbg.get_selected_pc(), 8);
+            assert!(repl.execute_command("up").is_err());
+            repl.execute_command("frame 0").unwrap();
+            assert_eq!(repl.dbg.get_selected_pc(), 16);
+
+            repl.execute_command("frame 1").unwrap();
+            repl.execute_command("stepi").unwrap();
+            assert_eq!(repl.dbg.selected_frame, 0);
+        });
+    }
+
+    #[test]
+    fn test_snapshot_commands() {
+        with_debugger("mov64 r1, 1\nmov64 r1, 2\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert!(repl.execute_command("restore").is_err());
+            repl.execute_command("stepi").unwrap();
+            repl.execute_command("snapshot").unwrap();
+            assert_eq!(
+                repl.execute_command("continue"),
+                Ok(CommandOutcome::Terminated)
+            );
+            repl.execute_command("restore").unwrap();
+            assert_eq!(repl.dbg.get_pc(), 8);
+            assert_eq!(repl.dbg.get_register(1), Some(1));
+            assert!(repl.execute_command("restore 1").is_err());
+            repl.execute_command("restore 0").unwrap();
+        });
+    }
+
+    #[test]
+    fn test_examine() {
+        assert_eq!(
+            Examine::parse(""),
+            Ok(Examine {
+                count: 1,
+                format: 'x',
+                size: 4
+            })
+        );
+        assert_eq!(
+            Examine::parse("8xb"),
+            Ok(Examine {
+                count: 8,
+                format: 'x',
+                size: 1
+            })
+        );
+        assert_eq!(Examine::parse("3c").unwrap().size, 1);
+        assert_eq!(Examine::parse("2dg").unwrap().len(), 16);
+        assert!(Examine::parse("4q").is_err());
+        assert_eq!(Examine::parse("8192g").unwrap().len(), 64 * 1024);
+        assert!(Examine::parse("8193g").unwrap_err().contains("too large"));
+        assert!(Examine::parse("9999999999999999999g")
+            .unwrap_err()
+            .contains("too large"));
+
+        let data = [0x48, 0x69, 0xff, 0xff, 0x00, 0x01, 0x00, 0x00];
+        assert_eq!(
+            Examine::parse("4xh").unwrap().format(0x10, &data),
+            "0x0000000000000010: 0x6948  0xffff  0x0100  0x0000\n"
+        );
+        assert_eq!(
+            Examine::parse("2dw").unwrap().format(0x10, &data),
+            "0x0000000000000010: -38584  256\n"
+        );
+        assert_eq!(
+            Examine::parse("2uw").unwrap().format(0x10, &data),
+            "0x0000000000000010: 4294928712  256\n"
+        );
+        assert_eq!(
+            Examine::parse("3c").unwrap().format(0x10, &data[..3]),
+            "0x0000000000000010: 72 'H'  105 'i'  255 '\\xff'\n"
+        );
+        // Lines hold up to 16 bytes.
+        assert_eq!(
+            Examine::parse("3xg")
+                .unwrap()
+                .format(0x10, &[0; 24])
+                .lines()
+                .count(),
+            2
+        );
+
+        with_debugger("mov64 r1, 5\nstxdw [r10-8], r1\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert!(repl.execute_command("x/8xb").is_err());
+            assert!(repl.execute_command("x/8xb 0").is_err());
+            repl.execute_command("x/8xb r10 - 8").unwrap();
+            repl.execute_command("x r10 - 8").unwrap();
+        });
+    }
+
+    #[test]
+    fn test_dump_and_restore_memory() {
+        with_debugger("mov64 r1, 5\nstxdw [r10-8], r1\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("stepi").unwrap();
+            repl.execute_command("stepi").unwrap();
+            let dir = tempfile::tempdir().unwrap();
+            let file = dir.path().join("stack.bin");
+            let file = file.to_str().unwrap();
+
+            repl.execute_command(&format!("dump memory {} r10-8 r10", file))
+                .unwrap();
+            assert_eq!(std::fs::read(file).unwrap(), 5u64.to_le_bytes());
+            assert!(repl
+                .execute_command(&format!("dump memory {} r10 r10", file))
+                .is_err());
+            assert!(repl.execute_command("dump memory stack.bin").is_err());
+
+            std::fs::write(file, 9u64.to_le_bytes()).unwrap();
+            repl.execute_command(&format!("restore memory {} r10-16", file))
+                .unwrap();
+            assert_eq!(repl.dbg.evaluate("*[r10 - 16]"), Ok(9));
+            // The program is read-only.
+            assert!(repl
+                .execute_command(&format!("restore memory {} 0x100000000", file))
+                .is_err());
+            assert!(repl.execute_command("restore memory").is_err());
+            // Without `memory`, `restore` only goes back to snapshots.
+            assert!(repl
+                .execute_command(&format!("restore {} r10-16", file))
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn test_format_register_as() {
+        let value = -16i64 as u64;
+        assert_eq!(
+            format_register_as(value, "/s"),
+            Ok("Signed: -16".to_string())
+        );
+        assert_eq!(format_register_as(5, "/b"), Ok("Binary: 0b101".to_string()));
+        assert_eq!(
+            format_register_as(u64::from_le_bytes(*b"Hi!\0\0\0\0\x7f"), "/c"),
+            Ok("ASCII:  \"Hi!.....\"".to_string())
+        );
+        assert!(format_register_as(5, "/x").is_err());
+    }
+
+    #[test]
+    fn test_watches() {
+        with_debugger("mov64 r1, 5\nstxdw [r10-8], r1\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert!(repl.execute_command("watch r10").is_err());
+            assert!(repl.execute_command("watch r10 - 8 0").is_err());
+            assert!(repl.execute_command("watch 0 8").is_err());
+            repl.execute_command("watch r10 - 8 8").unwrap();
+            let address = repl.dbg.get_register(10).unwrap() - 8;
+            assert_eq!(repl.dbg.watches.get(&1), Some(&Watch { address, len: 8 }));
+
+            repl.execute_command("stepi").unwrap();
+            repl.execute_command("stepi").unwrap();
+            assert!(repl
+                .format_watches()
+                .ends_with("\n05 00 00 00 00 00 00 00\n"));
+
+            repl.execute_command("unwatch 1").unwrap();
+            assert!(repl.dbg.watches.is_empty());
+            assert!(repl.execute_command("unwatch 1").is_err());
+        });
+    }
+
+    #[test]
+    fn test_set_disasm() {
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert!(repl.show_disasm);
+            assert_eq!(
+                repl.dbg.disassemble_instruction(8).as_deref(),
+                Some("mov64 r2, 2")
+            );
+            repl.execute_command("set disasm off").unwrap();
+            assert!(!repl.show_disasm);
+            repl.execute_command("set disasm on").unwrap();
+            assert!(repl.show_disasm);
+            assert!(repl.execute_command("set disasm maybe").is_err());
+            assert_eq!(repl.execute_command("step"), Ok(CommandOutcome::Continue));
+        });
+    }
+
+    #[test]
+    fn test_step_diff() {
+        assert_eq!(
+            format_register_changes(&[(1, 5, 8)]),
+            "  r1  0x0000000000000005 -> 0x0000000000000008\n"
+        );
+        assert_eq!(format_register_changes(&[]), "No registers changed\n");
+
+        with_debugger("mov64 r1, 5\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert_eq!(repl.execute_command("sd"), Ok(CommandOutcome::Continue));
+            assert_eq!(repl.dbg.get_register(1), Some(5));
+            assert_eq!(
+                repl.execute_command("step --diff"),
+                Ok(CommandOutcome::Terminated)
+            );
+        });
+    }
+
+    #[test]
+    fn test_json_flag() {
+        with_debugger("mov64 r1, 1\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert_eq!(
+                repl.execute_command("regs --json"),
+                Ok(CommandOutcome::Continue)
+            );
+            assert_eq!(
+                repl.execute_command("reg 1 --json"),
+                Ok(CommandOutcome::Continue)
+            );
+            assert!(repl.execute_command("reg 42 --json").is_err());
+            // The flag only applies to its command.
+            assert_eq!(repl.format, OutputFormat::Text);
+
+            repl.set_format(OutputFormat::Json);
+            assert_eq!(
+                repl.execute_command("compute"),
+                Ok(CommandOutcome::Continue)
+            );
+            assert_eq!(repl.format, OutputFormat::Json);
+        });
+
+        assert_eq!("json".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_script_skips_comments_and_blank_lines() {
+        let script = "# Step over the first move\n\nstep\n   # indented comment\n\t\nstep\n";
+
+        with_debugger("mov64 r1, 1\nmov64 r2, 2\nexit", |dbg| {
+            let mut repl = Repl::new(dbg);
+            assert_eq!(
+                repl.run_script(script, OnError::Stop),
+                Ok(CommandOutcome::Continue)
+            );
+            assert_eq!(repl.dbg.get_pc(), 2 * 8);
+        });
+    }
+
+    #[test]
+    fn test_list_pages_forward() {
+        let source: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+        let path = std::env::temp_dir().join(format!("sbpf-dbg-list-{}.s", std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        let mut line_map = LineMap::new();
+        for line in 1..=30 {
+            line_map.add_row(
+                (line as u64 - 1) * 8,
+                path.to_string_lossy().to_string(),
+                line,
+                0,
+            );
+        }
+
+        with_debugger("exit", |mut dbg| {
+            dbg.set_dwarf_line_map(line_map);
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("list").unwrap();
+            let first = repl.list_window.clone().unwrap();
+            repl.execute_command("list").unwrap();
+            let second = repl.list_window.clone().unwrap();
+            assert_eq!((first.first, first.last), (1, LIST_LINES));
+            assert_eq!(second.first, first.last + 1);
+            assert_eq!(second.last, 2 * LIST_LINES);
+
+            repl.execute_command("list -").unwrap();
+            assert_eq!(repl.list_window.clone().unwrap(), first);
+
+            repl.execute_command("list 25").unwrap();
+            let window = repl.list_window.clone().unwrap();
+            assert_eq!((window.first, window.last), (20, 29));
+        });
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_list_asm() {
+        let program = "mov64 r1, 1\nlddw r2, 0x1122334455667788\nexit";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("program.s");
+        std::fs::write(&path, program).unwrap();
+        let file = path.to_string_lossy().to_string();
+        let mut line_map = LineMap::new();
+        for (address, line) in [(0, 1), (8, 2), (24, 3)] {
+            line_map.add_row(address, file.clone(), line, 0);
+        }
+
+        with_debugger(program, |mut dbg| {
+            // Without line information, only the instructions are shown.
+            let mut repl = Repl::new(dbg);
+            repl.execute_command("stepi").unwrap();
+            assert_eq!(
+                repl.format_source_and_assembly().unwrap(),
+                concat!(
+                    "     0x0000000000000000: mov64 r1, 1\n",
+                    "=>   0x0000000000000008: lddw r2, 0x1122334455667788\n",
+                    "     0x0000000000000018: exit\n"
+                )
+            );
+            dbg = repl.dbg;
+
+            dbg.set_dwarf_line_map(line_map);
+            let mut repl = Repl::new(dbg);
+            let output = repl.format_source_and_assembly().unwrap();
+            let lines: Vec<_> = output.lines().collect();
+            assert_eq!(lines.len(), 6);
+            assert_eq!(lines[0], format!("{}:1  mov64 r1, 1", file));
+            assert_eq!(lines[2], format!("{}:2  lddw r2, 0x1122334455667788", file));
+            assert!(lines[3].starts_with("=>   0x0000000000000008"));
+            assert_eq!(lines[4], format!("{}:3  exit", file));
+        });
+    }
+
+    #[test]
+    fn test_list_missing_source_file() {
+        let mut line_map = LineMap::new();
+        line_map.add_row(0, "/nonexistent/program.s".to_string(), 1, 0);
+
+        with_debugger("exit", |mut dbg| {
+            dbg.set_dwarf_line_map(line_map);
+            let mut repl = Repl::new(dbg);
+            let err = repl.execute_command("list").unwrap_err();
+            assert!(err.contains("no longer exists"));
+        });
     }
 }
@@ -22,12 +22,295 @@
 //! respect this convention.
 
 use crate::DebugContextObject;
+use solana_clock::Clock;
+use solana_epoch_schedule::EpochSchedule;
+use solana_pubkey::Pubkey;
+use solana_rent::Rent;
 use solana_sbpf::{
-    declare_builtin_function,
-    error::EbpfError,
+    declare_builtin_function, ebpf,
+    elf::ElfError,
+    error::{EbpfError, ProgramResult},
     memory_region::{AccessType, MemoryMapping},
+    program::{BuiltinFunction, BuiltinProgram},
+    vm::{get_runtime_environment_key, Config, ContextObject, EbpfVm},
 };
-use std::{slice::from_raw_parts, str::from_utf8};
+use std::{
+    mem::size_of,
+    slice::{from_raw_parts, from_raw_parts_mut},
+    str::from_utf8,
+};
+use thiserror::Error;
+
+/// Alignment of heap allocations, matching the runtime's `BPF_ALIGN_OF_U128`
+const BPF_ALIGN_OF_U128: u64 = 16;
+/// Maximum size of return data, matching the runtime's `MAX_RETURN_DATA`
+const MAX_RETURN_DATA: u64 = 1024;
+/// Maximum number of seeds for a program derived address
+const MAX_SEEDS: u64 = 16;
+/// Maximum length of a program derived address seed
+const MAX_SEED_LEN: u64 = 32;
+/// Invocation stack height of a top-level instruction, matching the runtime's
+/// `TRANSACTION_LEVEL_STACK_HEIGHT`
+pub const TRANSACTION_LEVEL_STACK_HEIGHT: u64 = 1;
+/// Error codes returned by `sol_secp256k1_recover`, matching the runtime
+#[cfg(feature = "secp256k1")]
+const SECP256K1_RECOVER_INVALID_HASH: u64 = 1;
+#[cfg(feature = "secp256k1")]
+const SECP256K1_RECOVER_INVALID_RECOVERY_ID: u64 = 2;
+#[cfg(feature = "secp256k1")]
+const SECP256K1_RECOVER_INVALID_SIGNATURE: u64 = 3;
+/// Maximum number of inputs hashed by `sol_poseidon`, matching the runtime
+#[cfg(feature = "poseidon")]
+const MAX_POSEIDON_INPUTS: u64 = 12;
+
+/// Errors raised by syscalls that stop the program on purpose
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SyscallError {
+    #[error("program aborted")]
+    Abort,
+    #[error("program panicked at {file}:{line}:{column}")]
+    Panic {
+        file: String,
+        line: u64,
+        column: u64,
+    },
+}
+
+/// Syscalls made available to the program being debugged, registered by name.
+///
+/// Embedding applications can register their own syscalls in addition to (or instead of) the
+/// built-in ones before the loader and the `Executable` are constructed.
+pub struct SyscallRegistry {
+    functions: Vec<(String, BuiltinFunction<DebugContextObject>)>,
+}
+
+impl Default for SyscallRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyscallRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self {
+            functions: Vec::new(),
+        }
+    }
+
+    /// Create a registry with all the syscalls built into the debugger.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+
+        // Logging syscalls
+        registry
+            .register_traced::<SyscallLog>()
+            .register_traced::<SyscallLogU64>();
+
+        // Panic syscalls
+        registry
+            .register_traced::<SyscallAbort>()
+            .register_traced::<SyscallPanic>();
+
+        // Memory syscalls
+        registry.register_traced::<SyscallAllocFree>();
+
+        // Compute budget syscalls
+        registry.register_traced::<SyscallRemainingComputeUnits>();
+
+        // Return data syscalls
+        registry
+            .register_traced::<SyscallSetReturnData>()
+            .register_traced::<SyscallGetReturnData>();
+
+        // Program derived address syscalls
+        registry
+            .register_traced::<SyscallCreateProgramAddress>()
+            .register_traced::<SyscallTryFindProgramAddress>();
+
+        // Cryptographic syscalls
+        #[cfg(feature = "secp256k1")]
+        registry.register_traced::<SyscallSecp256k1Recover>();
+        #[cfg(feature = "poseidon")]
+        registry.register_traced::<SyscallPoseidon>();
+
+        // Sysvar syscalls
+        registry
+            .register_traced::<SyscallGetClockSysvar>()
+            .register_traced::<SyscallGetRentSysvar>()
+            .register_traced::<SyscallGetEpochScheduleSysvar>();
+
+        // Cross-program invocation syscalls (the callee is not executed)
+        registry
+            .register_traced::<SyscallInvokeSignedC>()
+            .register_traced::<SyscallInvokeSignedRust>()
+            .register_traced::<SyscallGetStackHeight>();
+
+        registry
+    }
+
+    /// Register a syscall by name, replacing any syscall previously registered under that name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        function: BuiltinFunction<DebugContextObject>,
+    ) -> &mut Self {
+        match self.functions.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = function,
+            None => self.functions.push((name.to_string(), function)),
+        }
+        self
+    }
+
+    /// Register a syscall whose invocations are recorded in the syscall trace.
+    pub fn register_traced<S: TracedSyscall>(&mut self) -> &mut Self {
+        self.register(S::NAME, traced::<S>)
+    }
+
+    /// Names of all registered syscalls.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.functions.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Build a loader with all registered syscalls.
+    pub fn build_loader(
+        &self,
+        config: Config,
+    ) -> Result<BuiltinProgram<DebugContextObject>, ElfError> {
+        let mut loader = BuiltinProgram::new_loader(config);
+        for (name, function) in &self.functions {
+            loader.register_function(name, *function)?;
+        }
+        Ok(loader)
+    }
+}
+
+/// A syscall whose invocations are recorded in the syscall trace of the context object.
+pub trait TracedSyscall {
+    /// Name the syscall is registered under
+    const NAME: &'static str;
+    /// Entry point of the syscall
+    const FUNCTION: BuiltinFunction<DebugContextObject>;
+}
+
+macro_rules! impl_traced_syscall {
+    ($($syscall:ident => $name:literal),* $(,)?) => {
+        $(
+            impl TracedSyscall for $syscall {
+                const NAME: &'static str = $name;
+                const FUNCTION: BuiltinFunction<DebugContextObject> = $syscall::vm;
+            }
+        )*
+    };
+}
+
+impl_traced_syscall!(
+    SyscallLog => "sol_log_",
+    SyscallLogU64 => "sol_log_64_",
+    SyscallAbort => "abort",
+    SyscallPanic => "sol_panic_",
+    SyscallAllocFree => "sol_alloc_free_",
+    SyscallRemainingComputeUnits => "sol_remaining_compute_units",
+    SyscallSetReturnData => "sol_set_return_data",
+    SyscallGetReturnData => "sol_get_return_data",
+    SyscallCreateProgramAddress => "sol_create_program_address",
+    SyscallTryFindProgramAddress => "sol_try_find_program_address",
+    SyscallGetClockSysvar => "sol_get_clock_sysvar",
+    SyscallGetRentSysvar => "sol_get_rent_sysvar",
+    SyscallGetEpochScheduleSysvar => "sol_get_epoch_schedule_sysvar",
+    SyscallInvokeSignedC => "sol_invoke_signed_c",
+    SyscallInvokeSignedRust => "sol_invoke_signed_rust",
+    SyscallGetStackHeight => "sol_get_stack_height",
+);
+
+#[cfg(feature = "secp256k1")]
+impl_traced_syscall!(SyscallSecp256k1Recover => "sol_secp256k1_recover");
+
+#[cfg(feature = "poseidon")]
+impl_traced_syscall!(SyscallPoseidon => "sol_poseidon");
+
+/// A syscall invocation recorded in the syscall trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyscallTraceEntry {
+    pub name: &'static str,
+    /// PC of the call instruction
+    pub pc: u64,
+    pub args: [u64; 5],
+    /// Value returned in `r0`, or `None` if the syscall failed
+    pub return_value: Option<u64>,
+}
+
+/// Invoke `S` and record the call in the syscall trace.
+fn traced<S: TracedSyscall>(
+    vm: *mut EbpfVm<DebugContextObject>,
+    arg_a: u64,
+    arg_b: u64,
+    arg_c: u64,
+    arg_d: u64,
+    arg_e: u64,
+) {
+    (S::FUNCTION)(vm, arg_a, arg_b, arg_c, arg_d, arg_e);
+    // Builtins are passed a pointer offset by the runtime environment key.
+    let vm = unsafe {
+        &mut *(vm
+            .cast::<u64>()
+            .offset(-(get_runtime_environment_key() as isize))
+            .cast::<EbpfVm<DebugContextObject>>())
+    };
+    let return_value = match &vm.program_result {
+        ProgramResult::Ok(value) => Some(*value),
+        ProgramResult::Err(_) => None,
+    };
+    let pc = vm.registers[11] * ebpf::INSN_SIZE as u64;
+    vm.context_object_pointer.record_syscall(SyscallTraceEntry {
+        name: S::NAME,
+        pc,
+        args: [arg_a, arg_b, arg_c, arg_d, arg_e],
+        return_value,
+    });
+}
+
+/// Translate a VM address range into a host slice for reading.
+fn translate_slice<'a>(
+    memory_mapping: &MemoryMapping,
+    vm_addr: u64,
+    len: u64,
+) -> Result<&'a [u8], EbpfError> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    let host_addr: Result<u64, EbpfError> =
+        memory_mapping.map(AccessType::Load, vm_addr, len).into();
+    let host_addr = host_addr?;
+    Ok(unsafe { from_raw_parts(host_addr as *const u8, len as usize) })
+}
+
+/// Read a little-endian `u64` from VM memory.
+fn read_u64(memory_mapping: &MemoryMapping, vm_addr: u64) -> Result<u64, EbpfError> {
+    let bytes = translate_slice(memory_mapping, vm_addr, 8)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Read a `Pubkey` from VM memory.
+fn read_pubkey(memory_mapping: &MemoryMapping, vm_addr: u64) -> Result<Pubkey, EbpfError> {
+    let bytes = translate_slice(memory_mapping, vm_addr, size_of::<Pubkey>() as u64)?;
+    Ok(Pubkey::try_from(bytes).unwrap())
+}
+
+/// Translate a VM address range into a host slice for writing.
+fn translate_slice_mut<'a>(
+    memory_mapping: &MemoryMapping,
+    vm_addr: u64,
+    len: u64,
+) -> Result<&'a mut [u8], EbpfError> {
+    if len == 0 {
+        return Ok(&mut []);
+    }
+    let host_addr: Result<u64, EbpfError> =
+        memory_mapping.map(AccessType::Store, vm_addr, len).into();
+    let host_addr = host_addr?;
+    Ok(unsafe { from_raw_parts_mut(host_addr as *mut u8, len as usize) })
+}
 
 declare_builtin_function!(
     /// Prints a NULL-terminated UTF-8 string.
@@ -51,7 +334,7 @@ declare_builtin_function!(
             let c_buf = from_raw_parts(host_addr as *const u8, len as usize);
             let len = c_buf.iter().position(|c| *c == 0).unwrap_or(len as usize);
             let message = from_utf8(&c_buf[0..len]).unwrap_or("Invalid UTF-8 String");
-            println!("Program log: {message}");
+            context_object.log(format!("Program log: {message}"));
         }
         Ok(0)
     }
@@ -72,12 +355,1146 @@ declare_builtin_function!(
         let cost = context_object.get_execution_cost().log_64_units;
         context_object.consume_checked(cost)?;
 
-        println!(
+        context_object.log(format!(
             "Program log: {:#x}, {:#x}, {:#x}, {:#x}, {:#x}",
             arg1, arg2, arg3, arg4, arg5
+        ));
+        Ok(0)
+    }
+);
+
+declare_builtin_function!(
+    /// Aborts the program, e.g. after a Rust panic.
+    SyscallAbort,
+    fn rust(
+        _context_object: &mut DebugContextObject,
+        _arg1: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        _memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        Err(SyscallError::Abort.into())
+    }
+);
+
+declare_builtin_function!(
+    /// Stops the program with the location of a Rust panic: the file name (`file`, `len`), line
+    /// and column.
+    SyscallPanic,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        file: u64,
+        len: u64,
+        line: u64,
+        column: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        context_object.consume_checked(len)?;
+
+        let file = translate_slice(memory_mapping, file, len)?;
+        Err(SyscallError::Panic {
+            file: String::from_utf8_lossy(file).into_owned(),
+            line,
+            column,
+        }
+        .into())
+    }
+);
+
+declare_builtin_function!(
+    /// Allocates memory from the heap region using a bump allocator.
+    ///
+    /// Freeing (a non-null `free_addr`) is a no-op, just like in the runtime.
+    SyscallAllocFree,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        size: u64,
+        free_addr: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        _memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        if free_addr != 0 {
+            return Ok(0);
+        }
+        // A null pointer signals an allocation failure to the program.
+        Ok(context_object
+            .alloc(size, BPF_ALIGN_OF_U128)
+            .unwrap_or(0))
+    }
+);
+
+declare_builtin_function!(
+    /// Sets the return data of the program, at most `MAX_RETURN_DATA` bytes.
+    SyscallSetReturnData,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        addr: u64,
+        len: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let execution_cost = context_object.get_execution_cost();
+        let cost = len
+            .checked_div(execution_cost.cpi_bytes_per_unit)
+            .unwrap_or(u64::MAX)
+            .saturating_add(execution_cost.syscall_base_cost);
+        context_object.consume_checked(cost)?;
+
+        if len > MAX_RETURN_DATA {
+            return Err(format!(
+                "Return data too large ({} > {})",
+                len, MAX_RETURN_DATA
+            )
+            .into());
+        }
+
+        let data = translate_slice(memory_mapping, addr, len)?.to_vec();
+        context_object.set_return_data(data);
+        Ok(0)
+    }
+);
+
+declare_builtin_function!(
+    /// Copies the return data and the id of the program that set it into VM memory.
+    ///
+    /// Returns the full length of the stored return data.
+    SyscallGetReturnData,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        return_data_addr: u64,
+        length: u64,
+        program_id_addr: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let execution_cost = context_object.get_execution_cost();
+        context_object.consume_checked(execution_cost.syscall_base_cost)?;
+
+        let (program_id, return_data) = context_object.get_return_data();
+        let (program_id, return_data) = (*program_id, return_data.to_vec());
+        let length = length.min(return_data.len() as u64);
+        if length != 0 {
+            let cost = length
+                .saturating_add(size_of::<Pubkey>() as u64)
+                .checked_div(execution_cost.cpi_bytes_per_unit)
+                .unwrap_or(u64::MAX);
+            context_object.consume_checked(cost)?;
+
+            translate_slice_mut(memory_mapping, return_data_addr, length)?
+                .copy_from_slice(&return_data[..length as usize]);
+            translate_slice_mut(memory_mapping, program_id_addr, size_of::<Pubkey>() as u64)?
+                .copy_from_slice(program_id.as_ref());
+        }
+
+        Ok(return_data.len() as u64)
+    }
+);
+
+/// Read the seeds (an array of `(addr, len)` slices) and program id of a PDA derivation.
+fn translate_program_address_inputs<'a>(
+    memory_mapping: &MemoryMapping,
+    seeds_addr: u64,
+    seeds_len: u64,
+    program_id_addr: u64,
+) -> Result<(Vec<&'a [u8]>, Pubkey), Box<dyn std::error::Error>> {
+    if seeds_len > MAX_SEEDS {
+        return Err(format!("Too many seeds ({} > {})", seeds_len, MAX_SEEDS).into());
+    }
+    let mut seeds = Vec::new();
+    for i in 0..seeds_len {
+        let seed_addr = read_u64(memory_mapping, seeds_addr + i * 16)?;
+        let seed_len = read_u64(memory_mapping, seeds_addr + i * 16 + 8)?;
+        if seed_len > MAX_SEED_LEN {
+            return Err(format!("Seed {} too long ({} > {})", i, seed_len, MAX_SEED_LEN).into());
+        }
+        seeds.push(translate_slice(memory_mapping, seed_addr, seed_len)?);
+    }
+    let program_id = read_pubkey(memory_mapping, program_id_addr)?;
+    Ok((seeds, program_id))
+}
+
+declare_builtin_function!(
+    /// Creates a program derived address from seeds and a program id.
+    ///
+    /// Returns 1 if the seeds derive an address on the ed25519 curve.
+    SyscallCreateProgramAddress,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        seeds_addr: u64,
+        seeds_len: u64,
+        program_id_addr: u64,
+        address_addr: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let cost = context_object
+            .get_execution_cost()
+            .create_program_address_units;
+        context_object.consume_checked(cost)?;
+
+        let (seeds, program_id) = translate_program_address_inputs(
+            memory_mapping,
+            seeds_addr,
+            seeds_len,
+            program_id_addr,
+        )?;
+        let Ok(address) = Pubkey::create_program_address(&seeds, &program_id) else {
+            return Ok(1);
+        };
+        translate_slice_mut(memory_mapping, address_addr, size_of::<Pubkey>() as u64)?
+            .copy_from_slice(address.as_ref());
+        Ok(0)
+    }
+);
+
+declare_builtin_function!(
+    /// Finds a valid program derived address and its bump seed, starting from bump 255.
+    ///
+    /// Returns 1 if no bump seed derives an address off the ed25519 curve.
+    SyscallTryFindProgramAddress,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        seeds_addr: u64,
+        seeds_len: u64,
+        program_id_addr: u64,
+        address_addr: u64,
+        bump_seed_addr: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let cost = context_object
+            .get_execution_cost()
+            .create_program_address_units;
+        context_object.consume_checked(cost)?;
+
+        let (seeds, program_id) = translate_program_address_inputs(
+            memory_mapping,
+            seeds_addr,
+            seeds_len,
+            program_id_addr,
+        )?;
+        let mut bump_seed = [u8::MAX];
+        for _ in 0..u8::MAX {
+            let seeds_with_bump: Vec<&[u8]> = seeds
+                .iter()
+                .copied()
+                .chain(std::iter::once(bump_seed.as_slice()))
+                .collect();
+            if let Ok(address) = Pubkey::create_program_address(&seeds_with_bump, &program_id) {
+                translate_slice_mut(memory_mapping, bump_seed_addr, 1)?
+                    .copy_from_slice(&bump_seed);
+                translate_slice_mut(memory_mapping, address_addr, size_of::<Pubkey>() as u64)?
+                    .copy_from_slice(address.as_ref());
+                return Ok(0);
+            }
+            bump_seed[0] = bump_seed[0].saturating_sub(1);
+            context_object.consume_checked(cost)?;
+        }
+        Ok(1)
+    }
+);
+
+#[cfg(feature = "secp256k1")]
+declare_builtin_function!(
+    /// Recovers the 64-byte secp256k1 public key that signed a 32-byte hash.
+    ///
+    /// Returns a non-zero error code for an invalid hash, recovery id or signature.
+    SyscallSecp256k1Recover,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        hash_addr: u64,
+        recovery_id_val: u64,
+        signature_addr: u64,
+        result_addr: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let cost = context_object.get_execution_cost().secp256k1_recover_cost;
+        context_object.consume_checked(cost)?;
+
+        let hash = translate_slice(memory_mapping, hash_addr, 32)?;
+        let signature = translate_slice(memory_mapping, signature_addr, 64)?;
+        let result = translate_slice_mut(memory_mapping, result_addr, 64)?;
+
+        let Ok(message) = libsecp256k1::Message::parse_slice(hash) else {
+            return Ok(SECP256K1_RECOVER_INVALID_HASH);
+        };
+        let Ok(recovery_id) = u8::try_from(recovery_id_val)
+            .map_err(|_| ())
+            .and_then(|id| libsecp256k1::RecoveryId::parse(id).map_err(|_| ()))
+        else {
+            return Ok(SECP256K1_RECOVER_INVALID_RECOVERY_ID);
+        };
+        let Ok(signature) = libsecp256k1::Signature::parse_standard_slice(signature) else {
+            return Ok(SECP256K1_RECOVER_INVALID_SIGNATURE);
+        };
+        let Ok(public_key) = libsecp256k1::recover(&message, &signature, &recovery_id) else {
+            return Ok(SECP256K1_RECOVER_INVALID_SIGNATURE);
+        };
+
+        // Drop the 0x04 prefix of the uncompressed key.
+        result.copy_from_slice(&public_key.serialize()[1..65]);
+        Ok(0)
+    }
+);
+
+#[cfg(feature = "poseidon")]
+declare_builtin_function!(
+    /// Computes the Poseidon hash of up to 12 inputs, each a 32-byte field element given as a
+    /// `(ptr, len)` slice, and writes the 32-byte result.
+    ///
+    /// `parameters` selects the hash parameters (0 for BN254 with x^5 S-boxes, the only ones
+    /// supported) and `endianness` the byte order of the inputs and result (0 for big endian, 1
+    /// for little endian). Returns 1 if an input is not a valid field element.
+    SyscallPoseidon,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        parameters: u64,
+        endianness: u64,
+        vals_addr: u64,
+        vals_len: u64,
+        result_addr: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let parameters = solana_poseidon::Parameters::try_from(parameters)?;
+        let endianness = solana_poseidon::Endianness::try_from(endianness)?;
+        if vals_len > MAX_POSEIDON_INPUTS {
+            return Err(format!(
+                "Poseidon hashing {} inputs is not supported (at most {})",
+                vals_len, MAX_POSEIDON_INPUTS
+            )
+            .into());
+        }
+        let cost = context_object
+            .get_execution_cost()
+            .poseidon_cost(vals_len)
+            .ok_or("Poseidon cost overflow")?;
+        context_object.consume_checked(cost)?;
+
+        let result = translate_slice_mut(
+            memory_mapping,
+            result_addr,
+            solana_poseidon::HASH_BYTES as u64,
+        )?;
+        let mut inputs = Vec::new();
+        for i in 0..vals_len {
+            let input_addr = read_u64(memory_mapping, vals_addr + i * 16)?;
+            let input_len = read_u64(memory_mapping, vals_addr + i * 16 + 8)?;
+            inputs.push(translate_slice(memory_mapping, input_addr, input_len)?);
+        }
+        let Ok(hash) = solana_poseidon::hashv(parameters, endianness, &inputs) else {
+            return Ok(1);
+        };
+        result.copy_from_slice(&hash.to_bytes());
+        Ok(0)
+    }
+);
+
+declare_builtin_function!(
+    /// Returns the compute units left in the budget, after paying for this syscall.
+    SyscallRemainingComputeUnits,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        _arg1: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        _memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let cost = context_object.get_execution_cost().syscall_base_cost;
+        context_object.consume_checked(cost)?;
+        Ok(context_object.get_remaining())
+    }
+);
+
+declare_builtin_function!(
+    /// Returns the height of the invocation stack, 1 for a top-level instruction.
+    ///
+    /// The invoke syscalls record cross-program invocations without executing the callee, so the
+    /// debugged program never runs nested and always sees the transaction level.
+    SyscallGetStackHeight,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        _arg1: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        _memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let cost = context_object.get_execution_cost().syscall_base_cost;
+        context_object.consume_checked(cost)?;
+        Ok(TRANSACTION_LEVEL_STACK_HEIGHT)
+    }
+);
+
+/// Sysvars returned by the `sol_get_*_sysvar` syscalls. Defaults to the default value of each
+/// sysvar.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sysvars {
+    pub clock: Clock,
+    pub rent: Rent,
+    pub epoch_schedule: EpochSchedule,
+}
+
+/// Encode a `Clock` with its in-memory (`repr(C)`) layout.
+fn clock_bytes(clock: &Clock) -> Vec<u8> {
+    [
+        clock.slot.to_le_bytes(),
+        clock.epoch_start_timestamp.to_le_bytes(),
+        clock.epoch.to_le_bytes(),
+        clock.leader_schedule_epoch.to_le_bytes(),
+        clock.unix_timestamp.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Encode a `Rent` with its in-memory (`repr(C)`) layout, padding included.
+fn rent_bytes(rent: &Rent) -> Vec<u8> {
+    let mut bytes = [
+        rent.lamports_per_byte_year.to_le_bytes(),
+        rent.exemption_threshold.to_le_bytes(),
+    ]
+    .concat();
+    bytes.extend_from_slice(&[rent.burn_percent, 0, 0, 0, 0, 0, 0, 0]);
+    bytes
+}
+
+/// Encode an `EpochSchedule` with its in-memory (`repr(C)`) layout, padding included.
+fn epoch_schedule_bytes(epoch_schedule: &EpochSchedule) -> Vec<u8> {
+    [
+        epoch_schedule.slots_per_epoch.to_le_bytes(),
+        epoch_schedule.leader_schedule_slot_offset.to_le_bytes(),
+        (epoch_schedule.warmup as u64).to_le_bytes(),
+        epoch_schedule.first_normal_epoch.to_le_bytes(),
+        epoch_schedule.first_normal_slot.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Write an encoded sysvar to VM memory, charging for its size like the runtime does.
+fn write_sysvar(
+    context_object: &mut DebugContextObject,
+    memory_mapping: &MemoryMapping,
+    var_addr: u64,
+    bytes: &[u8],
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let cost = context_object.get_execution_cost().sysvar_base_cost + bytes.len() as u64;
+    context_object.consume_checked(cost)?;
+    translate_slice_mut(memory_mapping, var_addr, bytes.len() as u64)?.copy_from_slice(bytes);
+    Ok(0)
+}
+
+declare_builtin_function!(
+    /// Writes the `Clock` sysvar to `var_addr`.
+    SyscallGetClockSysvar,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        var_addr: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let bytes = clock_bytes(&context_object.get_sysvars().clock);
+        write_sysvar(context_object, memory_mapping, var_addr, &bytes)
+    }
+);
+
+declare_builtin_function!(
+    /// Writes the `Rent` sysvar to `var_addr`.
+    SyscallGetRentSysvar,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        var_addr: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let bytes = rent_bytes(&context_object.get_sysvars().rent);
+        write_sysvar(context_object, memory_mapping, var_addr, &bytes)
+    }
+);
+
+declare_builtin_function!(
+    /// Writes the `EpochSchedule` sysvar to `var_addr`.
+    SyscallGetEpochScheduleSysvar,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        var_addr: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let bytes = epoch_schedule_bytes(&context_object.get_sysvars().epoch_schedule);
+        write_sysvar(context_object, memory_mapping, var_addr, &bytes)
+    }
+);
+
+/// An account passed to a cross-program invocation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvocationAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// A cross-program invocation made by the program. The callee is not executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub program_id: Pubkey,
+    pub accounts: Vec<InvocationAccount>,
+    pub data: Vec<u8>,
+}
+
+/// Size of a Rust `AccountMeta`: pubkey, is_signer and is_writable
+const RUST_ACCOUNT_META_SIZE: u64 = 34;
+/// Size of a C `SolAccountMeta`: pubkey pointer, is_writable and is_signer plus padding
+const C_ACCOUNT_META_SIZE: u64 = 16;
+
+/// Record an invocation and log a summary of it.
+fn record_invocation(context_object: &mut DebugContextObject, invocation: Invocation) {
+    context_object.log(format!(
+        "Program invoke: {} with {} accounts",
+        invocation.program_id,
+        invocation.accounts.len()
+    ));
+    context_object.record_invocation(invocation);
+}
+
+declare_builtin_function!(
+    /// Cross-program invocation using the Rust ABI (`StableInstruction`).
+    ///
+    /// The instruction is parsed and recorded, but the callee is not executed.
+    SyscallInvokeSignedRust,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        instruction_addr: u64,
+        _account_infos_addr: u64,
+        _account_infos_len: u64,
+        _signers_seeds_addr: u64,
+        _signers_seeds_len: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let cost = context_object.get_execution_cost().invoke_units;
+        context_object.consume_checked(cost)?;
+
+        // StableInstruction { accounts: StableVec<AccountMeta>, data: StableVec<u8>, program_id }
+        // where StableVec is { ptr, cap, len }.
+        let accounts_addr = read_u64(memory_mapping, instruction_addr)?;
+        let accounts_len = read_u64(memory_mapping, instruction_addr + 16)?;
+        let data_addr = read_u64(memory_mapping, instruction_addr + 24)?;
+        let data_len = read_u64(memory_mapping, instruction_addr + 40)?;
+        let program_id = read_pubkey(memory_mapping, instruction_addr + 48)?;
+
+        let metas = translate_slice(
+            memory_mapping,
+            accounts_addr,
+            accounts_len.saturating_mul(RUST_ACCOUNT_META_SIZE),
+        )?;
+        let accounts = metas
+            .chunks_exact(RUST_ACCOUNT_META_SIZE as usize)
+            .map(|meta| InvocationAccount {
+                pubkey: Pubkey::try_from(&meta[..32]).unwrap(),
+                is_signer: meta[32] != 0,
+                is_writable: meta[33] != 0,
+            })
+            .collect();
+        let data = translate_slice(memory_mapping, data_addr, data_len)?.to_vec();
+
+        record_invocation(
+            context_object,
+            Invocation {
+                program_id,
+                accounts,
+                data,
+            },
+        );
+        Ok(0)
+    }
+);
+
+declare_builtin_function!(
+    /// Cross-program invocation using the C ABI (`SolInstruction`).
+    ///
+    /// The instruction is parsed and recorded, but the callee is not executed.
+    SyscallInvokeSignedC,
+    fn rust(
+        context_object: &mut DebugContextObject,
+        instruction_addr: u64,
+        _account_infos_addr: u64,
+        _account_infos_len: u64,
+        _signers_seeds_addr: u64,
+        _signers_seeds_len: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let cost = context_object.get_execution_cost().invoke_units;
+        context_object.consume_checked(cost)?;
+
+        // SolInstruction { program_id, accounts, accounts_len, data, data_len }
+        let program_id_addr = read_u64(memory_mapping, instruction_addr)?;
+        let accounts_addr = read_u64(memory_mapping, instruction_addr + 8)?;
+        let accounts_len = read_u64(memory_mapping, instruction_addr + 16)?;
+        let data_addr = read_u64(memory_mapping, instruction_addr + 24)?;
+        let data_len = read_u64(memory_mapping, instruction_addr + 32)?;
+        let program_id = read_pubkey(memory_mapping, program_id_addr)?;
+
+        let mut accounts = Vec::new();
+        for i in 0..accounts_len {
+            // SolAccountMeta { pubkey, is_writable, is_signer }
+            let meta_addr = accounts_addr.saturating_add(i.saturating_mul(C_ACCOUNT_META_SIZE));
+            let pubkey_addr = read_u64(memory_mapping, meta_addr)?;
+            let flags = translate_slice(memory_mapping, meta_addr + 8, 2)?;
+            let (is_writable, is_signer) = (flags[0] != 0, flags[1] != 0);
+            accounts.push(InvocationAccount {
+                pubkey: read_pubkey(memory_mapping, pubkey_addr)?,
+                is_signer,
+                is_writable,
+            });
+        }
+        let data = translate_slice(memory_mapping, data_addr, data_len)?.to_vec();
+
+        record_invocation(
+            context_object,
+            Invocation {
+                program_id,
+                accounts,
+                data,
+            },
         );
         Ok(0)
     }
 );
 
 // TODO: Add more syscalls
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        debugger::{DebugEvent, DebugMode},
+        test_utils::{with_debugger, with_debugger_and_input},
+    };
+    use solana_program_runtime::execution_budget::{
+        SVMTransactionExecutionBudget, SVMTransactionExecutionCost,
+    };
+    use solana_sbpf::{ebpf, memory_region::MemoryRegion, program::SBPFVersion};
+
+    /// Unaligned memory mapping so regions can be placed at any address.
+    fn test_config() -> Config {
+        Config {
+            aligned_memory_mapping: false,
+            ..Config::default()
+        }
+    }
+
+    fn new_context_object(heap_size: u64) -> DebugContextObject {
+        DebugContextObject::new(
+            SVMTransactionExecutionBudget::default(),
+            SVMTransactionExecutionCost::default(),
+            heap_size,
+        )
+    }
+
+    #[test]
+    fn test_alloc_free() {
+        let config = test_config();
+        let mut memory_mapping = MemoryMapping::new(vec![], &config, SBPFVersion::V0).unwrap();
+        let mut context_object = new_context_object(1024);
+
+        let mut previous = None;
+        for size in [1, 17, 32, 5] {
+            let addr =
+                SyscallAllocFree::rust(&mut context_object, size, 0, 0, 0, 0, &mut memory_mapping)
+                    .unwrap();
+            assert!(addr >= ebpf::MM_HEAP_START);
+            assert_eq!(addr % BPF_ALIGN_OF_U128, 0);
+            if let Some(previous) = previous {
+                assert!(addr > previous);
+            }
+            previous = Some(addr);
+        }
+
+        // Frees are no-ops.
+        let addr = previous.unwrap();
+        let result =
+            SyscallAllocFree::rust(&mut context_object, 5, addr, 0, 0, 0, &mut memory_mapping);
+        assert_eq!(result.unwrap(), 0);
+
+        // No alignment is the same as an alignment of 1.
+        let end = addr + 5;
+        assert_eq!(context_object.alloc(3, 0), Some(end));
+
+        // Allocations beyond the heap size return null.
+        let result =
+            SyscallAllocFree::rust(&mut context_object, 1024, 0, 0, 0, 0, &mut memory_mapping);
+        assert_eq!(result.unwrap(), 0);
+    }
+
+    #[test]
+    fn test_return_data() {
+        let config = test_config();
+        let mut memory = vec![0u8; 64];
+        memory[..4].copy_from_slice(&[1, 2, 3, 4]);
+        let mut memory_mapping = MemoryMapping::new(
+            vec![MemoryRegion::new_writable(&mut memory, ebpf::MM_HEAP_START)],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+        let mut context_object = new_context_object(0);
+        let program_id = Pubkey::new_unique();
+        context_object.set_program_id(program_id);
+
+        let result = SyscallSetReturnData::rust(
+            &mut context_object,
+            ebpf::MM_HEAP_START,
+            4,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            context_object.get_return_data(),
+            (&program_id, &[1, 2, 3, 4][..])
+        );
+
+        // Only the requested prefix is copied back but the full length is returned.
+        let result = SyscallGetReturnData::rust(
+            &mut context_object,
+            ebpf::MM_HEAP_START + 8,
+            2,
+            ebpf::MM_HEAP_START + 16,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 4);
+        drop(memory_mapping);
+        assert_eq!(&memory[8..12], &[1, 2, 0, 0]);
+        assert_eq!(&memory[16..48], program_id.as_ref());
+
+        let config = test_config();
+        let mut memory_mapping = MemoryMapping::new(vec![], &config, SBPFVersion::V0).unwrap();
+        let result = SyscallSetReturnData::rust(
+            &mut context_object,
+            ebpf::MM_HEAP_START,
+            MAX_RETURN_DATA + 1,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invoke_signed_rust() {
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let base = ebpf::MM_HEAP_START;
+
+        // StableInstruction at 0, one AccountMeta at 128 and data at 192.
+        let mut memory = vec![0u8; 256];
+        memory[0..8].copy_from_slice(&(base + 128).to_le_bytes());
+        memory[8..16].copy_from_slice(&1u64.to_le_bytes());
+        memory[16..24].copy_from_slice(&1u64.to_le_bytes());
+        memory[24..32].copy_from_slice(&(base + 192).to_le_bytes());
+        memory[32..40].copy_from_slice(&3u64.to_le_bytes());
+        memory[40..48].copy_from_slice(&3u64.to_le_bytes());
+        memory[48..80].copy_from_slice(program_id.as_ref());
+        memory[128..160].copy_from_slice(account.as_ref());
+        memory[160] = 1;
+        memory[161] = 0;
+        memory[192..195].copy_from_slice(&[7, 8, 9]);
+
+        let config = test_config();
+        let mut memory_mapping = MemoryMapping::new(
+            vec![MemoryRegion::new_writable(&mut memory, base)],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+        let mut context_object = new_context_object(0);
+        let result = SyscallInvokeSignedRust::rust(
+            &mut context_object,
+            base,
+            0,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            context_object.get_invocations(),
+            &[Invocation {
+                program_id,
+                accounts: vec![InvocationAccount {
+                    pubkey: account,
+                    is_signer: true,
+                    is_writable: false,
+                }],
+                data: vec![7, 8, 9],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_program_address() {
+        let program_id = Pubkey::new_unique();
+        let (expected_address, expected_bump) =
+            Pubkey::find_program_address(&[b"vault".as_ref(), &[1, 2, 3]], &program_id);
+        let base = ebpf::MM_HEAP_START;
+
+        // Seed slices at 0, seed bytes at 64, program id at 96, bump seed at 128,
+        // output address at 160.
+        let mut memory = vec![0u8; 192];
+        memory[0..8].copy_from_slice(&(base + 64).to_le_bytes());
+        memory[8..16].copy_from_slice(&5u64.to_le_bytes());
+        memory[16..24].copy_from_slice(&(base + 72).to_le_bytes());
+        memory[24..32].copy_from_slice(&3u64.to_le_bytes());
+        // The bump seed for create_program_address follows the other seeds.
+        memory[32..40].copy_from_slice(&(base + 128).to_le_bytes());
+        memory[40..48].copy_from_slice(&1u64.to_le_bytes());
+        memory[64..69].copy_from_slice(b"vault");
+        memory[72..75].copy_from_slice(&[1, 2, 3]);
+        memory[96..128].copy_from_slice(program_id.as_ref());
+
+        let config = test_config();
+        let mut memory_mapping = MemoryMapping::new(
+            vec![MemoryRegion::new_writable(&mut memory, base)],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+        let mut context_object = new_context_object(0);
+
+        let result = SyscallTryFindProgramAddress::rust(
+            &mut context_object,
+            base,
+            2,
+            base + 96,
+            base + 160,
+            base + 128,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 0);
+        let address = read_pubkey(&memory_mapping, base + 160).unwrap();
+        let bump = translate_slice(&memory_mapping, base + 128, 1).unwrap()[0];
+        assert_eq!((address, bump), (expected_address, expected_bump));
+
+        // Deriving with the found bump seed yields the same address.
+        translate_slice_mut(&memory_mapping, base + 160, 32)
+            .unwrap()
+            .fill(0);
+        let result = SyscallCreateProgramAddress::rust(
+            &mut context_object,
+            base,
+            3,
+            base + 96,
+            base + 160,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            read_pubkey(&memory_mapping, base + 160).unwrap(),
+            expected_address
+        );
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_secp256k1_recover() {
+        let secret_key = libsecp256k1::SecretKey::parse(&[7u8; 32]).unwrap();
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+        let hash = [42u8; 32];
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&libsecp256k1::Message::parse(&hash), &secret_key);
+        let base = ebpf::MM_HEAP_START;
+
+        // Hash at 0, signature at 32, recovered key at 96.
+        let mut memory = vec![0u8; 160];
+        memory[0..32].copy_from_slice(&hash);
+        memory[32..96].copy_from_slice(&signature.serialize());
+
+        let config = test_config();
+        let mut memory_mapping = MemoryMapping::new(
+            vec![MemoryRegion::new_writable(&mut memory, base)],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+        let mut context_object = new_context_object(0);
+
+        let result = SyscallSecp256k1Recover::rust(
+            &mut context_object,
+            base,
+            recovery_id.serialize() as u64,
+            base + 32,
+            base + 96,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            translate_slice(&memory_mapping, base + 96, 64).unwrap(),
+            &public_key.serialize()[1..65]
+        );
+
+        let result = SyscallSecp256k1Recover::rust(
+            &mut context_object,
+            base,
+            4,
+            base + 32,
+            base + 96,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), SECP256K1_RECOVER_INVALID_RECOVERY_ID);
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_poseidon() {
+        let base = ebpf::MM_HEAP_START;
+
+        // Input slices at 0, inputs at 32 and 64, result at 96.
+        let mut memory = vec![0u8; 128];
+        memory[0..8].copy_from_slice(&(base + 32).to_le_bytes());
+        memory[8..16].copy_from_slice(&32u64.to_le_bytes());
+        memory[16..24].copy_from_slice(&(base + 64).to_le_bytes());
+        memory[24..32].copy_from_slice(&32u64.to_le_bytes());
+        memory[32..64].fill(1);
+        memory[64..96].fill(2);
+
+        let config = test_config();
+        let mut memory_mapping = MemoryMapping::new(
+            vec![MemoryRegion::new_writable(&mut memory, base)],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+        let mut context_object = new_context_object(0);
+
+        let result = SyscallPoseidon::rust(
+            &mut context_object,
+            0,
+            0,
+            base,
+            2,
+            base + 96,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            translate_slice(&memory_mapping, base + 96, 32).unwrap(),
+            [
+                13, 84, 225, 147, 143, 138, 140, 28, 125, 235, 94, 3, 85, 242, 99, 25, 32, 123,
+                132, 254, 156, 162, 206, 27, 38, 231, 53, 200, 41, 130, 25, 144
+            ]
+        );
+
+        // Unknown parameters and too many inputs abort the program.
+        assert!(SyscallPoseidon::rust(
+            &mut context_object,
+            1,
+            0,
+            base,
+            2,
+            base + 96,
+            &mut memory_mapping,
+        )
+        .is_err());
+        assert!(SyscallPoseidon::rust(
+            &mut context_object,
+            0,
+            0,
+            base,
+            13,
+            base + 96,
+            &mut memory_mapping,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_get_sysvars() {
+        let base = ebpf::MM_HEAP_START;
+        let mut memory = vec![0u8; 128];
+        let config = test_config();
+        let mut memory_mapping = MemoryMapping::new(
+            vec![MemoryRegion::new_writable(&mut memory, base)],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+        let mut context_object = new_context_object(0);
+        let mut sysvars = Sysvars::default();
+        sysvars.clock.slot = 42;
+        sysvars.clock.unix_timestamp = -7;
+        context_object.set_sysvars(sysvars);
+
+        let result =
+            SyscallGetClockSysvar::rust(&mut context_object, base, 0, 0, 0, 0, &mut memory_mapping);
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(read_u64(&memory_mapping, base).unwrap(), 42);
+        assert_eq!(read_u64(&memory_mapping, base + 32).unwrap() as i64, -7);
+
+        let result = SyscallGetRentSysvar::rust(
+            &mut context_object,
+            base + 40,
+            0,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 0);
+        let rent = Rent::default();
+        assert_eq!(
+            read_u64(&memory_mapping, base + 40).unwrap(),
+            rent.lamports_per_byte_year
+        );
+        assert_eq!(
+            translate_slice(&memory_mapping, base + 56, 1).unwrap()[0],
+            rent.burn_percent
+        );
+
+        let result = SyscallGetEpochScheduleSysvar::rust(
+            &mut context_object,
+            base + 64,
+            0,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            read_u64(&memory_mapping, base + 64).unwrap(),
+            EpochSchedule::default().slots_per_epoch
+        );
+
+        // The sysvar must fit in writable memory.
+        assert!(SyscallGetClockSysvar::rust(
+            &mut context_object,
+            base + 100,
+            0,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_remaining_compute_units() {
+        let config = test_config();
+        let mut memory_mapping = MemoryMapping::new(Vec::new(), &config, SBPFVersion::V0).unwrap();
+        let mut context_object = new_context_object(0);
+        let limit = context_object.get_compute_budget().compute_unit_limit;
+        let syscall_cost = context_object.get_execution_cost().syscall_base_cost;
+        context_object.consume(1000);
+
+        let result = SyscallRemainingComputeUnits::rust(
+            &mut context_object,
+            0,
+            0,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), limit - 1000 - syscall_cost);
+        assert_eq!(context_object.get_remaining(), limit - 1000 - syscall_cost);
+    }
+
+    #[test]
+    fn test_abort() {
+        let source = "
+            call abort
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_debug_mode(DebugMode::Continue);
+            let DebugEvent::Error(message) = dbg.run().unwrap() else {
+                panic!("expected an error");
+            };
+            assert_eq!(
+                message,
+                "Program error at PC 0x0000000000000000: program aborted"
+            );
+        });
+    }
+
+    #[test]
+    fn test_panic() {
+        let source = "
+            mov64 r2, 6
+            mov64 r3, 12
+            mov64 r4, 5
+            call sol_panic_
+            exit";
+        let mut input = *b"lib.rs";
+        with_debugger_and_input(source, &mut input, |mut dbg| {
+            dbg.set_debug_mode(DebugMode::Continue);
+            let DebugEvent::Error(message) = dbg.run().unwrap() else {
+                panic!("expected an error");
+            };
+            assert!(message.ends_with("program panicked at lib.rs:12:5"));
+        });
+    }
+
+    #[test]
+    fn test_get_stack_height() {
+        let source = "
+            call sol_get_stack_height
+            exit";
+        with_debugger(source, |mut dbg| {
+            dbg.set_debug_mode(DebugMode::Continue);
+            assert!(matches!(
+                dbg.run().unwrap(),
+                DebugEvent::Exit(TRANSACTION_LEVEL_STACK_HEIGHT)
+            ));
+        });
+    }
+
+    #[test]
+    fn test_syscall_registry() {
+        fn custom_syscall(
+            _vm: *mut solana_sbpf::vm::EbpfVm<DebugContextObject>,
+            _arg1: u64,
+            _arg2: u64,
+            _arg3: u64,
+            _arg4: u64,
+            _arg5: u64,
+        ) {
+        }
+
+        let mut registry = SyscallRegistry::with_builtins();
+        registry
+            .register("custom_syscall", custom_syscall)
+            .register("sol_log_", custom_syscall);
+        let names: Vec<_> = registry.names().collect();
+        assert_eq!(names.iter().filter(|&&name| name == "sol_log_").count(), 1);
+        assert!(names.contains(&"custom_syscall"));
+        assert!(registry.build_loader(Config::default()).is_ok());
+    }
+}
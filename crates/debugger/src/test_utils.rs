@@ -0,0 +1,149 @@
+//! Helpers for running the debugger against small assembled programs in tests.
+
+use solana_program_runtime::execution_budget::{
+    SVMTransactionExecutionBudget, SVMTransactionExecutionCost,
+};
+use solana_sbpf::{
+    aligned_memory::AlignedMemory,
+    assembler::assemble,
+    ebpf,
+    memory_region::{MemoryMapping, MemoryRegion},
+    program::SBPFVersion,
+    vm::{Config, EbpfVm},
+};
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    build::{build_assembly, BuildConfig, BuildError, BuildResult, DEFAULT_IMAGE_BASE},
+    debugger::{init_vm, Debugger},
+    syscalls::SyscallRegistry,
+    DebugContextObject,
+};
+
+/// Heap size used by test programs
+pub const HEAP_SIZE: usize = 32 * 1024;
+/// Address of the `.bss` region of test programs, past the end of their code
+pub const BSS_START: u64 = ebpf::MM_RODATA_START + 0x10000;
+
+/// Config building `files` without the cache, with or without debug information.
+pub fn test_config(files: &[impl AsRef<Path>], debug: bool) -> BuildConfig {
+    BuildConfig {
+        source_files: files
+            .iter()
+            .map(|file| file.as_ref().to_string_lossy().to_string())
+            .collect(),
+        linker_file: None,
+        debug,
+        clang_flags: vec![],
+        ld_flags: vec![],
+        toolchain: None,
+        image_base: DEFAULT_IMAGE_BASE,
+        use_cache: false,
+    }
+}
+
+/// Whether a build failed because the Solana platform tools are not installed, in which case
+/// the tests that build programs are skipped.
+pub fn is_toolchain_missing(err: &BuildError) -> bool {
+    matches!(
+        err,
+        BuildError::SolanaConfigNotFound
+            | BuildError::ActiveReleaseDirNotFound
+            | BuildError::PlatformToolsNotFound(_)
+    )
+}
+
+/// Build with `config`, or `None` if the Solana platform tools are not installed.
+pub fn build_or_skip(config: &BuildConfig) -> Option<BuildResult> {
+    match build_assembly(config) {
+        Ok(build_result) => Some(build_result),
+        Err(err) if is_toolchain_missing(&err) => None,
+        Err(err) => panic!(
+            "Failed to build {}: {}",
+            config.source_files.join(", "),
+            err
+        ),
+    }
+}
+
+/// Assemble `source` and pass a debugger stopped at its entrypoint to `f`.
+pub fn with_debugger<R>(source: &str, f: impl FnOnce(Debugger<DebugContextObject>) -> R) -> R {
+    with_debugger_and_input(source, &mut [], f)
+}
+
+/// Like [`with_debugger`], with `input` mapped at `MM_INPUT_START`.
+pub fn with_debugger_and_input<R>(
+    source: &str,
+    input: &mut [u8],
+    f: impl FnOnce(Debugger<DebugContextObject>) -> R,
+) -> R {
+    run_with_debugger(source, input, None, f)
+}
+
+/// Like [`with_debugger`], with `bss` mapped as a writable region at [`BSS_START`].
+pub fn with_debugger_and_bss<R>(
+    source: &str,
+    bss: &mut [u8],
+    f: impl FnOnce(Debugger<DebugContextObject>) -> R,
+) -> R {
+    run_with_debugger(source, &mut [], Some(bss), f)
+}
+
+fn run_with_debugger<R>(
+    source: &str,
+    input: &mut [u8],
+    bss: Option<&mut [u8]>,
+    f: impl FnOnce(Debugger<DebugContextObject>) -> R,
+) -> R {
+    let loader = SyscallRegistry::with_builtins()
+        .build_loader(Config {
+            enabled_sbpf_versions: SBPFVersion::V0..=SBPFVersion::V0,
+            enable_symbol_and_section_labels: true,
+            enable_instruction_tracing: true,
+            ..Config::default()
+        })
+        .unwrap();
+    let executable = assemble::<DebugContextObject>(source, Arc::new(loader)).unwrap();
+
+    let mut context_object = DebugContextObject::new(
+        SVMTransactionExecutionBudget::default(),
+        SVMTransactionExecutionCost::default(),
+        HEAP_SIZE as u64,
+    );
+    let config = executable.get_config();
+    let sbpf_version = executable.get_sbpf_version();
+    let mut stack = AlignedMemory::<{ ebpf::HOST_ALIGN }>::zero_filled(config.stack_size());
+    let stack_len = stack.len();
+    let mut heap = AlignedMemory::<{ ebpf::HOST_ALIGN }>::zero_filled(HEAP_SIZE);
+    let mut regions: Vec<MemoryRegion> = vec![
+        executable.get_ro_region(),
+        MemoryRegion::new_writable_gapped(
+            stack.as_slice_mut(),
+            ebpf::MM_STACK_START,
+            if !sbpf_version.dynamic_stack_frames() && config.enable_stack_frame_gaps {
+                config.stack_frame_size as u64
+            } else {
+                0
+            },
+        ),
+        MemoryRegion::new_writable(heap.as_slice_mut(), ebpf::MM_HEAP_START),
+        MemoryRegion::new_writable(input, ebpf::MM_INPUT_START),
+    ];
+    let mut mapping_config = config.clone();
+    if let Some(bss) = bss {
+        regions.push(MemoryRegion::new_writable(bss, BSS_START));
+        mapping_config.aligned_memory_mapping = false;
+    }
+    let memory_mapping = MemoryMapping::new(regions, &mapping_config, sbpf_version).unwrap();
+
+    let mut vm = EbpfVm::new(
+        executable.get_loader().clone(),
+        sbpf_version,
+        &mut context_object,
+        memory_mapping,
+        stack_len,
+    );
+    init_vm(&mut vm, &executable);
+
+    f(Debugger::new(&mut vm, &executable))
+}
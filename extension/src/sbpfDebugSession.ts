@@ -180,6 +180,9 @@ export class SbpfDebugSession extends LoggingDebugSession {
     response.body.supportsFunctionBreakpoints = true;
     response.body.supportsDelayedStackTraceLoading = false;
 
+    // make VS Code send gotoTargets and goto requests
+    response.body.supportsGotoTargetsRequest = true;
+
     this.sendResponse(response);
 
     // since this debug adapter can accept configuration requests like 'setBreakpoint' at any time,
@@ -288,6 +291,31 @@ export class SbpfDebugSession extends LoggingDebugSession {
     }
   }
 
+  protected async setFunctionBreakPointsRequest(
+    response: DebugProtocol.SetFunctionBreakpointsResponse,
+    args: DebugProtocol.SetFunctionBreakpointsArguments
+  ): Promise<void> {
+    await this._runtimeReady;
+    await this._runtime.clearFunctionBreakpoints();
+
+    const breakpoints: DebugProtocol.Breakpoint[] = [];
+    for (const { name } of args.breakpoints) {
+      try {
+        const result = await this._runtime.setFunctionBreakpoint(name);
+        const bp: DebugProtocol.Breakpoint = new Breakpoint(true, result.line);
+        bp.instructionReference = `0x${result.pc.toString(16)}`;
+        breakpoints.push(bp);
+      } catch (e) {
+        const bp: DebugProtocol.Breakpoint = new Breakpoint(false);
+        bp.message = e instanceof Error ? e.message : String(e);
+        breakpoints.push(bp);
+      }
+    }
+
+    response.body = { breakpoints };
+    this.sendResponse(response);
+  }
+
   protected async continueRequest(
     response: DebugProtocol.ContinueResponse,
     args: DebugProtocol.ContinueArguments
@@ -309,6 +337,69 @@ export class SbpfDebugSession extends LoggingDebugSession {
     }
   }
 
+  protected async pauseRequest(
+    response: DebugProtocol.PauseResponse,
+    args: DebugProtocol.PauseArguments
+  ): Promise<void> {
+    try {
+      // The interrupted continue request sends the stopped event.
+      await this._runtime.pause();
+      this.sendResponse(response);
+    } catch (error) {
+      const errorMessage =
+        error instanceof Error ? error.message : "Unknown error during pause";
+      this.sendErrorResponse(response, {
+        id: 1009,
+        format: `Pause failed: ${errorMessage}`,
+        showUser: true,
+      });
+    }
+  }
+
+  protected async gotoTargetsRequest(
+    response: DebugProtocol.GotoTargetsResponse,
+    args: DebugProtocol.GotoTargetsArguments
+  ): Promise<void> {
+    try {
+      const targets = await this._runtime.getGotoTargets(
+        args.source.path as string,
+        args.line
+      );
+      response.body = { targets };
+      this.sendResponse(response);
+    } catch (error) {
+      const errorMessage =
+        error instanceof Error ? error.message : "Unknown error";
+      this.sendErrorResponse(response, {
+        id: 1010,
+        format: `No goto targets: ${errorMessage}`,
+        showUser: false,
+      });
+    }
+  }
+
+  protected async gotoRequest(
+    response: DebugProtocol.GotoResponse,
+    args: DebugProtocol.GotoArguments
+  ): Promise<void> {
+    try {
+      const warning = await this._runtime.goto(args.targetId);
+      this.sendResponse(response);
+      if (warning) {
+        this.sendEvent(new OutputEvent(`Warning: ${warning}\n`, "console"));
+      }
+      this.sendEvent(new StoppedEvent("goto", SbpfDebugSession.threadID));
+    } catch (error) {
+      const errorMessage =
+        error instanceof Error ? error.message : "Unknown error during goto";
+      this.sendErrorResponse(response, {
+        id: 1011,
+        format: `Goto failed: ${errorMessage}`,
+        showUser: true,
+      });
+    }
+  }
+
   protected async nextRequest(
     response: DebugProtocol.NextResponse,
     args: DebugProtocol.NextArguments
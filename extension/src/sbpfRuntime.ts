@@ -176,6 +176,13 @@ export class SbpfRuntime extends EventEmitter {
         continue;
       }
 
+      // Program logs pushed by the backend
+      const pushed = event as any;
+      if (pushed.event === "output") {
+        this.emit("output", pushed.category, pushed.text);
+        continue;
+      }
+
       // Check for error responses from the backend
       if (event.success === false) {
         const errorMsg = event.error || "Unknown error from debugger backend";
@@ -205,6 +212,18 @@ export class SbpfRuntime extends EventEmitter {
             );
           }
           this.emit("exit");
+        } else if (data.type === "watchpoint") {
+          this.emit(
+            "output",
+            "stdout",
+            `Watchpoint at ${data.address} changed: ${data.old} -> ${data.new}`
+          );
+        } else if (data.type === "limitReached") {
+          this.emit(
+            "output",
+            "stdout",
+            `Instruction limit reached after ${data.count} instructions`
+          );
         } else if (data.type === "error") {
           const errorMsg = data.message || "Runtime error occurred";
           this.emit("error", new Error(errorMsg));
@@ -247,6 +266,10 @@ export class SbpfRuntime extends EventEmitter {
   public async step(): Promise<void> {
     await this.sendCommand({ command: "step" });
   }
+  public async pause(): Promise<void> {
+    // Interrupts a pending continue, which then resolves with a "paused" result.
+    await this.sendCommand({ command: "pause" });
+  }
   public async clearBreakpoints(file: string): Promise<void> {
     if (!this._backendReady) {
       this._breakpointQueue.push(() => this.clearBreakpoints(file));
@@ -281,6 +304,38 @@ export class SbpfRuntime extends EventEmitter {
     throw new Error(resp.error || "Failed to set breakpoint");
   }
 
+  public async clearFunctionBreakpoints(): Promise<void> {
+    if (!this._backendReady) {
+      this._breakpointQueue.push(() => this.clearFunctionBreakpoints());
+      return;
+    }
+    await this.sendCommand({ command: "clearFunctionBreakpoints" });
+  }
+
+  public async setFunctionBreakpoint(
+    name: string
+  ): Promise<{ pc: number; line?: number }> {
+    if (!this._backendReady) {
+      return new Promise((resolve, reject) => {
+        this._breakpointQueue.push(async () => {
+          try {
+            resolve(await this.setFunctionBreakpoint(name));
+          } catch (e) {
+            reject(e);
+          }
+        });
+      });
+    }
+    const resp = await this.sendCommand({
+      command: "setFunctionBreakpoint",
+      args: [name],
+    });
+    if (resp.success && resp.data) {
+      return { pc: resp.data.pc, line: resp.data.line ?? undefined };
+    }
+    throw new Error(resp.error || "Failed to set function breakpoint");
+  }
+
   public async getStackFrames(): Promise<ISbpfStackFrame[]> {
     const resp = await this.sendCommand({ command: "getStackFrames" });
     if (resp.success && resp.data) {
@@ -318,6 +373,21 @@ export class SbpfRuntime extends EventEmitter {
     }
     throw new Error(resp.error || "Failed to get memory");
   }
+  public async getGotoTargets(
+    file: string,
+    line: number
+  ): Promise<{ id: number; label: string; line: number }[]> {
+    const resp = await this.sendCommand({
+      command: "gotoTargets",
+      args: [file, line],
+    });
+    return resp.data?.targets ?? [];
+  }
+  // Returns a warning when the jump can leave the stack inconsistent.
+  public async goto(pc: number): Promise<string | undefined> {
+    const resp = await this.sendCommand({ command: "setPc", args: [pc] });
+    return resp.data?.warning ?? undefined;
+  }
   public async setRegister(index: number, value: number): Promise<void> {
     await this.sendCommand({ command: "setRegister", args: [index, value] });
   }
@@ -0,0 +1,11 @@
+.globl entrypoint
+
+entrypoint:
+  lddw r1, counter
+  ldxdw r0, [r1 + 0]
+  add64 r0, 1
+  stxdw [r1 + 0], r0
+  exit
+
+.bss
+  counter: .zero 8